@@ -1,5 +1,5 @@
 use conv::ValueInto;
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
 
 use std::{fs::File, io::Read};
 
@@ -58,5 +58,22 @@ fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+/// Fill throughput in bytes written per second, to compare with the memory
+/// bandwidth of the machine.
+fn fill_benchmark(c: &mut Criterion) {
+    let (width, height) = (3840, 2160);
+    let mut frame = Nv12Buffer::filled(width, height, BLACK);
+    let mut group = c.benchmark_group("fill");
+    group.throughput(Throughput::Bytes(frame.as_bytes().len() as u64));
+    group.bench_function("fill_4k", |b| b.iter(|| frame.fill(WHITE)));
+    // A label background covering a quarter of the frame.
+    let (w, h) = (width / 2, height / 2);
+    group.throughput(Throughput::Bytes(w as u64 * h as u64 * 3 / 2));
+    group.bench_function("fill_rect_4k_quarter", |b| {
+        b.iter(|| frame.fill_rect(101, 99, w, h, BLACK))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark, fill_benchmark);
 criterion_main!(benches);
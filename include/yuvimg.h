@@ -0,0 +1,80 @@
+#ifndef YUVIMG_H
+#define YUVIMG_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A parsed font, created with [`yuv_font_new`].
+ */
+typedef struct YuvFont YuvFont;
+
+/**
+ * An NV12 frame in caller memory: `height` rows of `y_stride` bytes of
+ * luma, directly followed by `height.div_ceil(2)` rows of `uv_stride` bytes
+ * of interleaved chroma. `uv_stride` must be at least `width.div_ceil(2) * 2`
+ * so that odd widths keep their last U, V pair.
+ */
+typedef struct YuvFrame {
+  uint8_t *data;
+  uint32_t width;
+  uint32_t height;
+  uint32_t y_stride;
+  uint32_t uv_stride;
+} YuvFrame;
+
+/**
+ * Draws a one pixel wide rectangle outline.
+ */
+int yuv_draw_rect(struct YuvFrame frame,
+                  int32_t x,
+                  int32_t y,
+                  uint32_t width,
+                  uint32_t height,
+                  uint8_t color_y,
+                  uint8_t color_u,
+                  uint8_t color_v);
+
+/**
+ * Parses a TrueType or OpenType font from `len` bytes at `data`. The bytes
+ * are copied. Returns null if the font cannot be parsed.
+ */
+struct YuvFont *yuv_font_new(const uint8_t *data, uintptr_t len);
+
+/**
+ * Frees a font returned by [`yuv_font_new`]. Null is ignored.
+ */
+void yuv_font_free(struct YuvFont *font);
+
+/**
+ * Draws the NUL-terminated UTF-8 string `text` with its top-left corner at
+ * `(x, y)` and a glyph height of `scale` pixels.
+ */
+int yuv_draw_text_utf8(struct YuvFrame frame,
+                       const struct YuvFont *font,
+                       int32_t x,
+                       int32_t y,
+                       float scale,
+                       const char *text,
+                       uint8_t color_y,
+                       uint8_t color_u,
+                       uint8_t color_v);
+
+/**
+ * Alpha-blends a straight (non-premultiplied) RGBA image of
+ * `width`x`height` pixels with rows of `stride` bytes onto `frame`, with
+ * its top-left corner at `(x, y)`.
+ */
+int yuv_overlay_rgba(struct YuvFrame frame,
+                     const uint8_t *rgba,
+                     uint32_t width,
+                     uint32_t height,
+                     uint32_t stride,
+                     int32_t x,
+                     int32_t y);
+
+#endif /* YUVIMG_H */
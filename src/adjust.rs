@@ -0,0 +1,535 @@
+//! In-place tone and color adjustments of NV12 frames.
+//!
+//! Brightness and contrast live in the Y plane and color in the UV plane, so
+//! each adjustment only rewrites the plane it concerns, without any RGB
+//! conversion.
+
+use core::ops::DerefMut;
+
+use alloc::vec::Vec;
+
+use crate::stats::histogram_y;
+use crate::{NV12Image, Region};
+
+/// Maps every luma sample through `lut`, for tone curves such as
+/// [`gamma_lut`] and [`s_curve_lut`].
+pub fn apply_y_lut_mut<T: DerefMut<Target = [u8]>>(img: &mut NV12Image<T>, lut: &[u8; 256]) {
+    for y in 0..img.height {
+        for luma in img.y_row_mut(y) {
+            *luma = lut[*luma as usize];
+        }
+    }
+}
+
+/// A lookup table applying gamma correction, `255 * (v / 255) ^ (1 / gamma)`.
+/// Gamma above 1 brightens the midtones, below 1 darkens them.
+#[cfg(feature = "std")]
+pub fn gamma_lut(gamma: f32) -> [u8; 256] {
+    let mut lut = [0; 256];
+    for (value, out) in lut.iter_mut().enumerate() {
+        let corrected = (value as f32 / 255.).powf(1. / gamma) * 255.;
+        *out = (corrected + 0.5).clamp(0., 255.) as u8;
+    }
+    lut
+}
+
+/// A lookup table for an S-shaped tone curve, which raises midtone contrast
+/// while compressing shadows and highlights. `strength` blends from the
+/// identity at 0 to a smoothstep curve at 1.
+pub fn s_curve_lut(strength: f32) -> [u8; 256] {
+    let mut lut = [0; 256];
+    for (value, out) in lut.iter_mut().enumerate() {
+        let t = value as f32 / 255.;
+        let curve = t * t * (3. - 2. * t);
+        *out = ((t + (curve - t) * strength) * 255. + 0.5).clamp(0., 255.) as u8;
+    }
+    lut
+}
+
+/// Calls `f` with every `[U, V]` pair of the chroma plane.
+fn for_each_chroma_mut<T, F>(img: &mut NV12Image<T>, mut f: F)
+where
+    T: DerefMut<Target = [u8]>,
+    F: FnMut(&mut [u8]),
+{
+    let (row_len, rows) = (img.width.div_ceil(2) as usize * 2, img.height.div_ceil(2));
+    let uv_stride = img.uv_stride as usize;
+    let (_, uv) = img.split_planes_mut();
+    for row in 0..rows as usize {
+        uv[row * uv_stride..][..row_len]
+            .chunks_exact_mut(2)
+            .for_each(&mut f);
+    }
+}
+
+/// The lookup table scaling chroma samples' distance from 128 by `gain` and
+/// adding `shift`.
+fn chroma_lut(gain: f32, shift: f32) -> [u8; 256] {
+    let mut lut = [0; 256];
+    for (value, out) in lut.iter_mut().enumerate() {
+        let corrected = (value as f32 - 128.) * gain + 128. + shift;
+        *out = (corrected + 0.5).clamp(0., 255.) as u8;
+    }
+    lut
+}
+
+/// The lookup table that equalizes `histogram` of `total` samples, mapping
+/// its cumulative distribution linearly onto `0..=255`.
+fn equalize_lut(histogram: &[u32; 256], total: u64) -> [u8; 256] {
+    let mut lut = [0; 256];
+    let mut cdf = 0u64;
+    for (value, &count) in lut.iter_mut().zip(histogram) {
+        cdf += count as u64;
+        *value = ((cdf * 255 + total / 2) / total) as u8;
+    }
+    lut
+}
+
+/// Equalizes the luma histogram of the whole frame. Frames of a single luma
+/// value are left unchanged.
+pub fn equalize_y_mut<T: DerefMut<Target = [u8]>>(img: &mut NV12Image<T>) {
+    let histogram = histogram_y(img);
+    let total = img.width as u64 * img.height as u64;
+    let min = histogram
+        .iter()
+        .find(|&&count| count != 0)
+        .copied()
+        .unwrap_or(0) as u64;
+    if min == total {
+        return;
+    }
+    let mut lut = [0; 256];
+    let mut cdf = 0u64;
+    for (value, &count) in lut.iter_mut().zip(&histogram) {
+        cdf += count as u64;
+        *value = ((cdf.saturating_sub(min) * 255 + (total - min) / 2) / (total - min)) as u8;
+    }
+    apply_y_lut_mut(img, &lut);
+}
+
+/// Linearly stretches the luma so the `low_pct` percentile maps to 0 and the
+/// `high_pct` percentile to 255, clamping the samples beyond them. Typical
+/// values are 1 and 99. Frames whose percentiles coincide are left
+/// unchanged.
+pub fn auto_levels_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    low_pct: f32,
+    high_pct: f32,
+) {
+    let histogram = histogram_y(img);
+    let total = img.width as u64 * img.height as u64;
+    let percentile = |pct: f32| {
+        let rank = ((pct.clamp(0., 100.) / 100.) as f64 * total as f64) as u64;
+        let mut cdf = 0u64;
+        histogram
+            .iter()
+            .position(|&count| {
+                cdf += count as u64;
+                cdf > rank.min(total.saturating_sub(1))
+            })
+            .unwrap_or(0) as i32
+    };
+    let (low, high) = (percentile(low_pct), percentile(high_pct));
+    if high <= low {
+        return;
+    }
+    let mut lut = [0; 256];
+    for (value, out) in lut.iter_mut().enumerate() {
+        let stretched = (value as i32 - low) * 255 + (high - low) / 2;
+        *out = (stretched / (high - low)).clamp(0, 255) as u8;
+    }
+    apply_y_lut_mut(img, &lut);
+}
+
+/// Scales the luma around mid-gray by `contrast` and then adds `brightness`,
+/// saturating at 0 and 255. `(0, 1.)` leaves the frame unchanged.
+pub fn adjust_brightness_contrast_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    brightness: i16,
+    contrast: f32,
+) {
+    let mut lut = [0; 256];
+    for (value, out) in lut.iter_mut().enumerate() {
+        let scaled = (value as f32 - 128.) * contrast + 128. + brightness as f32;
+        *out = (scaled + 0.5).clamp(0., 255.) as u8;
+    }
+    apply_y_lut_mut(img, &lut);
+}
+
+/// Scales the distance of U and V from neutral 128 by `factor`, clamping at
+/// 0 and 255. 0 gives grayscale, values above 1 stronger colors.
+pub fn adjust_saturation_mut<T: DerefMut<Target = [u8]>>(img: &mut NV12Image<T>, factor: f32) {
+    let lut = chroma_lut(factor, 0.);
+    for_each_chroma_mut(img, |uv| {
+        uv[0] = lut[uv[0] as usize];
+        uv[1] = lut[uv[1] as usize];
+    });
+}
+
+/// Darkens `rect`, e.g. as a backdrop that keeps labels readable over
+/// bright video: luma is scaled towards black by `factor` (0 black, 1
+/// unchanged) and chroma towards gray by `saturation` in every 2x2 block the
+/// rectangle touches. Black is the luma offset of the image's color matrix.
+/// `rect` is clipped to the image.
+pub fn dim_region_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    rect: Region,
+    factor: f32,
+    saturation: f32,
+) {
+    let rect = rect.clip(img.width, img.height);
+    if rect.is_empty() {
+        return;
+    }
+    let black = img.color_matrix.y_offset;
+    let mut luma_lut = [0; 256];
+    for (value, out) in luma_lut.iter_mut().enumerate() {
+        let dimmed = (value as f32 - black) * factor + black;
+        *out = (dimmed + 0.5).clamp(0., 255.) as u8;
+    }
+    let chroma_lut = chroma_lut(saturation, 0.);
+    let (x0, x1) = (rect.x as usize, (rect.x + rect.width) as usize);
+    for y in rect.y..rect.y + rect.height {
+        for luma in &mut img.y_row_mut(y)[x0..x1] {
+            *luma = luma_lut[*luma as usize];
+        }
+    }
+    let uv_stride = img.uv_stride as usize;
+    let (_, uv) = img.split_planes_mut();
+    let (bx0, bx1) = (x0 / 2 * 2, x1.div_ceil(2) * 2);
+    for row in rect.y as usize / 2..(rect.y + rect.height).div_ceil(2) as usize {
+        for sample in &mut uv[row * uv_stride + bx0..row * uv_stride + bx1] {
+            *sample = chroma_lut[*sample as usize];
+        }
+    }
+}
+
+/// Rotates the hue by `degrees`, as a rotation of each `(U - 128, V - 128)`
+/// chroma vector, clamping the result at 0 and 255. Positive angles turn
+/// from blue (+U) towards red (+V).
+#[cfg(feature = "std")]
+pub fn rotate_hue_mut<T: DerefMut<Target = [u8]>>(img: &mut NV12Image<T>, degrees: f32) {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (sin, cos) = ((sin * 4096.).round() as i32, (cos * 4096.).round() as i32);
+    for_each_chroma_mut(img, |uv| {
+        let (u, v) = (uv[0] as i32 - 128, uv[1] as i32 - 128);
+        let rotated_u = (u * cos - v * sin + 2048) >> 12;
+        let rotated_v = (u * sin + v * cos + 2048) >> 12;
+        uv[0] = (rotated_u + 128).clamp(0, 255) as u8;
+        uv[1] = (rotated_v + 128).clamp(0, 255) as u8;
+    });
+}
+
+/// Makes the frame grayscale by setting every chroma sample to neutral 128.
+/// Rows are filled with `memset`, so this is much cheaper than
+/// [`adjust_saturation_mut`] with a factor of 0.
+pub fn desaturate_mut<T: DerefMut<Target = [u8]>>(img: &mut NV12Image<T>) {
+    let (row_len, rows) = (img.width.div_ceil(2) as usize * 2, img.height.div_ceil(2));
+    let uv_stride = img.uv_stride as usize;
+    let (_, uv) = img.split_planes_mut();
+    for row in 0..rows as usize {
+        uv[row * uv_stride..][..row_len].fill(0x80);
+    }
+}
+
+/// A chroma correction for [`white_balance_mut`]: each of U and V has its
+/// distance from 128 scaled by its gain and is then shifted by its offset.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WhiteBalance {
+    pub u_gain: f32,
+    pub v_gain: f32,
+    pub u_shift: f32,
+    pub v_shift: f32,
+}
+
+impl WhiteBalance {
+    /// The correction that leaves the chroma unchanged.
+    pub const NEUTRAL: Self = Self {
+        u_gain: 1.,
+        v_gain: 1.,
+        u_shift: 0.,
+        v_shift: 0.,
+    };
+
+    /// Raw chroma gains, without shifts.
+    pub fn gains(u_gain: f32, v_gain: f32) -> Self {
+        Self {
+            u_gain,
+            v_gain,
+            ..Self::NEUTRAL
+        }
+    }
+
+    /// Raw chroma shifts, without gains. A positive `u_shift` adds blue, a
+    /// positive `v_shift` adds red.
+    pub fn shifts(u_shift: f32, v_shift: f32) -> Self {
+        Self {
+            u_shift,
+            v_shift,
+            ..Self::NEUTRAL
+        }
+    }
+
+    /// The shifts that turn white lit at `kelvin` into neutral gray under
+    /// D65 (6500 K), e.g. 3200 for tungsten light. The light's color comes
+    /// from Tanner Helland's fit of the blackbody curve, converted with BT.601
+    /// coefficients.
+    #[cfg(feature = "std")]
+    pub fn temperature(kelvin: f32) -> Self {
+        let [u, v] = blackbody_chroma(kelvin);
+        let [u_d65, v_d65] = blackbody_chroma(6500.);
+        Self::shifts(u_d65 - u, v_d65 - v)
+    }
+}
+
+/// The chroma offsets from 128 of the color of a blackbody at `kelvin`.
+#[cfg(feature = "std")]
+fn blackbody_chroma(kelvin: f32) -> [f32; 2] {
+    let t = kelvin.clamp(1000., 40000.) / 100.;
+    let r = if t <= 66. {
+        255.
+    } else {
+        329.69873 * (t - 60.).powf(-0.13320476)
+    };
+    let g = if t <= 66. {
+        99.4708 * t.ln() - 161.11957
+    } else {
+        288.12216 * (t - 60.).powf(-0.07551485)
+    };
+    let b = if t >= 66. {
+        255.
+    } else if t <= 19. {
+        0.
+    } else {
+        138.51773 * (t - 10.).ln() - 305.0448
+    };
+    let [r, g, b] = [r, g, b].map(|c| c.clamp(0., 255.));
+    [
+        -0.1687 * r - 0.3313 * g + 0.5 * b,
+        0.5 * r - 0.4187 * g - 0.0813 * b,
+    ]
+}
+
+/// Corrects a color cast by applying `balance` to every chroma sample,
+/// clamping at 0 and 255. Luma is unchanged.
+pub fn white_balance_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    balance: WhiteBalance,
+) {
+    let u_lut = chroma_lut(balance.u_gain, balance.u_shift);
+    let v_lut = chroma_lut(balance.v_gain, balance.v_shift);
+    for_each_chroma_mut(img, |uv| {
+        uv[0] = u_lut[uv[0] as usize];
+        uv[1] = v_lut[uv[1] as usize];
+    });
+}
+
+/// Turns the frame into its photographic negative, like [`YUV::negate`] on
+/// every pixel.
+///
+/// [`YUV::negate`]: crate::YUV::negate
+pub fn negate_frame_mut<T: DerefMut<Target = [u8]>>(img: &mut NV12Image<T>) {
+    let mut y_lut = [0; 256];
+    for (value, out) in y_lut.iter_mut().enumerate() {
+        *out = 255 - value as u8;
+    }
+    apply_y_lut_mut(img, &y_lut);
+    let uv_lut = chroma_lut(-1., 0.);
+    for_each_chroma_mut(img, |uv| {
+        uv[0] = uv_lut[uv[0] as usize];
+        uv[1] = uv_lut[uv[1] as usize];
+    });
+}
+
+/// Finds the tiles around half-pixel position `pos` (`2 * x + 1`) along an
+/// axis of `tiles` tiles of `tile_size`, and the weight of the second one.
+fn tile_neighbours(pos: u32, tile_size: u32, tiles: u32) -> (usize, usize, f32) {
+    let offset = match pos.checked_sub(tile_size) {
+        Some(offset) => offset,
+        None => return (0, 0, 0.),
+    };
+    let first = (offset / (2 * tile_size)).min(tiles - 1);
+    let second = (first + 1).min(tiles - 1);
+    let weight = (offset - first * 2 * tile_size) as f32 / (2 * tile_size) as f32;
+    (first as usize, second as usize, weight.min(1.))
+}
+
+/// Contrast limited adaptive histogram equalization (CLAHE) of the luma.
+///
+/// Each `tile_size`x`tile_size` tile is equalized on its own, with histogram
+/// bins clipped at `clip_limit` times their average count and the excess
+/// spread over all bins, which keeps noise in flat areas from being
+/// amplified. Pixels blend the mappings of the four nearest tiles so tile
+/// edges do not show. A `clip_limit` of 2 to 4 suits most footage.
+pub fn clahe_y_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    tile_size: u32,
+    clip_limit: f32,
+) {
+    assert!(tile_size > 0, "tile size must not be zero");
+    let (width, height) = (img.width, img.height);
+    let (columns, rows) = (width.div_ceil(tile_size), height.div_ceil(tile_size));
+    let mut luts = Vec::with_capacity((columns * rows) as usize);
+    for ty in 0..rows {
+        for tx in 0..columns {
+            let (x0, y0) = (tx * tile_size, ty * tile_size);
+            let tile_width = tile_size.min(width - x0);
+            let tile_height = tile_size.min(height - y0);
+            let mut histogram = [0u32; 256];
+            for y in y0..y0 + tile_height {
+                let row = &img.y_row(y)[x0 as usize..(x0 + tile_width) as usize];
+                for &luma in row {
+                    histogram[luma as usize] += 1;
+                }
+            }
+            let total = tile_width * tile_height;
+            let limit = ((clip_limit * total as f32 / 256.) as u32).max(1);
+            let mut excess = 0;
+            for count in histogram.iter_mut() {
+                excess += count.saturating_sub(limit);
+                *count = (*count).min(limit);
+            }
+            for (i, count) in histogram.iter_mut().enumerate() {
+                *count += excess / 256 + u32::from((i as u32) < excess % 256);
+            }
+            luts.push(equalize_lut(&histogram, total as u64));
+        }
+    }
+    let columns_usize = columns as usize;
+    for y in 0..height {
+        let (ty0, ty1, wy) = tile_neighbours(2 * y + 1, tile_size, rows);
+        let (top, bottom) = (ty0 * columns_usize, ty1 * columns_usize);
+        for (x, luma) in img.y_row_mut(y).iter_mut().enumerate() {
+            let (tx0, tx1, wx) = tile_neighbours(2 * x as u32 + 1, tile_size, columns);
+            let v = *luma as usize;
+            let lerp = |a: u8, b: u8, w: f32| a as f32 + (b as f32 - a as f32) * w;
+            let upper = lerp(luts[top + tx0][v], luts[top + tx1][v], wx);
+            let lower = lerp(luts[bottom + tx0][v], luts[bottom + tx1][v], wx);
+            *luma = (upper + (lower - upper) * wy + 0.5) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equalize_stretches_luma_only() {
+        let mut data: Vec<u8> = (0..16).map(|i| 100 + i).collect();
+        data.extend([7; 8]);
+        let mut img = NV12Image::from(data, 4, 4);
+        equalize_y_mut(&mut img);
+        assert_eq!((img.y_row(0)[0], img.y_row(3)[3]), (0, 255));
+        assert!(img.as_bytes()[16..].iter().all(|&c| c == 7));
+
+        let mut dark: Vec<u8> = (0..32 * 32u32).map(|i| (10 + i % 32 / 4) as u8).collect();
+        dark.extend([128; 32 * 16]);
+        let mut img = NV12Image::from(dark, 32, 32);
+        clahe_y_mut(&mut img, 16, 40.);
+        let row = img.y_row(0);
+        assert!(row[31] - row[0] > 100, "{:?}", row);
+    }
+
+    #[test]
+    fn dims_only_the_region() {
+        let mut img = crate::Nv12Buffer::filled(8, 4, crate::YUV([216, 160, 96]));
+        img.color_matrix.y_offset = 16.;
+        dim_region_mut(&mut img, Region::new(3, 0, 10, 2), 0.5, 0.);
+        assert_eq!(img.y_row(0), [216, 216, 216, 116, 116, 116, 116, 116]);
+        assert_eq!(img.y_row(2)[3], 216);
+        assert_eq!(img.uv_row(0), [160, 96, 128, 128, 128, 128, 128, 128]);
+        assert_eq!(img.uv_row(1)[2..4], [160, 96]);
+    }
+
+    #[test]
+    fn brightness_contrast_saturates() {
+        let mut data = vec![0, 100, 128, 250];
+        data.extend([60, 70, 60, 70]);
+        let mut img = NV12Image::from(data, 4, 1);
+        adjust_brightness_contrast_mut(&mut img, 10, 2.);
+        assert_eq!(img.as_bytes(), &[0, 82, 138, 255, 60, 70, 60, 70]);
+    }
+
+    #[test]
+    fn saturation_scales_chroma() {
+        let mut img = NV12Image::from(vec![50, 50, 50, 50, 100, 200], 2, 2);
+        adjust_saturation_mut(&mut img, 2.);
+        assert_eq!(img.as_bytes(), &[50, 50, 50, 50, 72, 255]);
+        adjust_saturation_mut(&mut img, 0.);
+        assert_eq!(&img.as_bytes()[4..], &[128, 128]);
+        let mut img = NV12Image::from_strided(vec![9; 14], 3, 2, 4, 6);
+        desaturate_mut(&mut img);
+        assert_eq!(&img.as_bytes()[8..], &[128, 128, 128, 128, 9, 9]);
+    }
+
+    #[test]
+    fn white_balance() {
+        let mut img = NV12Image::from(vec![50, 50, 50, 50, 140, 100], 2, 2);
+        white_balance_mut(&mut img, WhiteBalance::shifts(-12., 28.));
+        assert_eq!(&img.as_bytes()[4..], &[128, 128]);
+        white_balance_mut(&mut img, WhiteBalance::NEUTRAL);
+        assert_eq!(&img.as_bytes()[4..], &[128, 128]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn temperature_corrects_warm_light() {
+        let tungsten = WhiteBalance::temperature(3200.);
+        assert!(
+            tungsten.u_shift > 0. && tungsten.v_shift < 0.,
+            "{:?}",
+            tungsten
+        );
+        let d65 = WhiteBalance::temperature(6500.);
+        assert_eq!((d65.u_shift, d65.v_shift), (0., 0.));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hue_rotation() {
+        let mut img = NV12Image::from(vec![50, 50, 50, 50, 178, 128], 2, 2);
+        rotate_hue_mut(&mut img, 90.);
+        assert_eq!(&img.as_bytes()[4..], &[128, 178]);
+        rotate_hue_mut(&mut img, 180.);
+        assert_eq!(&img.as_bytes()[4..], &[128, 78]);
+    }
+
+    #[test]
+    fn tone_curves() {
+        let curve = s_curve_lut(1.);
+        assert_eq!((curve[0], curve[128], curve[255]), (0, 128, 255));
+        assert!(curve[64] < 64 && curve[192] > 192);
+        assert_eq!(s_curve_lut(0.)[77], 77);
+        let mut img = NV12Image::from(vec![64, 64, 64, 64, 90, 90], 2, 2);
+        apply_y_lut_mut(&mut img, &curve);
+        assert_eq!(img.y_row(1), &[curve[64]; 2]);
+        assert_eq!(&img.as_bytes()[4..], &[90, 90]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn gamma() {
+        let lut = gamma_lut(2.);
+        assert_eq!((lut[0], lut[64], lut[255]), (0, 128, 255));
+    }
+
+    #[test]
+    fn negative() {
+        let mut img = NV12Image::from(vec![0, 10, 200, 255, 0, 200], 2, 2);
+        negate_frame_mut(&mut img);
+        assert_eq!(img.as_bytes(), &[255, 245, 55, 0, 255, 56]);
+        assert_eq!(crate::RED.negate().0, [0xb3, 0xab, 0x01]);
+    }
+
+    #[test]
+    fn auto_levels_clips_outliers() {
+        let mut data = vec![100u8; 100];
+        data[..10].copy_from_slice(&[0, 90, 90, 90, 90, 110, 110, 110, 110, 255]);
+        data.extend([128; 50]);
+        let mut img = NV12Image::from(data, 10, 10);
+        auto_levels_mut(&mut img, 2., 98.);
+        assert_eq!(img.y_row(0), &[0, 0, 0, 0, 0, 255, 255, 255, 255, 255]);
+        assert_eq!(img.y_row(1)[0], 128);
+    }
+}
@@ -0,0 +1,395 @@
+//! Drawing lists of annotations, optionally split across threads.
+//!
+//! [`draw_annotations_parallel_mut`] cuts the frame into horizontal bands
+//! with [`NV12Image::split_rows_mut`], assigns each annotation to the bands
+//! it overlaps and draws every band on its own thread. Text is drawn as a
+//! [`CoverageMask`] rendered beforehand, e.g. with
+//! `TextRenderer::render_mask`, so no font state is shared between threads.
+//!
+//! [`OverlayCanvas`] records annotations for streaming overlays and applies
+//! the last complete set to each frame, so a frame never carries half of an
+//! update.
+
+use std::ops::DerefMut;
+use std::thread;
+
+use crate::overlay::blend_region_mut;
+use crate::{NV12Image, YUV};
+
+/// An 8-bit coverage (alpha) bitmap.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoverageMask {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height` values, row by row.
+    pub coverage: Vec<u8>,
+}
+
+impl CoverageMask {
+    /// A fully transparent mask.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            coverage: vec![0; width as usize * height as usize],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Annotation<'a> {
+    /// A one pixel wide rectangle outline.
+    Rect {
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        color: YUV,
+    },
+    /// A solid rectangle, see [`NV12Image::fill_rect`].
+    FilledRect {
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        color: YUV,
+    },
+    /// `color` blended through `mask`, e.g. a rendered label.
+    Mask {
+        x: i64,
+        y: i64,
+        mask: &'a CoverageMask,
+        color: YUV,
+    },
+}
+
+impl Annotation<'_> {
+    /// First row and one past the last row covered.
+    fn rows(&self) -> (i64, i64) {
+        match *self {
+            Annotation::Rect { y, height, .. } | Annotation::FilledRect { y, height, .. } => {
+                (y, y + height as i64)
+            }
+            Annotation::Mask { y, mask, .. } => (y, y + mask.height as i64),
+        }
+    }
+
+    /// Draws the annotation shifted up by `top` rows.
+    fn draw<T: DerefMut<Target = [u8]>>(&self, img: &mut NV12Image<T>, top: i64) {
+        match *self {
+            Annotation::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => img.draw_rect_mut(x, y - top, width, height, color, 1),
+            Annotation::FilledRect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => img.fill_rect(x, y - top, width, height, color),
+            Annotation::Mask { x, y, mask, color } => {
+                blend_region_mut(img, x, y - top, mask.width, mask.height, |dx, dy| {
+                    let i = (dy * mask.width + dx) as usize;
+                    (color, mask.coverage[i] as u32)
+                })
+            }
+        }
+    }
+}
+
+/// Draws `annotations` in order.
+pub fn draw_annotations_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    annotations: &[Annotation],
+) {
+    for annotation in annotations {
+        annotation.draw(img, 0);
+    }
+}
+
+/// Draws `annotations` like [`draw_annotations_mut`] on up to `threads`
+/// threads, one per band of rows. Falls back to drawing on the calling
+/// thread if `threads` is at most 1 or there is at most one annotation.
+///
+/// Overlapping annotations are drawn in list order within every band, so
+/// the result is the same as the serial path.
+pub fn draw_annotations_parallel_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    annotations: &[Annotation],
+    threads: usize,
+) {
+    if threads <= 1 || annotations.len() <= 1 || img.height == 0 {
+        return draw_annotations_mut(img, annotations);
+    }
+    let rows = img.height.div_ceil(threads as u32).div_ceil(2) * 2;
+    let mut bands: Vec<_> = img
+        .split_rows_mut(rows)
+        .into_iter()
+        .enumerate()
+        .map(|(i, band)| (i as i64 * rows as i64, band, Vec::new()))
+        .collect();
+    for annotation in annotations {
+        let (first, last) = annotation.rows();
+        for (top, band, assigned) in &mut bands {
+            if first < *top + band.height as i64 && last > *top {
+                assigned.push(annotation);
+            }
+        }
+    }
+    thread::scope(|scope| {
+        for (top, mut band, assigned) in bands {
+            if assigned.is_empty() {
+                continue;
+            }
+            scope.spawn(move || {
+                for annotation in assigned {
+                    annotation.draw(&mut band, top);
+                }
+            });
+        }
+    });
+}
+
+/// An owned [`Annotation`], with masks kept in the [`Layer`].
+#[derive(Clone, Debug)]
+enum Op {
+    Rect {
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        color: YUV,
+    },
+    FilledRect {
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        color: YUV,
+    },
+    Mask {
+        x: i64,
+        y: i64,
+        mask: usize,
+        color: YUV,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+struct Layer {
+    ops: Vec<Op>,
+    masks: Vec<CoverageMask>,
+}
+
+impl Layer {
+    fn clear(&mut self) {
+        self.ops.clear();
+        self.masks.clear();
+    }
+
+    fn annotations(&self) -> Vec<Annotation<'_>> {
+        self.ops
+            .iter()
+            .map(|op| match *op {
+                Op::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => Annotation::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                },
+                Op::FilledRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => Annotation::FilledRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                },
+                Op::Mask { x, y, mask, color } => Annotation::Mask {
+                    x,
+                    y,
+                    mask: &self.masks[mask],
+                    color,
+                },
+            })
+            .collect()
+    }
+}
+
+/// A double-buffered list of annotations for overlays updated while frames
+/// stream past.
+///
+/// Drawing calls are recorded into a back layer and only
+/// [`present`](Self::present) makes them the front layer, which
+/// [`commit`](Self::commit) draws onto a frame in one pass. A frame committed
+/// while the next update is being drawn gets the previous update in full.
+///
+/// ```
+/// use yuvimg::annotate::OverlayCanvas;
+/// use yuvimg::{Nv12Buffer, BLACK, RED};
+///
+/// let mut canvas = OverlayCanvas::new();
+/// canvas.fill_rect(10, 10, 40, 20, RED);
+/// canvas.present();
+///
+/// let mut frame = Nv12Buffer::filled(64, 48, BLACK);
+/// canvas.commit(&mut frame);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct OverlayCanvas {
+    back: Layer,
+    front: Layer,
+}
+
+impl OverlayCanvas {
+    /// A canvas with nothing to commit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records [`Annotation::Rect`].
+    pub fn draw_rect(&mut self, x: i64, y: i64, width: u32, height: u32, color: YUV) {
+        self.back.ops.push(Op::Rect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    /// Records [`Annotation::FilledRect`].
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: u32, height: u32, color: YUV) {
+        self.back.ops.push(Op::FilledRect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    /// Records [`Annotation::Mask`], taking ownership of `mask`.
+    pub fn draw_mask(&mut self, x: i64, y: i64, mask: CoverageMask, color: YUV) {
+        let index = self.back.masks.len();
+        self.back.masks.push(mask);
+        self.back.ops.push(Op::Mask {
+            x,
+            y,
+            mask: index,
+            color,
+        });
+    }
+
+    /// Number of operations recorded since the last
+    /// [`present`](Self::present).
+    pub fn pending(&self) -> usize {
+        self.back.ops.len()
+    }
+
+    /// Makes the recorded operations the ones committed from now on and
+    /// starts an empty back layer.
+    pub fn present(&mut self) {
+        std::mem::swap(&mut self.back, &mut self.front);
+        self.back.clear();
+    }
+
+    /// Forgets the operations recorded since the last
+    /// [`present`](Self::present).
+    pub fn discard(&mut self) {
+        self.back.clear();
+    }
+
+    /// Draws the presented operations onto `img`, in the order they were
+    /// recorded.
+    pub fn commit<T: DerefMut<Target = [u8]>>(&self, img: &mut NV12Image<T>) {
+        draw_annotations_mut(img, &self.front.annotations());
+    }
+
+    /// Like [`commit`](Self::commit), on up to `threads` threads, see
+    /// [`draw_annotations_parallel_mut`].
+    pub fn commit_parallel<T: DerefMut<Target = [u8]>>(
+        &self,
+        img: &mut NV12Image<T>,
+        threads: usize,
+    ) {
+        draw_annotations_parallel_mut(img, &self.front.annotations(), threads);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLACK, RED, WHITE};
+
+    #[test]
+    fn parallel_matches_serial() {
+        let mut mask = CoverageMask::new(5, 7);
+        mask.coverage.iter_mut().step_by(3).for_each(|c| *c = 200);
+        let annotations = [
+            Annotation::FilledRect {
+                x: 2,
+                y: 3,
+                width: 20,
+                height: 30,
+                color: RED,
+            },
+            Annotation::Rect {
+                x: -4,
+                y: 5,
+                width: 30,
+                height: 19,
+                color: WHITE,
+            },
+            Annotation::Mask {
+                x: 9,
+                y: 6,
+                mask: &mask,
+                color: WHITE,
+            },
+        ];
+        let mut serial = NV12Image::from(vec![0; 32 * 36 * 3 / 2], 32, 36);
+        serial.fill(BLACK);
+        let mut parallel = NV12Image::from(serial.as_bytes().to_vec(), 32, 36);
+        draw_annotations_mut(&mut serial, &annotations);
+        draw_annotations_parallel_mut(&mut parallel, &annotations, 5);
+        assert_eq!(serial.as_bytes(), parallel.as_bytes());
+    }
+
+    #[test]
+    fn canvas_commits_presented_updates_only() {
+        let mut canvas = OverlayCanvas::new();
+        canvas.fill_rect(0, 0, 2, 2, RED);
+        let mut frame = crate::Nv12Buffer::filled(4, 4, BLACK);
+        canvas.commit(&mut frame);
+        assert_eq!(frame.pixel(0, 0), BLACK);
+
+        canvas.present();
+        canvas.draw_mask(2, 2, CoverageMask::new(2, 2), WHITE);
+        canvas.fill_rect(2, 2, 2, 2, WHITE);
+        assert_eq!(canvas.pending(), 2);
+        canvas.commit(&mut frame);
+        assert_eq!(frame.pixel(0, 0), RED);
+        assert_eq!(frame.pixel(2, 2), BLACK);
+
+        canvas.present();
+        canvas.commit(&mut frame);
+        assert_eq!(frame.pixel(2, 2), WHITE);
+    }
+}
@@ -0,0 +1,137 @@
+//! Anti-aliased rectangles and lines with sub-pixel coordinates.
+//!
+//! Each pixel is blended with the fraction of it the shape covers: luma per
+//! pixel and chroma per 2x2 block, weighted by the coverage of its pixels,
+//! like in [`overlay_rgba_mut`](crate::overlay::overlay_rgba_mut). Soft
+//! edges keep boxes from shimmering once the stream is encoded.
+
+use std::ops::DerefMut;
+
+use crate::overlay::blend_region_mut;
+use crate::{NV12Image, YUV};
+
+/// The length of `[p, p + 1]` inside `[a, b]`.
+fn overlap(p: f32, a: f32, b: f32) -> f32 {
+    ((p + 1.0).min(b) - p.max(a)).clamp(0.0, 1.0)
+}
+
+/// The fraction of pixel `(px, py)` inside the rectangle `[x0, x1]` by
+/// `[y0, y1]`.
+fn rect_coverage(px: f32, py: f32, x0: f32, y0: f32, x1: f32, y1: f32) -> f32 {
+    overlap(px, x0, x1) * overlap(py, y0, y1)
+}
+
+/// Blends `color` over the pixels between `(x0, y0)` and `(x1, y1)`, each by
+/// the coverage `coverage` returns for its top-left corner.
+fn blend_coverage<T, F>(
+    img: &mut NV12Image<T>,
+    (x0, y0): (f32, f32),
+    (x1, y1): (f32, f32),
+    color: YUV,
+    coverage: F,
+) where
+    T: DerefMut<Target = [u8]>,
+    F: Fn(f32, f32) -> f32,
+{
+    let (left, top) = (x0.floor() as i64, y0.floor() as i64);
+    let width = (x1.ceil() as i64 - left).max(0) as u32;
+    let height = (y1.ceil() as i64 - top).max(0) as u32;
+    blend_region_mut(img, left, top, width, height, |dx, dy| {
+        let px = (left + i64::from(dx)) as f32;
+        let py = (top + i64::from(dy)) as f32;
+        let alpha = (coverage(px, py) * 255.0).round() as u32;
+        (color, alpha)
+    });
+}
+
+/// Fills the `width`x`height` rectangle at `(x, y)`.
+pub fn fill_rect_aa_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    color: YUV,
+) {
+    let (x1, y1) = (x + width, y + height);
+    blend_coverage(img, (x, y), (x1, y1), color, |px, py| {
+        rect_coverage(px, py, x, y, x1, y1)
+    });
+}
+
+/// Draws the outline of the `width`x`height` rectangle at `(x, y)`,
+/// `thickness` wide along the inside of its edges.
+pub fn draw_rect_aa_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    thickness: f32,
+    color: YUV,
+) {
+    let (x1, y1) = (x + width, y + height);
+    let (ix0, iy0, ix1, iy1) = (x + thickness, y + thickness, x1 - thickness, y1 - thickness);
+    blend_coverage(img, (x, y), (x1, y1), color, |px, py| {
+        let outer = rect_coverage(px, py, x, y, x1, y1);
+        let inner = if ix0 < ix1 && iy0 < iy1 {
+            rect_coverage(px, py, ix0, iy0, ix1, iy1)
+        } else {
+            0.0
+        };
+        outer - inner
+    });
+}
+
+/// Draws a line `width` wide from `start` to `end`, with round caps.
+/// Coordinates are in pixels, the center of pixel `(0, 0)` being at
+/// `(0.5, 0.5)`.
+pub fn draw_line_aa_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    start: (f32, f32),
+    end: (f32, f32),
+    width: f32,
+    color: YUV,
+) {
+    let radius = width / 2.0;
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let len2 = dx * dx + dy * dy;
+    let reach = radius + 1.0;
+    let min = (start.0.min(end.0) - reach, start.1.min(end.1) - reach);
+    let max = (start.0.max(end.0) + reach, start.1.max(end.1) + reach);
+    blend_coverage(img, min, max, color, |px, py| {
+        let (cx, cy) = (px + 0.5 - start.0, py + 0.5 - start.1);
+        let t = if len2 > 0.0 {
+            ((cx * dx + cy * dy) / len2).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let distance = (cx - t * dx).hypot(cy - t * dy);
+        (radius + 0.5 - distance).clamp(0.0, 1.0)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Nv12Buffer, BLACK, WHITE};
+
+    #[test]
+    fn edges_blend_by_coverage() {
+        let mut img = Nv12Buffer::filled(8, 4, BLACK);
+        fill_rect_aa_mut(&mut img, 0.5, 0.0, 2.0, 2.0, WHITE);
+        let (black, white) = (BLACK.0[0], WHITE.0[0]);
+        let half = img.y_row(0)[0];
+        assert!(half > black && half < white, "{}", half);
+        assert_eq!(img.y_row(1)[1], white);
+        assert_eq!(img.y_row(0)[2], half);
+        assert_eq!(img.y_row(0)[3], black);
+
+        let mut img = Nv12Buffer::filled(8, 4, BLACK);
+        draw_line_aa_mut(&mut img, (0.0, 2.0), (8.0, 2.0), 1.0, WHITE);
+        let (above, below) = (img.y_row(1)[4], img.y_row(2)[4]);
+        assert_eq!(above, below);
+        assert!(above > black && above < white, "{}", above);
+        assert_eq!(img.y_row(0)[4], black);
+    }
+}
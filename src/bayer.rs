@@ -0,0 +1,116 @@
+//! Demosaicing of raw Bayer sensor data into NV12.
+
+use crate::convert::nv12_from_rgb;
+use crate::{Nv12Buffer, YuvError};
+
+/// Arrangement of the color filter array, named after the colors of the
+/// top-left 2x2 block, row by row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CfaPattern {
+    RGGB,
+    BGGR,
+    GRBG,
+    GBRG,
+}
+
+impl CfaPattern {
+    /// The RGB channel sampled at `(x, y)`.
+    fn channel(&self, x: u32, y: u32) -> usize {
+        let block = match self {
+            CfaPattern::RGGB => [[0, 1], [1, 2]],
+            CfaPattern::BGGR => [[2, 1], [1, 0]],
+            CfaPattern::GRBG => [[1, 0], [2, 1]],
+            CfaPattern::GBRG => [[1, 2], [0, 1]],
+        };
+        block[(y & 1) as usize][(x & 1) as usize]
+    }
+}
+
+/// Converts `width`x`height` 8-bit raw samples, row by row without padding,
+/// to a tightly packed NV12 frame with bilinear demosaicing: the channels a
+/// pixel lacks are the average of the nearest samples of that channel in
+/// its 3x3 neighbourhood.
+///
+/// Panics if `raw` is shorter than `width * height`, see [`try_debayer`].
+pub fn debayer(raw: &[u8], pattern: CfaPattern, width: u32, height: u32) -> Nv12Buffer {
+    assert!(
+        raw.len() >= width as usize * height as usize,
+        "raw buffer of {} bytes is smaller than {}x{}",
+        raw.len(),
+        width,
+        height
+    );
+    nv12_from_rgb(width, height, |x, y| {
+        let own = pattern.channel(x, y);
+        let mut sum = [0u32; 3];
+        let mut count = [0u32; 3];
+        for ny in y.saturating_sub(1)..(y + 2).min(height) {
+            for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                let c = pattern.channel(nx, ny);
+                if c == own && (nx, ny) != (x, y) {
+                    continue;
+                }
+                sum[c] += raw[(ny * width + nx) as usize] as u32;
+                count[c] += 1;
+            }
+        }
+        // A channel missing from the neighbourhood, only possible in images
+        // one pixel wide or high, is left at zero.
+        core::array::from_fn(|c| {
+            let rounded = sum[c] + count[c] / 2;
+            rounded.checked_div(count[c]).unwrap_or(0) as u8
+        })
+    })
+}
+
+/// Like [`debayer`], but returns an error instead of panicking if `raw` is
+/// too short.
+pub fn try_debayer(
+    raw: &[u8],
+    pattern: CfaPattern,
+    width: u32,
+    height: u32,
+) -> Result<Nv12Buffer, YuvError> {
+    let needed = width as usize * height as usize;
+    if raw.len() < needed {
+        return Err(YuvError::BufferTooSmall {
+            len: raw.len(),
+            needed,
+        });
+    }
+    Ok(debayer(raw, pattern, width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YUV;
+
+    #[test]
+    fn interpolates_missing_channels() {
+        // A red scene: only the R sites carry signal.
+        let (width, height) = (6, 4);
+        let raw: alloc::vec::Vec<u8> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                if CfaPattern::GRBG.channel(x, y) == 0 {
+                    200
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let img = debayer(&raw, CfaPattern::GRBG, width, height);
+        let red = YUV::from_rgb([200, 0, 0]);
+        for (x, y) in [(0, 0), (3, 1), (5, 3)] {
+            assert_eq!(img.pixel(x, y), red);
+        }
+        assert!(matches!(
+            try_debayer(&raw[1..], CfaPattern::GRBG, width, height),
+            Err(YuvError::BufferTooSmall {
+                len: 23,
+                needed: 24
+            })
+        ));
+    }
+}
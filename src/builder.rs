@@ -0,0 +1,144 @@
+use core::ops::Deref;
+
+use crate::{ChromaWrite, ColorMatrix, NV12Image, YuvError};
+
+/// Configures the layout and interpretation of an [`NV12Image`] and checks
+/// it against the buffer in [`build`](Self::build).
+///
+/// Unset strides default to the tightly packed layout, and an unset UV
+/// offset places the UV plane right after the Y plane.
+///
+/// ```
+/// use yuvimg::{ChromaWrite, ColorMatrix, Nv12ImageBuilder};
+///
+/// let img = Nv12ImageBuilder::new(4, 2)
+///     .y_stride(8)
+///     .uv_stride(8)
+///     .color_matrix(ColorMatrix::BT709_VIDEO)
+///     .chroma_write(ChromaWrite::Pixel)
+///     .build(vec![0u8; 24])
+///     .unwrap();
+/// assert_eq!(img.strides(), (8, 8));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Nv12ImageBuilder {
+    width: u32,
+    height: u32,
+    y_stride: Option<u32>,
+    uv_stride: Option<u32>,
+    uv_offset: Option<u32>,
+    color_matrix: ColorMatrix,
+    chroma_write: ChromaWrite,
+}
+
+impl Nv12ImageBuilder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            y_stride: None,
+            uv_stride: None,
+            uv_offset: None,
+            color_matrix: ColorMatrix::DEFAULT,
+            chroma_write: ChromaWrite::Block,
+        }
+    }
+
+    pub fn y_stride(mut self, y_stride: u32) -> Self {
+        self.y_stride = Some(y_stride);
+        self
+    }
+
+    pub fn uv_stride(mut self, uv_stride: u32) -> Self {
+        self.uv_stride = Some(uv_stride);
+        self
+    }
+
+    /// Byte offset of the UV plane in the buffer. Ignored by
+    /// [`build_planes`](Self::build_planes).
+    pub fn uv_offset(mut self, uv_offset: u32) -> Self {
+        self.uv_offset = Some(uv_offset);
+        self
+    }
+
+    /// The matrix and range of the samples, which covers both, e.g.
+    /// [`ColorMatrix::BT709_VIDEO`].
+    pub fn color_matrix(mut self, color_matrix: ColorMatrix) -> Self {
+        self.color_matrix = color_matrix;
+        self
+    }
+
+    pub fn chroma_write(mut self, chroma_write: ChromaWrite) -> Self {
+        self.chroma_write = chroma_write;
+        self
+    }
+
+    fn strides(&self) -> (u32, u32) {
+        (
+            self.y_stride.unwrap_or(self.width),
+            self.uv_stride.unwrap_or(self.width.div_ceil(2) * 2),
+        )
+    }
+
+    /// Wraps `data`, holding both planes. Fails if a stride is shorter than
+    /// a row, the planes overlap, or `data` is too short.
+    pub fn build<T: Deref<Target = [u8]>>(self, data: T) -> Result<NV12Image<T>, YuvError> {
+        let (y_stride, uv_stride) = self.strides();
+        let mut img = NV12Image::new_strided(data, self.width, self.height, y_stride, uv_stride)
+            .with_color_matrix(self.color_matrix)
+            .with_chroma_write(self.chroma_write);
+        img.uv_offset = self.uv_offset.unwrap_or(y_stride * self.height);
+        img.validate()?;
+        Ok(img)
+    }
+
+    /// Like [`build`](Self::build) for planes in separate buffers.
+    pub fn build_planes<T: Deref<Target = [u8]>>(
+        self,
+        y: T,
+        uv: T,
+    ) -> Result<NV12Image<T>, YuvError> {
+        let (y_stride, uv_stride) = self.strides();
+        let img = NV12Image::new_planes(y, uv, self.width, self.height, y_stride, uv_stride)
+            .with_color_matrix(self.color_matrix)
+            .with_chroma_write(self.chroma_write);
+        img.validate()?;
+        Ok(img)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RED;
+
+    #[test]
+    fn validates_and_configures() {
+        assert!(matches!(
+            Nv12ImageBuilder::new(4, 2)
+                .uv_offset(4)
+                .build(vec![0u8; 12]),
+            Err(YuvError::PlanesOverlap {
+                uv_offset: 4,
+                y_len: 8
+            })
+        ));
+        assert!(matches!(
+            Nv12ImageBuilder::new(4, 2)
+                .uv_offset(10)
+                .build(vec![0u8; 12]),
+            Err(YuvError::BufferTooSmall {
+                len: 12,
+                needed: 14
+            })
+        ));
+        let mut img = Nv12ImageBuilder::new(4, 2)
+            .chroma_write(ChromaWrite::Pixel)
+            .build_planes(vec![0u8; 8], vec![0u8; 4])
+            .unwrap();
+        img.set_pixel(1, 1, RED);
+        let (y, uv) = img.into_planes();
+        assert_eq!(y, [0, 0, 0, 0, 0, RED.0[0], 0, 0]);
+        assert_eq!(uv.unwrap(), [RED.0[1], RED.0[2], 0, 0]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Chroma keying (green or blue screen) computed from the U and V planes,
+//! without converting to RGB.
+//!
+//! The key is decided per 2x2 block, by the distance of the block's chroma
+//! from the chroma of the key color.
+
+use std::ops::Deref;
+
+use alloc::vec;
+
+use crate::metrics::check_sizes;
+use crate::{FrameDescriptor, NV12Image, Nv12Buffer, PixelFormat, YUV};
+
+/// Parameters of [`chroma_key_composite`]. Distances are in chroma units,
+/// measured in the U/V plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChromaKeyParams {
+    /// The screen color.
+    pub key: YUV,
+    /// Blocks closer to the key than this are fully replaced by the
+    /// background.
+    pub tolerance: f32,
+    /// Width of the ramp beyond `tolerance` over which the foreground fades
+    /// in. Zero gives a hard edge.
+    pub softness: f32,
+    /// How much of the key's tint, 0 to 1, is removed from foreground blocks
+    /// within twice the ramp, in chroma and in the extra luma the key color
+    /// brings.
+    pub spill: f32,
+}
+
+impl ChromaKeyParams {
+    /// Defaults that suit an evenly lit screen of color `key`.
+    pub fn new(key: YUV) -> Self {
+        Self {
+            key,
+            tolerance: 24.0,
+            softness: 32.0,
+            spill: 0.5,
+        }
+    }
+
+    /// A typical chroma key green, RGB `#00b140`.
+    pub fn green_screen() -> Self {
+        Self::new(YUV::from_rgb([0x00, 0xb1, 0x40]))
+    }
+
+    /// A typical chroma key blue, RGB `#0047bb`.
+    pub fn blue_screen() -> Self {
+        Self::new(YUV::from_rgb([0x00, 0x47, 0xbb]))
+    }
+}
+
+/// Composites `fg` over `bg` where `fg` is not the key color. Both frames
+/// must have the same size. The result is tightly packed with the color
+/// matrix of `fg`.
+pub fn chroma_key_composite<A, B>(
+    fg: &NV12Image<A>,
+    bg: &NV12Image<B>,
+    key: ChromaKeyParams,
+) -> Nv12Buffer
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    check_sizes(fg, bg);
+    let (width, height) = (fg.width, fg.height);
+    let desc = FrameDescriptor::packed(width, height, PixelFormat::NV12);
+    let data = vec![0; desc.frame_size()];
+    let mut out = NV12Image::from_strided(data, width, height, desc.y_stride, desc.uv_stride)
+        .with_color_matrix(fg.color_matrix);
+    let (y_stride, uv_stride) = (desc.y_stride, desc.uv_stride);
+
+    let key_uv = [key.key.0[1] as f32 - 128.0, key.key.0[2] as f32 - 128.0];
+    let key_len = key_uv[0].hypot(key_uv[1]).max(1.0);
+    let key_dir = [key_uv[0] / key_len, key_uv[1] / key_len];
+    // The luma the key adds per unit of its chroma, removed with the spill.
+    let spill_luma = (key.key.0[0] as f32 - 128.0).max(0.0) / key_len;
+    let ramp = key.softness.max(f32::EPSILON);
+
+    let (fg_uv, bg_uv) = (fg.uv_plane(), bg.uv_plane());
+    let (luma, chroma) = out.split_planes_mut();
+    for by in 0..height.div_ceil(2) {
+        for bx in 0..width.div_ceil(2) {
+            let i = (by * fg.uv_stride + bx * 2) as usize;
+            let mut c = [fg_uv[i] as f32 - 128.0, fg_uv[i + 1] as f32 - 128.0];
+            let distance = (c[0] - key_uv[0]).hypot(c[1] - key_uv[1]);
+            let alpha = ((distance - key.tolerance) / ramp).clamp(0.0, 1.0);
+            // Spill: the part of the chroma pointing towards the key.
+            let along = c[0] * key_dir[0] + c[1] * key_dir[1];
+            let near = 1.0 - ((distance - key.tolerance) / (2.0 * ramp)).clamp(0.0, 1.0);
+            let removed = (along * key.spill.clamp(0.0, 1.0) * near).max(0.0);
+            c[0] -= key_dir[0] * removed;
+            c[1] -= key_dir[1] * removed;
+            let luma_shift = removed * spill_luma;
+
+            let j = (by * bg.uv_stride + bx * 2) as usize;
+            let o = (by * uv_stride + bx * 2) as usize;
+            for k in 0..2 {
+                let fg = c[k] + 128.0;
+                let value = fg * alpha + bg_uv[j + k] as f32 * (1.0 - alpha);
+                chroma[o + k] = value.round().clamp(0.0, 255.0) as u8;
+            }
+            for y in by * 2..(by * 2 + 2).min(height) {
+                for x in bx * 2..(bx * 2 + 2).min(width) {
+                    let fg = fg.data[(y * fg.y_stride + x) as usize] as f32 - luma_shift;
+                    let bg = bg.data[(y * bg.y_stride + x) as usize] as f32;
+                    let value = fg * alpha + bg * (1.0 - alpha);
+                    luma[(y * y_stride + x) as usize] = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLUE, RED};
+
+    #[test]
+    fn keys_out_the_screen() {
+        let key = ChromaKeyParams::green_screen();
+        let mut fg = Nv12Buffer::filled(4, 2, key.key);
+        fg.fill_rect(2, 0, 2, 2, RED);
+        let bg = Nv12Buffer::filled(4, 2, BLUE);
+        let out = chroma_key_composite(&fg, &bg, key);
+        assert_eq!(out.pixel(0, 0), BLUE);
+        assert_eq!(out.pixel(2, 0), RED);
+
+        let hard = ChromaKeyParams {
+            softness: 0.0,
+            spill: 0.0,
+            ..key
+        };
+        let tinted = YUV([key.key.0[0], key.key.0[1] + 30, key.key.0[2] + 30]);
+        let fg = Nv12Buffer::filled(4, 2, tinted);
+        assert_eq!(chroma_key_composite(&fg, &bg, hard).pixel(0, 0), tinted);
+    }
+}
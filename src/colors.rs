@@ -0,0 +1,321 @@
+//! The CSS named colors, converted at compile time with
+//! [`yuv_from_rgb`](crate::yuv_from_rgb).
+//!
+//! Names follow CSS, so [`GREEN`] is the dark `#008000`; the pure green
+//! primary is [`LIME`], which is also [`crate::GREEN`].
+
+use crate::{yuv_from_rgb, YUV};
+
+/// `aliceblue`, #f0f8ff.
+pub const ALICEBLUE: YUV = yuv_from_rgb(240, 248, 255);
+/// `antiquewhite`, #faebd7.
+pub const ANTIQUEWHITE: YUV = yuv_from_rgb(250, 235, 215);
+/// `aqua`, #00ffff.
+pub const AQUA: YUV = yuv_from_rgb(0, 255, 255);
+/// `aquamarine`, #7fffd4.
+pub const AQUAMARINE: YUV = yuv_from_rgb(127, 255, 212);
+/// `azure`, #f0ffff.
+pub const AZURE: YUV = yuv_from_rgb(240, 255, 255);
+/// `beige`, #f5f5dc.
+pub const BEIGE: YUV = yuv_from_rgb(245, 245, 220);
+/// `bisque`, #ffe4c4.
+pub const BISQUE: YUV = yuv_from_rgb(255, 228, 196);
+/// `black`, #000000.
+pub const BLACK: YUV = yuv_from_rgb(0, 0, 0);
+/// `blanchedalmond`, #ffebcd.
+pub const BLANCHEDALMOND: YUV = yuv_from_rgb(255, 235, 205);
+/// `blue`, #0000ff.
+pub const BLUE: YUV = yuv_from_rgb(0, 0, 255);
+/// `blueviolet`, #8a2be2.
+pub const BLUEVIOLET: YUV = yuv_from_rgb(138, 43, 226);
+/// `brown`, #a52a2a.
+pub const BROWN: YUV = yuv_from_rgb(165, 42, 42);
+/// `burlywood`, #deb887.
+pub const BURLYWOOD: YUV = yuv_from_rgb(222, 184, 135);
+/// `cadetblue`, #5f9ea0.
+pub const CADETBLUE: YUV = yuv_from_rgb(95, 158, 160);
+/// `chartreuse`, #7fff00.
+pub const CHARTREUSE: YUV = yuv_from_rgb(127, 255, 0);
+/// `chocolate`, #d2691e.
+pub const CHOCOLATE: YUV = yuv_from_rgb(210, 105, 30);
+/// `coral`, #ff7f50.
+pub const CORAL: YUV = yuv_from_rgb(255, 127, 80);
+/// `cornflowerblue`, #6495ed.
+pub const CORNFLOWERBLUE: YUV = yuv_from_rgb(100, 149, 237);
+/// `cornsilk`, #fff8dc.
+pub const CORNSILK: YUV = yuv_from_rgb(255, 248, 220);
+/// `crimson`, #dc143c.
+pub const CRIMSON: YUV = yuv_from_rgb(220, 20, 60);
+/// `cyan`, #00ffff.
+pub const CYAN: YUV = yuv_from_rgb(0, 255, 255);
+/// `darkblue`, #00008b.
+pub const DARKBLUE: YUV = yuv_from_rgb(0, 0, 139);
+/// `darkcyan`, #008b8b.
+pub const DARKCYAN: YUV = yuv_from_rgb(0, 139, 139);
+/// `darkgoldenrod`, #b8860b.
+pub const DARKGOLDENROD: YUV = yuv_from_rgb(184, 134, 11);
+/// `darkgray`, #a9a9a9.
+pub const DARKGRAY: YUV = yuv_from_rgb(169, 169, 169);
+/// `darkgreen`, #006400.
+pub const DARKGREEN: YUV = yuv_from_rgb(0, 100, 0);
+/// `darkgrey`, #a9a9a9.
+pub const DARKGREY: YUV = yuv_from_rgb(169, 169, 169);
+/// `darkkhaki`, #bdb76b.
+pub const DARKKHAKI: YUV = yuv_from_rgb(189, 183, 107);
+/// `darkmagenta`, #8b008b.
+pub const DARKMAGENTA: YUV = yuv_from_rgb(139, 0, 139);
+/// `darkolivegreen`, #556b2f.
+pub const DARKOLIVEGREEN: YUV = yuv_from_rgb(85, 107, 47);
+/// `darkorange`, #ff8c00.
+pub const DARKORANGE: YUV = yuv_from_rgb(255, 140, 0);
+/// `darkorchid`, #9932cc.
+pub const DARKORCHID: YUV = yuv_from_rgb(153, 50, 204);
+/// `darkred`, #8b0000.
+pub const DARKRED: YUV = yuv_from_rgb(139, 0, 0);
+/// `darksalmon`, #e9967a.
+pub const DARKSALMON: YUV = yuv_from_rgb(233, 150, 122);
+/// `darkseagreen`, #8fbc8f.
+pub const DARKSEAGREEN: YUV = yuv_from_rgb(143, 188, 143);
+/// `darkslateblue`, #483d8b.
+pub const DARKSLATEBLUE: YUV = yuv_from_rgb(72, 61, 139);
+/// `darkslategray`, #2f4f4f.
+pub const DARKSLATEGRAY: YUV = yuv_from_rgb(47, 79, 79);
+/// `darkslategrey`, #2f4f4f.
+pub const DARKSLATEGREY: YUV = yuv_from_rgb(47, 79, 79);
+/// `darkturquoise`, #00ced1.
+pub const DARKTURQUOISE: YUV = yuv_from_rgb(0, 206, 209);
+/// `darkviolet`, #9400d3.
+pub const DARKVIOLET: YUV = yuv_from_rgb(148, 0, 211);
+/// `deeppink`, #ff1493.
+pub const DEEPPINK: YUV = yuv_from_rgb(255, 20, 147);
+/// `deepskyblue`, #00bfff.
+pub const DEEPSKYBLUE: YUV = yuv_from_rgb(0, 191, 255);
+/// `dimgray`, #696969.
+pub const DIMGRAY: YUV = yuv_from_rgb(105, 105, 105);
+/// `dimgrey`, #696969.
+pub const DIMGREY: YUV = yuv_from_rgb(105, 105, 105);
+/// `dodgerblue`, #1e90ff.
+pub const DODGERBLUE: YUV = yuv_from_rgb(30, 144, 255);
+/// `firebrick`, #b22222.
+pub const FIREBRICK: YUV = yuv_from_rgb(178, 34, 34);
+/// `floralwhite`, #fffaf0.
+pub const FLORALWHITE: YUV = yuv_from_rgb(255, 250, 240);
+/// `forestgreen`, #228b22.
+pub const FORESTGREEN: YUV = yuv_from_rgb(34, 139, 34);
+/// `fuchsia`, #ff00ff.
+pub const FUCHSIA: YUV = yuv_from_rgb(255, 0, 255);
+/// `gainsboro`, #dcdcdc.
+pub const GAINSBORO: YUV = yuv_from_rgb(220, 220, 220);
+/// `ghostwhite`, #f8f8ff.
+pub const GHOSTWHITE: YUV = yuv_from_rgb(248, 248, 255);
+/// `gold`, #ffd700.
+pub const GOLD: YUV = yuv_from_rgb(255, 215, 0);
+/// `goldenrod`, #daa520.
+pub const GOLDENROD: YUV = yuv_from_rgb(218, 165, 32);
+/// `gray`, #808080.
+pub const GRAY: YUV = yuv_from_rgb(128, 128, 128);
+/// `green`, #008000.
+pub const GREEN: YUV = yuv_from_rgb(0, 128, 0);
+/// `greenyellow`, #adff2f.
+pub const GREENYELLOW: YUV = yuv_from_rgb(173, 255, 47);
+/// `grey`, #808080.
+pub const GREY: YUV = yuv_from_rgb(128, 128, 128);
+/// `honeydew`, #f0fff0.
+pub const HONEYDEW: YUV = yuv_from_rgb(240, 255, 240);
+/// `hotpink`, #ff69b4.
+pub const HOTPINK: YUV = yuv_from_rgb(255, 105, 180);
+/// `indianred`, #cd5c5c.
+pub const INDIANRED: YUV = yuv_from_rgb(205, 92, 92);
+/// `indigo`, #4b0082.
+pub const INDIGO: YUV = yuv_from_rgb(75, 0, 130);
+/// `ivory`, #fffff0.
+pub const IVORY: YUV = yuv_from_rgb(255, 255, 240);
+/// `khaki`, #f0e68c.
+pub const KHAKI: YUV = yuv_from_rgb(240, 230, 140);
+/// `lavender`, #e6e6fa.
+pub const LAVENDER: YUV = yuv_from_rgb(230, 230, 250);
+/// `lavenderblush`, #fff0f5.
+pub const LAVENDERBLUSH: YUV = yuv_from_rgb(255, 240, 245);
+/// `lawngreen`, #7cfc00.
+pub const LAWNGREEN: YUV = yuv_from_rgb(124, 252, 0);
+/// `lemonchiffon`, #fffacd.
+pub const LEMONCHIFFON: YUV = yuv_from_rgb(255, 250, 205);
+/// `lightblue`, #add8e6.
+pub const LIGHTBLUE: YUV = yuv_from_rgb(173, 216, 230);
+/// `lightcoral`, #f08080.
+pub const LIGHTCORAL: YUV = yuv_from_rgb(240, 128, 128);
+/// `lightcyan`, #e0ffff.
+pub const LIGHTCYAN: YUV = yuv_from_rgb(224, 255, 255);
+/// `lightgoldenrodyellow`, #fafad2.
+pub const LIGHTGOLDENRODYELLOW: YUV = yuv_from_rgb(250, 250, 210);
+/// `lightgray`, #d3d3d3.
+pub const LIGHTGRAY: YUV = yuv_from_rgb(211, 211, 211);
+/// `lightgreen`, #90ee90.
+pub const LIGHTGREEN: YUV = yuv_from_rgb(144, 238, 144);
+/// `lightgrey`, #d3d3d3.
+pub const LIGHTGREY: YUV = yuv_from_rgb(211, 211, 211);
+/// `lightpink`, #ffb6c1.
+pub const LIGHTPINK: YUV = yuv_from_rgb(255, 182, 193);
+/// `lightsalmon`, #ffa07a.
+pub const LIGHTSALMON: YUV = yuv_from_rgb(255, 160, 122);
+/// `lightseagreen`, #20b2aa.
+pub const LIGHTSEAGREEN: YUV = yuv_from_rgb(32, 178, 170);
+/// `lightskyblue`, #87cefa.
+pub const LIGHTSKYBLUE: YUV = yuv_from_rgb(135, 206, 250);
+/// `lightslategray`, #778899.
+pub const LIGHTSLATEGRAY: YUV = yuv_from_rgb(119, 136, 153);
+/// `lightslategrey`, #778899.
+pub const LIGHTSLATEGREY: YUV = yuv_from_rgb(119, 136, 153);
+/// `lightsteelblue`, #b0c4de.
+pub const LIGHTSTEELBLUE: YUV = yuv_from_rgb(176, 196, 222);
+/// `lightyellow`, #ffffe0.
+pub const LIGHTYELLOW: YUV = yuv_from_rgb(255, 255, 224);
+/// `lime`, #00ff00.
+pub const LIME: YUV = yuv_from_rgb(0, 255, 0);
+/// `limegreen`, #32cd32.
+pub const LIMEGREEN: YUV = yuv_from_rgb(50, 205, 50);
+/// `linen`, #faf0e6.
+pub const LINEN: YUV = yuv_from_rgb(250, 240, 230);
+/// `magenta`, #ff00ff.
+pub const MAGENTA: YUV = yuv_from_rgb(255, 0, 255);
+/// `maroon`, #800000.
+pub const MAROON: YUV = yuv_from_rgb(128, 0, 0);
+/// `mediumaquamarine`, #66cdaa.
+pub const MEDIUMAQUAMARINE: YUV = yuv_from_rgb(102, 205, 170);
+/// `mediumblue`, #0000cd.
+pub const MEDIUMBLUE: YUV = yuv_from_rgb(0, 0, 205);
+/// `mediumorchid`, #ba55d3.
+pub const MEDIUMORCHID: YUV = yuv_from_rgb(186, 85, 211);
+/// `mediumpurple`, #9370db.
+pub const MEDIUMPURPLE: YUV = yuv_from_rgb(147, 112, 219);
+/// `mediumseagreen`, #3cb371.
+pub const MEDIUMSEAGREEN: YUV = yuv_from_rgb(60, 179, 113);
+/// `mediumslateblue`, #7b68ee.
+pub const MEDIUMSLATEBLUE: YUV = yuv_from_rgb(123, 104, 238);
+/// `mediumspringgreen`, #00fa9a.
+pub const MEDIUMSPRINGGREEN: YUV = yuv_from_rgb(0, 250, 154);
+/// `mediumturquoise`, #48d1cc.
+pub const MEDIUMTURQUOISE: YUV = yuv_from_rgb(72, 209, 204);
+/// `mediumvioletred`, #c71585.
+pub const MEDIUMVIOLETRED: YUV = yuv_from_rgb(199, 21, 133);
+/// `midnightblue`, #191970.
+pub const MIDNIGHTBLUE: YUV = yuv_from_rgb(25, 25, 112);
+/// `mintcream`, #f5fffa.
+pub const MINTCREAM: YUV = yuv_from_rgb(245, 255, 250);
+/// `mistyrose`, #ffe4e1.
+pub const MISTYROSE: YUV = yuv_from_rgb(255, 228, 225);
+/// `moccasin`, #ffe4b5.
+pub const MOCCASIN: YUV = yuv_from_rgb(255, 228, 181);
+/// `navajowhite`, #ffdead.
+pub const NAVAJOWHITE: YUV = yuv_from_rgb(255, 222, 173);
+/// `navy`, #000080.
+pub const NAVY: YUV = yuv_from_rgb(0, 0, 128);
+/// `oldlace`, #fdf5e6.
+pub const OLDLACE: YUV = yuv_from_rgb(253, 245, 230);
+/// `olive`, #808000.
+pub const OLIVE: YUV = yuv_from_rgb(128, 128, 0);
+/// `olivedrab`, #6b8e23.
+pub const OLIVEDRAB: YUV = yuv_from_rgb(107, 142, 35);
+/// `orange`, #ffa500.
+pub const ORANGE: YUV = yuv_from_rgb(255, 165, 0);
+/// `orangered`, #ff4500.
+pub const ORANGERED: YUV = yuv_from_rgb(255, 69, 0);
+/// `orchid`, #da70d6.
+pub const ORCHID: YUV = yuv_from_rgb(218, 112, 214);
+/// `palegoldenrod`, #eee8aa.
+pub const PALEGOLDENROD: YUV = yuv_from_rgb(238, 232, 170);
+/// `palegreen`, #98fb98.
+pub const PALEGREEN: YUV = yuv_from_rgb(152, 251, 152);
+/// `paleturquoise`, #afeeee.
+pub const PALETURQUOISE: YUV = yuv_from_rgb(175, 238, 238);
+/// `palevioletred`, #db7093.
+pub const PALEVIOLETRED: YUV = yuv_from_rgb(219, 112, 147);
+/// `papayawhip`, #ffefd5.
+pub const PAPAYAWHIP: YUV = yuv_from_rgb(255, 239, 213);
+/// `peachpuff`, #ffdab9.
+pub const PEACHPUFF: YUV = yuv_from_rgb(255, 218, 185);
+/// `peru`, #cd853f.
+pub const PERU: YUV = yuv_from_rgb(205, 133, 63);
+/// `pink`, #ffc0cb.
+pub const PINK: YUV = yuv_from_rgb(255, 192, 203);
+/// `plum`, #dda0dd.
+pub const PLUM: YUV = yuv_from_rgb(221, 160, 221);
+/// `powderblue`, #b0e0e6.
+pub const POWDERBLUE: YUV = yuv_from_rgb(176, 224, 230);
+/// `purple`, #800080.
+pub const PURPLE: YUV = yuv_from_rgb(128, 0, 128);
+/// `rebeccapurple`, #663399.
+pub const REBECCAPURPLE: YUV = yuv_from_rgb(102, 51, 153);
+/// `red`, #ff0000.
+pub const RED: YUV = yuv_from_rgb(255, 0, 0);
+/// `rosybrown`, #bc8f8f.
+pub const ROSYBROWN: YUV = yuv_from_rgb(188, 143, 143);
+/// `royalblue`, #4169e1.
+pub const ROYALBLUE: YUV = yuv_from_rgb(65, 105, 225);
+/// `saddlebrown`, #8b4513.
+pub const SADDLEBROWN: YUV = yuv_from_rgb(139, 69, 19);
+/// `salmon`, #fa8072.
+pub const SALMON: YUV = yuv_from_rgb(250, 128, 114);
+/// `sandybrown`, #f4a460.
+pub const SANDYBROWN: YUV = yuv_from_rgb(244, 164, 96);
+/// `seagreen`, #2e8b57.
+pub const SEAGREEN: YUV = yuv_from_rgb(46, 139, 87);
+/// `seashell`, #fff5ee.
+pub const SEASHELL: YUV = yuv_from_rgb(255, 245, 238);
+/// `sienna`, #a0522d.
+pub const SIENNA: YUV = yuv_from_rgb(160, 82, 45);
+/// `silver`, #c0c0c0.
+pub const SILVER: YUV = yuv_from_rgb(192, 192, 192);
+/// `skyblue`, #87ceeb.
+pub const SKYBLUE: YUV = yuv_from_rgb(135, 206, 235);
+/// `slateblue`, #6a5acd.
+pub const SLATEBLUE: YUV = yuv_from_rgb(106, 90, 205);
+/// `slategray`, #708090.
+pub const SLATEGRAY: YUV = yuv_from_rgb(112, 128, 144);
+/// `slategrey`, #708090.
+pub const SLATEGREY: YUV = yuv_from_rgb(112, 128, 144);
+/// `snow`, #fffafa.
+pub const SNOW: YUV = yuv_from_rgb(255, 250, 250);
+/// `springgreen`, #00ff7f.
+pub const SPRINGGREEN: YUV = yuv_from_rgb(0, 255, 127);
+/// `steelblue`, #4682b4.
+pub const STEELBLUE: YUV = yuv_from_rgb(70, 130, 180);
+/// `tan`, #d2b48c.
+pub const TAN: YUV = yuv_from_rgb(210, 180, 140);
+/// `teal`, #008080.
+pub const TEAL: YUV = yuv_from_rgb(0, 128, 128);
+/// `thistle`, #d8bfd8.
+pub const THISTLE: YUV = yuv_from_rgb(216, 191, 216);
+/// `tomato`, #ff6347.
+pub const TOMATO: YUV = yuv_from_rgb(255, 99, 71);
+/// `turquoise`, #40e0d0.
+pub const TURQUOISE: YUV = yuv_from_rgb(64, 224, 208);
+/// `violet`, #ee82ee.
+pub const VIOLET: YUV = yuv_from_rgb(238, 130, 238);
+/// `wheat`, #f5deb3.
+pub const WHEAT: YUV = yuv_from_rgb(245, 222, 179);
+/// `white`, #ffffff.
+pub const WHITE: YUV = yuv_from_rgb(255, 255, 255);
+/// `whitesmoke`, #f5f5f5.
+pub const WHITESMOKE: YUV = yuv_from_rgb(245, 245, 245);
+/// `yellow`, #ffff00.
+pub const YELLOW: YUV = yuv_from_rgb(255, 255, 0);
+/// `yellowgreen`, #9acd32.
+pub const YELLOWGREEN: YUV = yuv_from_rgb(154, 205, 50);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_follow_css() {
+        assert_eq!(LIME, crate::GREEN);
+        assert_eq!((WHITE, BLACK), (crate::WHITE, crate::BLACK));
+        let [r, g, b] = GREEN.rgb();
+        assert!(
+            r <= 1 && g.abs_diff(128) <= 1 && b <= 1,
+            "{:?}",
+            GREEN.rgb()
+        );
+    }
+}
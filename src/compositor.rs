@@ -0,0 +1,211 @@
+//! Composition of several frames into one, for multiview monitors, video
+//! walls and picture-in-picture.
+
+use core::ops::{Deref, DerefMut};
+
+use alloc::vec::Vec;
+
+use crate::imageops::{fit_cover, letterbox, replace, resize};
+use crate::{NV12Image, Nv12Buffer, Region, BLACK, YUV};
+
+/// How a source is scaled into its cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scaling {
+    /// Fills the cell, distorting sources of a different aspect ratio.
+    Stretch,
+    /// Fits the source in the cell and pads it with the background, see
+    /// [`letterbox`].
+    #[default]
+    Letterbox,
+    /// Covers the cell and crops the source, see [`fit_cover`].
+    Cover,
+}
+
+/// Places source frames into the cells of a layout in one output frame.
+///
+/// Cells starting on even coordinates with even sizes are copied row by row;
+/// others have the chroma of their edge blocks mixed with the neighbouring
+/// content.
+#[derive(Clone, Debug)]
+pub struct Compositor {
+    width: u32,
+    height: u32,
+    cells: Vec<Region>,
+    background: YUV,
+    scaling: Scaling,
+}
+
+impl Compositor {
+    /// A `width`x`height` output with the sources placed in `cells`, in
+    /// order. Cells are clipped to the output.
+    pub fn new(width: u32, height: u32, cells: Vec<Region>) -> Self {
+        let cells = cells.iter().map(|cell| cell.clip(width, height)).collect();
+        Self {
+            width,
+            height,
+            cells,
+            background: BLACK,
+            scaling: Scaling::default(),
+        }
+    }
+
+    /// `columns` by `rows` cells of (nearly) equal size, filled row by row.
+    /// Cell edges are rounded to even coordinates.
+    pub fn grid(width: u32, height: u32, columns: u32, rows: u32) -> Self {
+        let edge = |i: u32, count: u32, len: u32| match i {
+            i if i >= count => len,
+            i => ((u64::from(i) * u64::from(len) / u64::from(count)) as u32) & !1,
+        };
+        let mut cells = Vec::new();
+        for row in 0..rows {
+            let (y0, y1) = (edge(row, rows, height), edge(row + 1, rows, height));
+            for column in 0..columns {
+                let (x0, x1) = (
+                    edge(column, columns, width),
+                    edge(column + 1, columns, width),
+                );
+                cells.push(Region::new(x0, y0, x1 - x0, y1 - y0));
+            }
+        }
+        Self::new(width, height, cells)
+    }
+
+    /// The color of the output outside the sources. Black by default.
+    pub fn with_background(mut self, background: YUV) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// [`Scaling::Letterbox`] by default.
+    pub fn with_scaling(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    pub fn cells(&self) -> &[Region] {
+        &self.cells
+    }
+
+    /// A new output frame with `sources` in their cells. Surplus sources are
+    /// ignored and cells without a source are left as background.
+    pub fn compose<'a, T, I>(&self, sources: I) -> Nv12Buffer
+    where
+        T: Deref<Target = [u8]> + 'a,
+        I: IntoIterator<Item = &'a NV12Image<T>>,
+    {
+        let mut out = Nv12Buffer::filled(self.width, self.height, self.background);
+        self.place(&mut out, sources);
+        out
+    }
+
+    /// Like [`compose`](Self::compose), drawing into `out`, for example a
+    /// frame from a [`FramePool`](crate::pool::FramePool). Panics if `out` is
+    /// not the size of the layout.
+    pub fn compose_into<'a, T, U, I>(&self, out: &mut NV12Image<U>, sources: I)
+    where
+        T: Deref<Target = [u8]> + 'a,
+        U: DerefMut<Target = [u8]>,
+        I: IntoIterator<Item = &'a NV12Image<T>>,
+    {
+        assert_eq!(
+            (out.width, out.height),
+            (self.width, self.height),
+            "output frame does not match the layout"
+        );
+        out.fill(self.background);
+        self.place(out, sources);
+    }
+
+    fn place<'a, T, U, I>(&self, out: &mut NV12Image<U>, sources: I)
+    where
+        T: Deref<Target = [u8]> + 'a,
+        U: DerefMut<Target = [u8]>,
+        I: IntoIterator<Item = &'a NV12Image<T>>,
+    {
+        for (cell, source) in self.cells.iter().zip(sources) {
+            if cell.is_empty() {
+                continue;
+            }
+            let (width, height) = (cell.width, cell.height);
+            let scaled = match self.scaling {
+                Scaling::Stretch => resize(source, width, height),
+                Scaling::Letterbox => letterbox(source, width, height, self.background),
+                Scaling::Cover => fit_cover(source, width, height),
+            };
+            replace(out, &scaled, i64::from(cell.x), i64::from(cell.y));
+        }
+    }
+}
+
+/// A solid frame drawn around a picture-in-picture, see [`insert_pip_mut`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Border {
+    pub color: YUV,
+    /// Thickness in pixels. Even thicknesses keep the picture on block
+    /// boundaries.
+    pub width: u32,
+}
+
+/// Scales `src` into `rect` of `dst`, for example a corner of a conference
+/// view, with `border` drawn along the inside of `rect`. The source is
+/// stretched to the space left inside the border, and `rect` is clipped to
+/// `dst`.
+pub fn insert_pip_mut<T, U>(
+    dst: &mut NV12Image<T>,
+    src: &NV12Image<U>,
+    rect: Region,
+    border: Option<Border>,
+) where
+    T: DerefMut<Target = [u8]>,
+    U: Deref<Target = [u8]>,
+{
+    let (x, y) = (i64::from(rect.x), i64::from(rect.y));
+    let thickness = match border {
+        Some(border) => {
+            dst.fill_rect(x, y, rect.width, rect.height, border.color);
+            border.width.min(rect.width / 2).min(rect.height / 2)
+        }
+        None => 0,
+    };
+    let (width, height) = (rect.width - 2 * thickness, rect.height - 2 * thickness);
+    if width == 0 || height == 0 {
+        return;
+    }
+    let scaled = resize(src, width, height);
+    let inset = i64::from(thickness);
+    replace(dst, &scaled, x + inset, y + inset);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLUE, GREEN, RED};
+
+    #[test]
+    fn grid_places_sources_in_cells() {
+        let compositor = Compositor::grid(10, 6, 2, 2).with_scaling(Scaling::Stretch);
+        assert_eq!(compositor.cells()[3], Region::new(4, 2, 6, 4));
+        let sources = [RED, GREEN, BLUE].map(|color| Nv12Buffer::filled(8, 8, color));
+        let out = compositor.compose(&sources);
+        assert_eq!(out.pixel(0, 0), RED);
+        assert_eq!(out.pixel(9, 0), GREEN);
+        assert_eq!(out.pixel(3, 5), BLUE);
+        assert_eq!(out.pixel(4, 2), BLACK);
+    }
+
+    #[test]
+    fn pip_with_border() {
+        let mut dst = Nv12Buffer::filled(16, 12, BLUE);
+        let src = Nv12Buffer::filled(32, 24, RED);
+        let border = Border {
+            color: GREEN,
+            width: 2,
+        };
+        insert_pip_mut(&mut dst, &src, Region::new(8, 6, 8, 6), Some(border));
+        assert_eq!(dst.pixel(6, 6), BLUE);
+        assert_eq!(dst.pixel(8, 6), GREEN);
+        assert_eq!(dst.pixel(10, 8), RED);
+        assert_eq!(dst.pixel(13, 9), RED);
+        assert_eq!(dst.pixel(14, 10), GREEN);
+    }
+}
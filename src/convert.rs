@@ -0,0 +1,321 @@
+//! Whole-frame conversions between NV12 and RGB.
+
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+#[cfg(feature = "std")]
+use core::ops::DerefMut;
+
+#[cfg(feature = "std")]
+use image::{GenericImageView, ImageBuffer, Pixel, Rgb, RgbImage};
+
+#[cfg(feature = "std")]
+use crate::overlay::{blend_region_mut, AlphaMode};
+#[cfg(feature = "std")]
+use crate::Nv12Buffer;
+use crate::{FrameDescriptor, NV12Image, PixelFormat, YuvError, YUV};
+
+/// Converts every pixel of `img` to RGB with its
+/// [`color_matrix`](NV12Image::color_matrix), combining its own luma with the
+/// chroma of its 2x2 block. Builds [`RgbTables`] on every call; use
+/// [`to_rgb_with`] to reuse them.
+#[cfg(feature = "std")]
+pub fn to_rgb<T: Deref<Target = [u8]>>(img: &NV12Image<T>) -> RgbImage {
+    to_rgb_with(img, &RgbTables::new(&img.color_matrix()))
+}
+
+/// Converts an RGB image to NV12, averaging chroma over each 2x2 block.
+#[cfg(feature = "std")]
+pub fn from_rgb(img: &RgbImage) -> NV12Image<Vec<u8>> {
+    nv12_from_rgb(img.width(), img.height(), |x, y| img.get_pixel(x, y).0)
+}
+
+#[cfg(feature = "std")]
+impl Nv12Buffer {
+    /// Renders any 8-bit image, such as a decoded PNG (`Rgb`, `Rgba` or
+    /// `Luma` pixels) or a generated chart, into a new tightly packed frame,
+    /// averaging chroma over each 2x2 block. Alpha is ignored; use
+    /// [`draw_view_mut`](NV12Image::draw_view_mut) to blend onto a frame.
+    pub fn from_view<I, P>(view: &I) -> Self
+    where
+        I: GenericImageView<Pixel = P>,
+        P: Pixel<Subpixel = u8>,
+    {
+        let (width, height) = view.dimensions();
+        nv12_from_rgb(width, height, |x, y| view.get_pixel(x, y).to_rgb().0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: DerefMut<Target = [u8]>> NV12Image<T> {
+    /// Draws `view` with its top-left corner at `(x, y)`, blended by its
+    /// alpha, if it has one, as `mode` says. Parts outside the frame are
+    /// skipped and chroma is blended per 2x2 block like in
+    /// [`overlay_rgba_mut`](crate::overlay::overlay_rgba_mut).
+    pub fn draw_view_mut<I, P>(&mut self, view: &I, x: i64, y: i64, mode: AlphaMode)
+    where
+        I: GenericImageView<Pixel = P>,
+        P: Pixel<Subpixel = u8>,
+    {
+        let (width, height) = view.dimensions();
+        blend_region_mut(self, x, y, width, height, |dx, dy| {
+            let (rgb, alpha) = mode.straight(view.get_pixel(dx, dy).to_rgba().0);
+            (YUV::from_rgb(rgb), alpha)
+        });
+    }
+}
+
+/// Like [`to_rgb`], returning tightly packed RGB24 bytes.
+pub fn to_rgb_bytes<T: Deref<Target = [u8]>>(img: &NV12Image<T>) -> Vec<u8> {
+    to_rgb_bytes_with(img, &RgbTables::new(&img.color_matrix()))
+}
+
+/// Like [`from_rgb`] for tightly packed RGB24 bytes. Returns `None` if `rgb`
+/// is shorter than `width * height * 3`.
+pub fn from_rgb_bytes(rgb: &[u8], width: u32, height: u32) -> Option<NV12Image<Vec<u8>>> {
+    try_from_rgb_bytes(rgb, width, height).ok()
+}
+
+/// Like [`from_rgb_bytes`], reporting a short buffer as an error.
+pub fn try_from_rgb_bytes(
+    rgb: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<NV12Image<Vec<u8>>, YuvError> {
+    let needed = width as usize * height as usize * 3;
+    if rgb.len() < needed {
+        return Err(YuvError::BufferTooSmall {
+            len: rgb.len(),
+            needed,
+        });
+    }
+    Ok(nv12_from_rgb(width, height, |x, y| {
+        let i = (y * width + x) as usize * 3;
+        [rgb[i], rgb[i + 1], rgb[i + 2]]
+    }))
+}
+
+/// Coefficients of a YUV to RGB conversion. With `Ys = y_scale * (Y -
+/// y_offset)` and U, V centered on 128:
+///
+/// ```text
+/// R = Ys + rv * V
+/// G = Ys - gu * U - gv * V
+/// B = Ys + bu * U
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorMatrix {
+    pub y_offset: f32,
+    pub y_scale: f32,
+    pub rv: f32,
+    pub gu: f32,
+    pub gv: f32,
+    pub bu: f32,
+}
+
+impl ColorMatrix {
+    /// The conversion of [`YUV::rgb`].
+    pub const DEFAULT: Self = Self::full(1.40, 0.34, 0.71, 1.77);
+    pub const BT601_FULL: Self = Self::full(1.402, 0.344, 0.714, 1.772);
+    pub const BT601_VIDEO: Self = Self::video(1.596, 0.392, 0.813, 2.017);
+    pub const BT709_FULL: Self = Self::full(1.5748, 0.1873, 0.4681, 1.8556);
+    pub const BT709_VIDEO: Self = Self::video(1.793, 0.213, 0.533, 2.112);
+
+    const fn full(rv: f32, gu: f32, gv: f32, bu: f32) -> Self {
+        Self {
+            y_offset: 0.,
+            y_scale: 1.,
+            rv,
+            gu,
+            gv,
+            bu,
+        }
+    }
+
+    /// Luma in 16-235, chroma coefficients already scaled for 16-240.
+    const fn video(rv: f32, gu: f32, gv: f32, bu: f32) -> Self {
+        Self {
+            y_offset: 16.,
+            y_scale: 255. / 219.,
+            rv,
+            gu,
+            gv,
+            bu,
+        }
+    }
+}
+
+/// Lookup tables for one [`ColorMatrix`]: the expanded luma and the four
+/// chroma contributions for every 8-bit input, 2.5KB in total.
+///
+/// Converting through the tables takes five loads and a few adds per pixel
+/// instead of multiplications, which pays off on CPUs without wide SIMD.
+/// Build them once and reuse them for every frame.
+pub struct RgbTables {
+    y: [i16; 256],
+    rv: [i16; 256],
+    gu: [i16; 256],
+    gv: [i16; 256],
+    bu: [i16; 256],
+}
+
+impl RgbTables {
+    pub fn new(matrix: &ColorMatrix) -> Self {
+        let table = |f: &dyn Fn(f32) -> f32| {
+            let mut t = [0; 256];
+            for (i, v) in t.iter_mut().enumerate() {
+                let x = f(i as f32);
+                *v = (x + if x < 0. { -0.5 } else { 0.5 }) as i16;
+            }
+            t
+        };
+        Self {
+            y: table(&|y| matrix.y_scale * (y - matrix.y_offset)),
+            rv: table(&|v| matrix.rv * (v - 128.)),
+            gu: table(&|u| matrix.gu * (u - 128.)),
+            gv: table(&|v| matrix.gv * (v - 128.)),
+            bu: table(&|u| matrix.bu * (u - 128.)),
+        }
+    }
+
+    pub fn rgb(&self, yuv: YUV) -> [u8; 3] {
+        let [y, u, v] = yuv.0.map(usize::from);
+        let y = self.y[y];
+        let channel = |c: i16| c.clamp(0, 255) as u8;
+        [
+            channel(y + self.rv[v]),
+            channel(y - self.gu[u] - self.gv[v]),
+            channel(y + self.bu[u]),
+        ]
+    }
+}
+
+/// [`to_rgb`] through precomputed tables.
+#[cfg(feature = "std")]
+pub fn to_rgb_with<T: Deref<Target = [u8]>>(img: &NV12Image<T>, tables: &RgbTables) -> RgbImage {
+    ImageBuffer::from_fn(img.width, img.height, |x, y| {
+        Rgb(tables.rgb(img.sample(x, y)))
+    })
+}
+
+/// [`to_rgb_bytes`] through precomputed tables.
+pub fn to_rgb_bytes_with<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    tables: &RgbTables,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(img.width as usize * img.height as usize * 3);
+    for y in 0..img.height {
+        for x in 0..img.width {
+            out.extend_from_slice(&tables.rgb(img.sample(x, y)));
+        }
+    }
+    out
+}
+
+/// Renders a `width`x`height` RGB source into a tightly packed NV12 image.
+/// Each chroma sample is the average of the converted pixels of its 2x2
+/// block.
+pub(crate) fn nv12_from_rgb<F>(width: u32, height: u32, mut rgb_at: F) -> NV12Image<Vec<u8>>
+where
+    F: FnMut(u32, u32) -> [u8; 3],
+{
+    nv12_from_fn(width, height, |x, y| YUV::from_rgb(rgb_at(x, y)))
+}
+
+/// Like [`nv12_from_rgb`], for a source that is already YUV.
+pub(crate) fn nv12_from_fn<F>(width: u32, height: u32, mut yuv_at: F) -> NV12Image<Vec<u8>>
+where
+    F: FnMut(u32, u32) -> YUV,
+{
+    let desc = FrameDescriptor::packed(width, height, PixelFormat::NV12);
+    let mut data = alloc::vec![0; desc.frame_size()];
+    let uv_offset = (desc.y_stride * height) as usize;
+    for by in 0..height.div_ceil(2) {
+        for bx in 0..width.div_ceil(2) {
+            let (mut u, mut v, mut n) = (0, 0, 0);
+            for y in by * 2..(by * 2 + 2).min(height) {
+                for x in bx * 2..(bx * 2 + 2).min(width) {
+                    let yuv = yuv_at(x, y);
+                    data[(y * desc.y_stride + x) as usize] = yuv.0[0];
+                    u += yuv.0[1] as u32;
+                    v += yuv.0[2] as u32;
+                    n += 1;
+                }
+            }
+            let i = uv_offset + (by * desc.uv_stride + bx * 2) as usize;
+            data[i] = ((u + n / 2) / n) as u8;
+            data[i + 1] = ((v + n / 2) / n) as u8;
+        }
+    }
+    NV12Image::from_strided(data, width, height, desc.y_stride, desc.uv_stride)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chroma_is_block_average() {
+        let img = nv12_from_rgb(2, 2, |x, _| if x == 0 { [255, 0, 0] } else { [0, 0, 255] });
+        let red = YUV::from_rgb([255, 0, 0]);
+        let blue = YUV::from_rgb([0, 0, 255]);
+        let data = img.take_data();
+        assert_eq!(data[..4], [red.0[0], blue.0[0], red.0[0], blue.0[0]]);
+        let u = (red.0[1] as u32 * 2 + blue.0[1] as u32 * 2 + 2) / 4;
+        assert_eq!(data[4], u as u8);
+    }
+
+    #[test]
+    fn tables_match_default_conversion() {
+        let tables = RgbTables::new(&ColorMatrix::DEFAULT);
+        for y in (0..=255).step_by(15) {
+            for u in (0..=255).step_by(15) {
+                let yuv = YUV([y, u, 255 - u]);
+                for (a, b) in tables.rgb(yuv).iter().zip(yuv.rgb()) {
+                    assert!((*a as i32 - b as i32).abs() <= 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rgb_bytes_round_trip() {
+        let rgb = [200, 120, 40].repeat(4);
+        let img = from_rgb_bytes(&rgb, 2, 2).unwrap();
+        assert_eq!(to_rgb_bytes(&img).len(), rgb.len());
+        assert!(from_rgb_bytes(&rgb[1..], 2, 2).is_none());
+    }
+
+    #[test]
+    fn converts_with_the_tagged_matrix() {
+        let img = NV12Image::from(vec![235, 235, 235, 235, 128, 128], 2, 2)
+            .with_color_matrix(ColorMatrix::BT709_VIDEO);
+        assert_eq!(to_rgb_bytes(&img), [255; 12]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn rgb_round_trip() {
+        let rgb = RgbImage::from_pixel(4, 2, Rgb([200, 120, 40]));
+        let back = to_rgb(&from_rgb(&rgb));
+        for (a, b) in rgb.as_raw().iter().zip(back.as_raw()) {
+            assert!((*a as i32 - *b as i32).abs() <= 3, "{} vs {}", a, b);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn renders_views() {
+        use image::{GrayImage, Luma, Rgba, RgbaImage};
+
+        let gray = GrayImage::from_pixel(3, 3, Luma([255]));
+        let img = Nv12Buffer::from_view(&gray);
+        assert_eq!(img.pixel(2, 2), YUV::from_rgb([255, 255, 255]));
+
+        let mut img = Nv12Buffer::filled(4, 4, crate::BLACK);
+        let logo = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        img.draw_view_mut(&logo, 2, 2, AlphaMode::Straight);
+        assert_eq!(img.pixel(3, 3), YUV::from_rgb([255, 0, 0]));
+        assert_eq!(img.pixel(1, 1), crate::BLACK);
+    }
+}
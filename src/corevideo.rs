@@ -0,0 +1,180 @@
+//! Access to Apple `CVPixelBuffer`s in the bi-planar 4:2:0 formats
+//! (`kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange` and `...FullRange`),
+//! as delivered by AVFoundation capture and VideoToolbox decoding.
+
+use std::os::raw::c_void;
+use std::slice;
+
+use crate::{ColorMatrix, NV12Image};
+
+#[repr(C)]
+pub struct __CVBuffer {
+    _private: [u8; 0],
+}
+
+pub type CVPixelBufferRef = *mut __CVBuffer;
+pub type CVReturn = i32;
+
+/// `'420v'`, NV12 with video range (16-235) luma.
+pub const K_CV_PIXEL_FORMAT_TYPE_420_YP_CB_CR8_BI_PLANAR_VIDEO_RANGE: u32 =
+    u32::from_be_bytes(*b"420v");
+/// `'420f'`, NV12 with full range (0-255) luma.
+pub const K_CV_PIXEL_FORMAT_TYPE_420_YP_CB_CR8_BI_PLANAR_FULL_RANGE: u32 =
+    u32::from_be_bytes(*b"420f");
+
+const K_CV_PIXEL_BUFFER_LOCK_READ_ONLY: u64 = 1;
+
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+    fn CVPixelBufferLockBaseAddress(pixel_buffer: CVPixelBufferRef, lock_flags: u64) -> CVReturn;
+    fn CVPixelBufferUnlockBaseAddress(
+        pixel_buffer: CVPixelBufferRef,
+        unlock_flags: u64,
+    ) -> CVReturn;
+    fn CVPixelBufferGetPixelFormatType(pixel_buffer: CVPixelBufferRef) -> u32;
+    fn CVPixelBufferGetWidth(pixel_buffer: CVPixelBufferRef) -> usize;
+    fn CVPixelBufferGetHeight(pixel_buffer: CVPixelBufferRef) -> usize;
+    fn CVPixelBufferGetBaseAddressOfPlane(
+        pixel_buffer: CVPixelBufferRef,
+        plane_index: usize,
+    ) -> *mut c_void;
+    fn CVPixelBufferGetBytesPerRowOfPlane(
+        pixel_buffer: CVPixelBufferRef,
+        plane_index: usize,
+    ) -> usize;
+}
+
+struct Layout {
+    start: *mut u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    y_stride: u32,
+    uv_stride: u32,
+    uv_offset: u32,
+}
+
+/// A `CVPixelBuffer` with its base address locked. The lock is released on
+/// drop.
+pub struct LockedPixelBuffer {
+    buffer: CVPixelBufferRef,
+    flags: u64,
+}
+
+impl LockedPixelBuffer {
+    /// Locks `buffer` for reading, or for reading and writing if
+    /// `read_only` is false. Returns the `CVReturn` code if locking fails.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must be a valid `CVPixelBufferRef` that stays retained while
+    /// the returned lock is alive.
+    pub unsafe fn lock(buffer: CVPixelBufferRef, read_only: bool) -> Result<Self, CVReturn> {
+        let flags = if read_only {
+            K_CV_PIXEL_BUFFER_LOCK_READ_ONLY
+        } else {
+            0
+        };
+        match CVPixelBufferLockBaseAddress(buffer, flags) {
+            0 => Ok(Self { buffer, flags }),
+            code => Err(code),
+        }
+    }
+
+    pub fn pixel_format(&self) -> u32 {
+        unsafe { CVPixelBufferGetPixelFormatType(self.buffer) }
+    }
+
+    /// Whether the buffer is one of the bi-planar 4:2:0 formats.
+    pub fn is_nv12(&self) -> bool {
+        matches!(
+            self.pixel_format(),
+            K_CV_PIXEL_FORMAT_TYPE_420_YP_CB_CR8_BI_PLANAR_VIDEO_RANGE
+                | K_CV_PIXEL_FORMAT_TYPE_420_YP_CB_CR8_BI_PLANAR_FULL_RANGE
+        )
+    }
+
+    /// Whether luma uses the full 0-255 range rather than video range.
+    pub fn is_full_range(&self) -> bool {
+        self.pixel_format() == K_CV_PIXEL_FORMAT_TYPE_420_YP_CB_CR8_BI_PLANAR_FULL_RANGE
+    }
+
+    /// The matrix the samples are encoded with: `'420v'` buffers are video
+    /// range BT.601, `'420f'` buffers full range.
+    pub fn color_matrix(&self) -> ColorMatrix {
+        if self.is_full_range() {
+            ColorMatrix::DEFAULT
+        } else {
+            ColorMatrix::BT601_VIDEO
+        }
+    }
+
+    /// The byte range spanning both planes, and the offset of the UV plane
+    /// in it. Both planes of a bi-planar buffer live in one allocation.
+    fn layout(&self) -> Option<Layout> {
+        if !self.is_nv12() {
+            return None;
+        }
+        unsafe {
+            let width = CVPixelBufferGetWidth(self.buffer) as u32;
+            let height = CVPixelBufferGetHeight(self.buffer) as u32;
+            let y = CVPixelBufferGetBaseAddressOfPlane(self.buffer, 0) as *mut u8;
+            let uv = CVPixelBufferGetBaseAddressOfPlane(self.buffer, 1) as *mut u8;
+            let y_stride = CVPixelBufferGetBytesPerRowOfPlane(self.buffer, 0);
+            let uv_stride = CVPixelBufferGetBytesPerRowOfPlane(self.buffer, 1);
+            if y.is_null() || uv.is_null() {
+                return None;
+            }
+            let uv_offset = (uv as usize).checked_sub(y as usize)?;
+            if uv_offset < y_stride * height as usize {
+                return None;
+            }
+            let len = uv_offset + uv_stride * height.div_ceil(2) as usize;
+            Some(Layout {
+                start: y,
+                len,
+                width,
+                height,
+                y_stride: y_stride as u32,
+                uv_stride: uv_stride as u32,
+                uv_offset: uv_offset as u32,
+            })
+        }
+    }
+
+    /// Views the buffer as an NV12 image tagged with its
+    /// [`color_matrix`](Self::color_matrix), or returns `None` if it is not
+    /// in a bi-planar 4:2:0 format.
+    pub fn image(&self) -> Option<NV12Image<&[u8]>> {
+        let l = self.layout()?;
+        let data = unsafe { slice::from_raw_parts(l.start as *const u8, l.len) };
+        Some(
+            NV12Image::from_strided(data, l.width, l.height, l.y_stride, l.uv_stride)
+                .with_uv_offset(l.uv_offset)
+                .with_color_matrix(self.color_matrix()),
+        )
+    }
+
+    /// Like [`image`](Self::image), for drawing in place. Returns `None` if
+    /// the buffer was locked read-only.
+    pub fn image_mut(&mut self) -> Option<NV12Image<&mut [u8]>> {
+        if self.flags & K_CV_PIXEL_BUFFER_LOCK_READ_ONLY != 0 {
+            return None;
+        }
+        let l = self.layout()?;
+        let data = unsafe { slice::from_raw_parts_mut(l.start, l.len) };
+        Some(
+            NV12Image::from_strided(data, l.width, l.height, l.y_stride, l.uv_stride)
+                .with_uv_offset(l.uv_offset)
+                .with_color_matrix(self.color_matrix()),
+        )
+    }
+}
+
+impl Drop for LockedPixelBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            CVPixelBufferUnlockBaseAddress(self.buffer, self.flags);
+        }
+    }
+}
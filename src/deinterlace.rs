@@ -0,0 +1,128 @@
+//! Deinterlacing of frames that hold two interlaced fields, as delivered by
+//! analog capture cards.
+//!
+//! The top field is made of the even rows and the bottom field of the odd
+//! rows. In interlaced 4:2:0 each field has its own chroma, so even chroma
+//! rows belong to the top field and odd ones to the bottom field.
+
+use core::ops::Deref;
+
+use alloc::vec;
+
+use crate::{FrameDescriptor, NV12Image, Nv12Buffer, PixelFormat};
+
+/// How [`deinterlace`] combines the two fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Keeps the top field and interpolates the rows of the bottom field
+    /// from the rows above and below. Halves the vertical resolution but
+    /// leaves no combing.
+    Bob,
+    /// Keeps both fields as they are. Sharp for still content, combed where
+    /// things move.
+    Weave,
+    /// Blends every row with its neighbours, `(above + 2 * row + below) / 4`,
+    /// which smears motion over both fields instead of combing.
+    LinearBlend,
+}
+
+/// Filters `rows` rows read through `src` into `dst`, rows `stride` bytes
+/// apart.
+fn deinterlace_plane<'a, F>(rows: u32, src: F, dst: &mut [u8], stride: usize, method: Method)
+where
+    F: Fn(u32) -> &'a [u8],
+{
+    for r in 0..rows {
+        let out = &mut dst[r as usize * stride..];
+        match method {
+            Method::Weave => {
+                let row = src(r);
+                out[..row.len()].copy_from_slice(row);
+            }
+            Method::Bob if r % 2 == 1 && r + 1 < rows => {
+                let (above, below) = (src(r - 1), src(r + 1));
+                for ((o, &a), &b) in out.iter_mut().zip(above).zip(below) {
+                    *o = (u16::from(a) + u16::from(b)).div_ceil(2) as u8;
+                }
+            }
+            Method::Bob => {
+                let row = src(r - r % 2);
+                out[..row.len()].copy_from_slice(row);
+            }
+            Method::LinearBlend => {
+                // Mirrored at the edges, so the neighbours always come from
+                // the other field.
+                let above = if r > 0 { r - 1 } else { 1.min(rows - 1) };
+                let below = if r + 1 < rows {
+                    r + 1
+                } else {
+                    r.saturating_sub(1)
+                };
+                let rows = src(above).iter().zip(src(r)).zip(src(below));
+                for (o, ((&a, &b), &c)) in out.iter_mut().zip(rows) {
+                    let sum = u16::from(a) + 2 * u16::from(b) + u16::from(c);
+                    *o = ((sum + 2) / 4) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// A progressive copy of the interlaced frame `img`, tightly packed and with
+/// the same color matrix. Luma and chroma are filtered per plane, so the
+/// chroma of one field is only mixed with that of the other where `method`
+/// mixes the luma too.
+pub fn deinterlace<T: Deref<Target = [u8]>>(img: &NV12Image<T>, method: Method) -> Nv12Buffer {
+    let (width, height) = (img.width, img.height);
+    let desc = FrameDescriptor::packed(width, height, PixelFormat::NV12);
+    let data = vec![0; desc.frame_size()];
+    let mut out = NV12Image::from_strided(data, width, height, desc.y_stride, desc.uv_stride)
+        .with_color_matrix(img.color_matrix);
+    let (luma, chroma) = out.split_planes_mut();
+    let y_stride = desc.y_stride as usize;
+    deinterlace_plane(height, |r| img.y_row(r), luma, y_stride, method);
+    let uv = img.uv_plane();
+    let uv_width = width.div_ceil(2) as usize * 2;
+    let uv_row = |r: u32| {
+        let start = (r * img.uv_stride) as usize;
+        &uv[start..start + uv_width]
+    };
+    let uv_stride = desc.uv_stride as usize;
+    deinterlace_plane(height.div_ceil(2), uv_row, chroma, uv_stride, method);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Nv12Buffer {
+        // Top field rows at 100, bottom field rows at 200, and the same for
+        // the chroma of each field.
+        let mut img = Nv12Buffer::filled(4, 4, crate::BLACK);
+        for y in 0..4 {
+            img.y_row_mut(y).fill(if y % 2 == 1 { 200 } else { 100 });
+        }
+        let (_, uv) = img.split_planes_mut();
+        uv[..4].fill(100);
+        uv[4..].fill(200);
+        img
+    }
+
+    #[test]
+    fn methods() {
+        let img = fields();
+        let weave = deinterlace(&img, Method::Weave);
+        assert_eq!(weave.as_bytes(), img.as_bytes());
+
+        let bob = deinterlace(&img, Method::Bob);
+        let column: Vec<u8> = (0..4).map(|y| bob.y_row(y)[0]).collect();
+        assert_eq!(column, [100, 100, 100, 100]);
+        assert_eq!(bob.uv_row(1), [100; 4]);
+
+        let blend = deinterlace(&img, Method::LinearBlend);
+        let column: Vec<u8> = (0..4).map(|y| blend.y_row(y)[0]).collect();
+        assert_eq!(column, [150, 150, 150, 150]);
+        assert_eq!((blend.uv_row(0)[0], blend.uv_row(1)[0]), (150, 150));
+    }
+}
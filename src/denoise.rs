@@ -0,0 +1,136 @@
+//! Temporal noise reduction by averaging successive frames.
+
+use core::ops::DerefMut;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::NV12Image;
+
+/// Blends each frame of a stream with a running average of the previous
+/// ones, which removes sensor noise from static parts of low-light footage.
+///
+/// The average is kept per sample with 8 fractional bits. Where a sample
+/// differs from the average, the history counts less, and beyond the motion
+/// threshold not at all, so moving content does not leave trails.
+pub struct TemporalDenoiser {
+    width: u32,
+    height: u32,
+    /// Weight of the history, in 1/256.
+    strength: u32,
+    motion_threshold: u8,
+    luma: Vec<u16>,
+    chroma: Vec<u16>,
+    primed: bool,
+}
+
+impl TemporalDenoiser {
+    /// A denoiser for `width`x`height` frames with a strength of 0.75 and a
+    /// motion threshold of 24.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            strength: 192,
+            motion_threshold: 24,
+            luma: vec![0; (width * height) as usize],
+            chroma: vec![0; (width.div_ceil(2) * 2 * height.div_ceil(2)) as usize],
+            primed: false,
+        }
+    }
+
+    /// The weight of the history for static samples, from 0 (no filtering)
+    /// to 1 (frozen).
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = (strength.clamp(0.0, 1.0) * 256.0) as u32;
+        self
+    }
+
+    /// The difference from the average at which a sample is taken to be
+    /// moving and passed through unfiltered.
+    pub fn with_motion_threshold(mut self, threshold: u8) -> Self {
+        self.motion_threshold = threshold;
+        self
+    }
+
+    /// Forgets the history, e.g. after a scene cut.
+    pub fn reset(&mut self) {
+        self.primed = false;
+    }
+
+    /// Blends one sample into its running average and returns the new
+    /// average.
+    fn blend(&self, average: &mut u16, sample: u8) -> u8 {
+        let current = u32::from(sample) << 8;
+        let diff = (u32::from(*average)).abs_diff(current) >> 8;
+        let threshold = u32::from(self.motion_threshold);
+        let weight = if diff >= threshold {
+            0
+        } else {
+            self.strength * (threshold - diff) / threshold
+        };
+        let blended = (u32::from(*average) * weight + current * (256 - weight)) / 256;
+        *average = blended as u16;
+        ((blended + 128) >> 8).min(255) as u8
+    }
+
+    /// Denoises `frame` in place and adds it to the history. Panics if the
+    /// frame is not the size the denoiser was created for.
+    pub fn process_mut<T: DerefMut<Target = [u8]>>(&mut self, frame: &mut NV12Image<T>) {
+        assert_eq!(
+            (frame.width, frame.height),
+            (self.width, self.height),
+            "frame does not match the denoiser size"
+        );
+        let (width, height) = (self.width as usize, self.height as usize);
+        let uv_width = self.width.div_ceil(2) as usize * 2;
+        let (y_stride, uv_stride) = (frame.y_stride as usize, frame.uv_stride as usize);
+        let (luma, chroma) = frame.split_planes_mut();
+        let mut history_luma = core::mem::take(&mut self.luma);
+        let mut history_chroma = core::mem::take(&mut self.chroma);
+        let planes = [
+            (luma, &mut history_luma, width, height, y_stride),
+            (
+                chroma,
+                &mut history_chroma,
+                uv_width,
+                height.div_ceil(2),
+                uv_stride,
+            ),
+        ];
+        for (plane, history, row_len, rows, stride) in planes {
+            for row in 0..rows {
+                let samples = &mut plane[row * stride..row * stride + row_len];
+                let averages = &mut history[row * row_len..(row + 1) * row_len];
+                for (sample, average) in samples.iter_mut().zip(averages) {
+                    if self.primed {
+                        *sample = self.blend(average, *sample);
+                    } else {
+                        *average = u16::from(*sample) << 8;
+                    }
+                }
+            }
+        }
+        self.luma = history_luma;
+        self.chroma = history_chroma;
+        self.primed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Nv12Buffer;
+
+    #[test]
+    fn averages_static_samples_and_keeps_motion() {
+        let mut denoiser = TemporalDenoiser::new(2, 2).with_strength(0.5);
+        let mut frame = Nv12Buffer::filled(2, 2, crate::YUV([100, 128, 128]));
+        denoiser.process_mut(&mut frame);
+        frame.y_row_mut(0).copy_from_slice(&[110, 200]);
+        denoiser.process_mut(&mut frame);
+        // 10 below the threshold of 24 weighs the history by 0.5 * 14 / 24.
+        assert_eq!(frame.y_row(0), [107, 200]);
+        assert_eq!(frame.uv_row(0), [128, 128]);
+    }
+}
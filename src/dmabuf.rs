@@ -0,0 +1,91 @@
+//! CPU access to Linux dma-buf objects, such as surfaces exported by VAAPI
+//! (`vaExportSurfaceHandle`) or V4L2 (`VIDIOC_EXPBUF`).
+//!
+//! A [`DmaBuf`] derefs to the mapped bytes, so it can back an
+//! [`NV12Image`](crate::NV12Image) directly:
+//!
+//! ```ignore
+//! let buf = DmaBuf::map(fd, len)?;
+//! let img = NV12Image::from_strided(buf, width, height, pitch[0], pitch[1])
+//!     .with_uv_offset(offset[1]);
+//! ```
+//!
+//! Surfaces exported as one object per plane are wrapped with
+//! [`NV12Image::from_planes`](crate::NV12Image::from_planes) over two maps.
+
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::io::{AsRawFd, BorrowedFd};
+
+use memmap2::{MmapMut, MmapOptions};
+
+const DMA_BUF_SYNC_RW: u64 = 3;
+const DMA_BUF_SYNC_START: u64 = 0;
+const DMA_BUF_SYNC_END: u64 = 4;
+/// `_IOW('b', 0, struct dma_buf_sync)`
+const DMA_BUF_IOCTL_SYNC: u64 = 0x4008_6200;
+
+#[repr(C)]
+struct DmaBufSync {
+    flags: u64,
+}
+
+/// A dma-buf mapped for reading and writing.
+///
+/// The buffer is synchronized for CPU access while it is mapped: mapping
+/// issues `DMA_BUF_IOCTL_SYNC` with `DMA_BUF_SYNC_START` and dropping it
+/// `DMA_BUF_SYNC_END`, so hand the buffer back to the device (e.g. the
+/// encoder) only after the map is dropped.
+pub struct DmaBuf<'fd> {
+    map: MmapMut,
+    fd: BorrowedFd<'fd>,
+}
+
+impl<'fd> DmaBuf<'fd> {
+    /// Maps the first `len` bytes of the dma-buf `fd`.
+    pub fn map(fd: BorrowedFd<'fd>, len: usize) -> io::Result<Self> {
+        // SAFETY: a dma-buf mapping stays valid until it is unmapped, and
+        // concurrent device access is fenced by the sync ioctls.
+        let map = unsafe { MmapOptions::new().len(len).map_mut(fd.as_raw_fd())? };
+        let buf = Self { map, fd };
+        buf.sync(DMA_BUF_SYNC_START)?;
+        Ok(buf)
+    }
+
+    fn sync(&self, stage: u64) -> io::Result<()> {
+        let sync = DmaBufSync {
+            flags: stage | DMA_BUF_SYNC_RW,
+        };
+        loop {
+            match unsafe { libc::ioctl(self.fd.as_raw_fd(), DMA_BUF_IOCTL_SYNC as _, &sync) } {
+                0 => return Ok(()),
+                _ => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Deref for DmaBuf<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl DerefMut for DmaBuf<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.map
+    }
+}
+
+impl Drop for DmaBuf<'_> {
+    fn drop(&mut self) {
+        let _ = self.sync(DMA_BUF_SYNC_END);
+    }
+}
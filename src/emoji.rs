@@ -0,0 +1,270 @@
+//! Color emoji burnt into NV12 frames.
+//!
+//! Glyphs are rendered to RGBA and blended through
+//! [`overlay_rgba_mut`](crate::overlay::overlay_rgba_mut). Bitmap fonts (PNG
+//! strikes in `CBDT` or `sbix` tables, e.g. Noto Color Emoji or Apple Color
+//! Emoji) are scaled from the closest strike. Layered `COLR` version 0 fonts
+//! have each layer rasterized as an outline and filled with its `CPAL`
+//! color; `COLR` version 1 gradients are not supported.
+
+use std::collections::HashMap;
+use std::ops::DerefMut;
+
+use image::imageops::{resize, FilterType};
+use image::{ImageFormat, Rgba, RgbaImage};
+use rusttype::{point, Font, GlyphId, Scale};
+use ttf_parser::{Face, RasterImageFormat, Tag};
+
+use crate::overlay::{overlay_rgba_mut, AlphaMode};
+use crate::NV12Image;
+
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The `(glyph, palette index)` layers of `glyph` in a `COLR` version 0
+/// table, bottom first.
+fn color_layers(colr: &[u8], glyph: u16) -> Option<Vec<(u16, u16)>> {
+    let bases = be16(colr, 2)? as usize;
+    let base_offset = be32(colr, 4)? as usize;
+    let layer_offset = be32(colr, 8)? as usize;
+    let record = |i: usize| be16(colr, base_offset + i * 6);
+    // Base glyph records are sorted by glyph id.
+    let (mut low, mut high) = (0, bases);
+    while low < high {
+        let mid = (low + high) / 2;
+        match record(mid)? {
+            id if id < glyph => low = mid + 1,
+            id if id > glyph => high = mid,
+            _ => {
+                let first = be16(colr, base_offset + mid * 6 + 2)? as usize;
+                let count = be16(colr, base_offset + mid * 6 + 4)? as usize;
+                return (first..first + count)
+                    .map(|i| {
+                        let at = layer_offset + i * 4;
+                        Some((be16(colr, at)?, be16(colr, at + 2)?))
+                    })
+                    .collect();
+            }
+        }
+    }
+    None
+}
+
+/// Color `index` of the first palette of a `CPAL` table.
+fn palette_color(cpal: &[u8], index: u16) -> Option<Rgba<u8>> {
+    if index >= be16(cpal, 2)? {
+        return None;
+    }
+    let records = be32(cpal, 8)? as usize;
+    let first = be16(cpal, 12)? as usize;
+    let at = records + (first + index as usize) * 4;
+    let bgra = cpal.get(at..at + 4)?;
+    Some(Rgba([bgra[2], bgra[1], bgra[0], bgra[3]]))
+}
+
+/// Composites `color` with `coverage` over `dst`, in straight alpha.
+fn blend_over(dst: &mut Rgba<u8>, color: Rgba<u8>, coverage: f32) {
+    let src_a = color.0[3] as f32 / 255.0 * coverage;
+    let dst_a = dst.0[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    if out_a <= 0.0 {
+        return;
+    }
+    for c in 0..3 {
+        let value = (color.0[c] as f32 * src_a + dst.0[c] as f32 * dst_a * (1.0 - src_a)) / out_a;
+        dst.0[c] = value.round() as u8;
+    }
+    dst.0[3] = (out_a * 255.0).round() as u8;
+}
+
+/// An emoji rendered at one size, with the offset of its top-left corner
+/// from the top-left corner of the line.
+struct Emoji {
+    image: RgbaImage,
+    left: i64,
+    top: i64,
+}
+
+/// Draws color emoji from one font onto NV12 images, rendering each emoji
+/// only once per size.
+pub struct EmojiRenderer<'f> {
+    face: Face<'f>,
+    outlines: Font<'f>,
+    cache: HashMap<(char, u32), Option<Emoji>>,
+}
+
+impl<'f> EmojiRenderer<'f> {
+    /// Parses the first font of `data`. Returns `None` if it cannot be
+    /// parsed.
+    pub fn new(data: &'f [u8]) -> Option<Self> {
+        Some(Self {
+            face: Face::parse(data, 0).ok()?,
+            outlines: Font::try_from_bytes(data)?,
+            cache: HashMap::new(),
+        })
+    }
+
+    fn table(&self, tag: &[u8; 4]) -> Option<&'f [u8]> {
+        self.face.raw_face().table(Tag::from_bytes(tag))
+    }
+
+    /// Whether the font has a color glyph for `c`.
+    pub fn has_emoji(&self, c: char) -> bool {
+        let Some(glyph) = self.face.glyph_index(c) else {
+            return false;
+        };
+        self.face.glyph_raster_image(glyph, u16::MAX).is_some()
+            || self
+                .table(b"COLR")
+                .and_then(|colr| color_layers(colr, glyph.0))
+                .is_some()
+    }
+
+    /// Number of emoji in the cache, including characters without one.
+    pub fn cached_emoji(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// The emoji for `c` scaled from the bitmap strike closest to `size`.
+    fn render_bitmap(&self, glyph: ttf_parser::GlyphId, size: f32) -> Option<Emoji> {
+        let ppem = size.round().clamp(1.0, u16::MAX as f32) as u16;
+        let raster = self.face.glyph_raster_image(glyph, ppem)?;
+        if raster.format != RasterImageFormat::PNG {
+            return None;
+        }
+        let strike = image::load_from_memory_with_format(raster.data, ImageFormat::Png)
+            .ok()?
+            .to_rgba8();
+        let factor = size / raster.pixels_per_em as f32;
+        let width = (strike.width() as f32 * factor).round().max(1.0) as u32;
+        let height = (strike.height() as f32 * factor).round().max(1.0) as u32;
+        let units = size / self.face.units_per_em() as f32;
+        let ascent = self.face.ascender() as f32 * units;
+        let bottom = raster.y as f32 * factor;
+        Some(Emoji {
+            image: resize(&strike, width, height, FilterType::Triangle),
+            left: (raster.x as f32 * factor).round() as i64,
+            top: (ascent - bottom).round() as i64 - height as i64,
+        })
+    }
+
+    /// The emoji for `c` with its `COLR` layers filled in their palette
+    /// colors. Layers with the foreground color index are black.
+    fn render_layers(&self, glyph: ttf_parser::GlyphId, size: f32) -> Option<Emoji> {
+        let layers = color_layers(self.table(b"COLR")?, glyph.0)?;
+        let cpal = self.table(b"CPAL");
+        let scale = Scale::uniform(size);
+        let ascent = self.outlines.v_metrics(scale).ascent;
+        let placed: Vec<_> = layers
+            .iter()
+            .filter_map(|&(id, index)| {
+                let layer = self
+                    .outlines
+                    .glyph(GlyphId(id))
+                    .scaled(scale)
+                    .positioned(point(0.0, ascent));
+                let bb = layer.pixel_bounding_box()?;
+                let color = match index {
+                    0xffff => Rgba([0, 0, 0, 255]),
+                    index => palette_color(cpal?, index)?,
+                };
+                Some((layer, bb, color))
+            })
+            .collect();
+        let left = placed.iter().map(|(_, bb, _)| bb.min.x).min()?;
+        let top = placed.iter().map(|(_, bb, _)| bb.min.y).min()?;
+        let right = placed.iter().map(|(_, bb, _)| bb.max.x).max()?;
+        let bottom = placed.iter().map(|(_, bb, _)| bb.max.y).max()?;
+        let mut image = RgbaImage::new((right - left) as u32, (bottom - top) as u32);
+        for (layer, bb, color) in &placed {
+            let (dx, dy) = ((bb.min.x - left) as u32, (bb.min.y - top) as u32);
+            layer.draw(|x, y, coverage| {
+                blend_over(image.get_pixel_mut(x + dx, y + dy), *color, coverage);
+            });
+        }
+        Some(Emoji {
+            image,
+            left: left as i64,
+            top: top as i64,
+        })
+    }
+
+    fn emoji(&mut self, c: char, size: f32) -> Option<&Emoji> {
+        if !self.cache.contains_key(&(c, size.to_bits())) {
+            let emoji = self.face.glyph_index(c).and_then(|glyph| {
+                self.render_bitmap(glyph, size)
+                    .or_else(|| self.render_layers(glyph, size))
+            });
+            self.cache.insert((c, size.to_bits()), emoji);
+        }
+        self.cache[&(c, size.to_bits())].as_ref()
+    }
+
+    /// The RGBA rendering of the emoji for `c`, `size` pixels per em, or
+    /// `None` if the font has no color glyph for it.
+    pub fn render(&mut self, c: char, size: f32) -> Option<&RgbaImage> {
+        self.emoji(c, size).map(|emoji| &emoji.image)
+    }
+
+    /// Draws the emoji for `c`, `size` pixels per em, on a line whose
+    /// top-left corner is at `(x, y)`, like
+    /// [`TextRenderer::draw_text_mut`](crate::text::TextRenderer::draw_text_mut)
+    /// places text. Returns `false`, drawing nothing, if the font has no
+    /// color glyph for `c`.
+    pub fn draw_emoji_mut<T: DerefMut<Target = [u8]>>(
+        &mut self,
+        img: &mut NV12Image<T>,
+        c: char,
+        x: i32,
+        y: i32,
+        size: f32,
+    ) -> bool {
+        match self.emoji(c, size) {
+            Some(emoji) => {
+                let (left, top) = (x as i64 + emoji.left, y as i64 + emoji.top);
+                overlay_rgba_mut(img, &emoji.image, left, top, AlphaMode::Straight);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_color_layers_and_palette() {
+        let mut colr = vec![0, 0, 0, 2, 0, 0, 0, 14, 0, 0, 0, 26, 0, 3];
+        colr.extend([0, 2, 0, 0, 0, 1, 0, 5, 0, 1, 0, 2]);
+        colr.extend([0, 9, 0, 0, 0, 7, 0, 0, 0, 8, 0xff, 0xff]);
+        assert_eq!(color_layers(&colr, 5), Some(vec![(7, 0), (8, 0xffff)]));
+        assert_eq!(color_layers(&colr, 2), Some(vec![(9, 0)]));
+        assert_eq!(color_layers(&colr, 4), None);
+
+        let cpal = [
+            0, 0, 0, 1, 0, 1, 0, 1, 0, 0, 0, 14, 0, 0, 0x10, 0x20, 0x30, 0xff,
+        ];
+        assert_eq!(
+            palette_color(&cpal, 0),
+            Some(Rgba([0x30, 0x20, 0x10, 0xff]))
+        );
+        assert_eq!(palette_color(&cpal, 1), None);
+
+        let mut pixel = Rgba([0, 0, 255, 255]);
+        blend_over(&mut pixel, Rgba([255, 0, 0, 255]), 0.5);
+        assert_eq!(pixel, Rgba([128, 0, 128, 255]));
+    }
+}
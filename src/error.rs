@@ -0,0 +1,74 @@
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+use crate::PixelFormat;
+
+/// Errors of the fallible `try_` constructors and accessors, for callers
+/// that cannot afford the panics of their plain counterparts.
+///
+/// With `std` this is a `thiserror` error. `thiserror` needs `std`, so
+/// without it `Display` falls back to the `Debug` form.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum YuvError {
+    /// A buffer cannot hold all rows of its plane. Lengths count samples,
+    /// which are bytes for images of `u8`.
+    #[cfg_attr(
+        feature = "std",
+        error("buffer of {len} bytes is smaller than {needed}")
+    )]
+    BufferTooSmall { len: usize, needed: usize },
+    /// An output buffer does not have exactly the length of the result.
+    #[cfg_attr(
+        feature = "std",
+        error("buffer of {len} samples is not the {expected} expected")
+    )]
+    LengthMismatch { len: usize, expected: usize },
+    /// A row stride is shorter than one row of samples.
+    #[cfg_attr(feature = "std", error("stride {stride} is less than {min}"))]
+    StrideTooSmall { stride: u32, min: u32 },
+    /// The UV plane starts inside the Y plane.
+    #[cfg_attr(
+        feature = "std",
+        error("UV plane at {uv_offset} overlaps the Y plane of {y_len} bytes")
+    )]
+    PlanesOverlap { uv_offset: usize, y_len: usize },
+    /// A pixel coordinate is outside the image.
+    #[cfg_attr(
+        feature = "std",
+        error("pixel {:?} is out of bounds {:?}", (x, y), (width, height))
+    )]
+    OutOfBounds {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// A row of a plane reaches past the end of its buffer, as detected by
+    /// the `strict` checks. `start..end` are the offsets of the row in the
+    /// buffer of `len` samples.
+    #[cfg_attr(
+        feature = "std",
+        error("{plane} plane row {row} spans {start}..{end} of a buffer of {len}")
+    )]
+    RowOutOfRange {
+        plane: &'static str,
+        row: u32,
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+    /// The operation does not support this pixel format.
+    #[cfg_attr(feature = "std", error("unsupported format {0:?}"))]
+    UnsupportedFormat(PixelFormat),
+    #[cfg(feature = "std")]
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(not(feature = "std"))]
+impl fmt::Display for YuvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,257 @@
+//! C interface for drawing on NV12 buffers owned by the caller.
+//!
+//! The declarations are in `include/yuvimg.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/yuvimg.h`. Build a
+//! linkable library with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or
+//! `cdylib`).
+//!
+//! Every function returns `0` on success and `-1` if an argument is invalid,
+//! e.g. a null pointer or a stride shorter than a row of the frame. Panics
+//! are caught at the boundary and also reported as `-1`.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use image::{GenericImageView, Rgba};
+use imageproc::drawing::{draw_hollow_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use rusttype::{Font, Scale};
+
+use crate::overlay::{overlay_rgba_mut, AlphaMode};
+use crate::{FrameDescriptor, NV12Image, Nv12ImageBuilder, PixelFormat, YUV};
+
+/// An NV12 frame in caller memory: `height` rows of `y_stride` bytes of
+/// luma, directly followed by `height.div_ceil(2)` rows of `uv_stride` bytes
+/// of interleaved chroma. `uv_stride` must be at least `width.div_ceil(2) * 2`
+/// so that odd widths keep their last U, V pair.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct YuvFrame {
+    pub data: *mut u8,
+    pub width: u32,
+    pub height: u32,
+    pub y_stride: u32,
+    pub uv_stride: u32,
+}
+
+/// A parsed font, created with [`yuv_font_new`].
+pub struct YuvFont(Font<'static>);
+
+unsafe fn frame_image<'a>(frame: &YuvFrame) -> Option<NV12Image<&'a mut [u8]>> {
+    if frame.data.is_null() {
+        return None;
+    }
+    let len = FrameDescriptor {
+        width: frame.width,
+        height: frame.height,
+        y_stride: frame.y_stride,
+        uv_stride: frame.uv_stride,
+        format: PixelFormat::NV12,
+    }
+    .frame_size();
+    let data = slice::from_raw_parts_mut(frame.data, len);
+    Nv12ImageBuilder::new(frame.width, frame.height)
+        .y_stride(frame.y_stride)
+        .uv_stride(frame.uv_stride)
+        .build(data)
+        .ok()
+}
+
+/// Runs `f`, turning a panic into `err` so it cannot unwind into C.
+fn guard<R>(err: R, f: impl FnOnce() -> R) -> R {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(err)
+}
+
+/// Draws a one pixel wide rectangle outline.
+///
+/// # Safety
+///
+/// `frame` must describe a valid, writable NV12 buffer.
+#[no_mangle]
+pub unsafe extern "C" fn yuv_draw_rect(
+    frame: YuvFrame,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    color_y: u8,
+    color_u: u8,
+    color_v: u8,
+) -> c_int {
+    guard(-1, || {
+        let mut img = match frame_image(&frame) {
+            Some(img) if width > 0 && height > 0 => img,
+            _ => return -1,
+        };
+        let rect = Rect::at(x, y).of_size(width, height);
+        draw_hollow_rect_mut(&mut img, rect, YUV([color_y, color_u, color_v]));
+        0
+    })
+}
+
+/// Parses a TrueType or OpenType font from `len` bytes at `data`. The bytes
+/// are copied. Returns null if the font cannot be parsed.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn yuv_font_new(data: *const u8, len: usize) -> *mut YuvFont {
+    guard(std::ptr::null_mut(), || {
+        if data.is_null() {
+            return std::ptr::null_mut();
+        }
+        let bytes = slice::from_raw_parts(data, len).to_vec();
+        match Font::try_from_vec(bytes) {
+            Some(font) => Box::into_raw(Box::new(YuvFont(font))),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Frees a font returned by [`yuv_font_new`]. Null is ignored.
+///
+/// # Safety
+///
+/// `font` must come from [`yuv_font_new`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn yuv_font_free(font: *mut YuvFont) {
+    guard((), || {
+        if !font.is_null() {
+            drop(Box::from_raw(font));
+        }
+    })
+}
+
+/// Draws the NUL-terminated UTF-8 string `text` with its top-left corner at
+/// `(x, y)` and a glyph height of `scale` pixels.
+///
+/// # Safety
+///
+/// `frame` must describe a valid, writable NV12 buffer, `font` must come
+/// from [`yuv_font_new`] and `text` must be NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn yuv_draw_text_utf8(
+    frame: YuvFrame,
+    font: *const YuvFont,
+    x: i32,
+    y: i32,
+    scale: f32,
+    text: *const c_char,
+    color_y: u8,
+    color_u: u8,
+    color_v: u8,
+) -> c_int {
+    guard(-1, || {
+        if font.is_null() || text.is_null() {
+            return -1;
+        }
+        let text = match CStr::from_ptr(text).to_str() {
+            Ok(text) => text,
+            Err(_) => return -1,
+        };
+        let mut img = match frame_image(&frame) {
+            Some(img) => img,
+            None => return -1,
+        };
+        draw_text_mut(
+            &mut img,
+            YUV([color_y, color_u, color_v]),
+            x,
+            y,
+            Scale::uniform(scale),
+            &(*font).0,
+            text,
+        );
+        0
+    })
+}
+
+struct RgbaView<'a> {
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+}
+
+impl GenericImageView for RgbaView<'_> {
+    type Pixel = Rgba<u8>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        (0, 0, self.width, self.height)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        let i = y as usize * self.stride + x as usize * 4;
+        Rgba([
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ])
+    }
+}
+
+/// Alpha-blends a straight (non-premultiplied) RGBA image of
+/// `width`x`height` pixels with rows of `stride` bytes onto `frame`, with
+/// its top-left corner at `(x, y)`.
+///
+/// # Safety
+///
+/// `frame` must describe a valid, writable NV12 buffer and `rgba` must point
+/// to `height` rows of `stride` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn yuv_overlay_rgba(
+    frame: YuvFrame,
+    rgba: *const u8,
+    width: u32,
+    height: u32,
+    stride: u32,
+    x: i32,
+    y: i32,
+) -> c_int {
+    guard(-1, || {
+        if rgba.is_null() || (stride as u64) < width as u64 * 4 {
+            return -1;
+        }
+        let mut img = match frame_image(&frame) {
+            Some(img) => img,
+            None => return -1,
+        };
+        let top = RgbaView {
+            data: slice::from_raw_parts(rgba, stride as usize * height as usize),
+            width,
+            height,
+            stride: stride as usize,
+        };
+        overlay_rgba_mut(&mut img, &top, x as i64, y as i64, AlphaMode::Straight);
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_short_chroma_rows() {
+        let mut data = vec![0u8; 3 * 2 + 4];
+        let mut frame = YuvFrame {
+            data: data.as_mut_ptr(),
+            width: 3,
+            height: 2,
+            y_stride: 3,
+            uv_stride: 3,
+        };
+        assert_eq!(unsafe { yuv_draw_rect(frame, 0, 0, 3, 2, 0, 0, 0) }, -1);
+        frame.uv_stride = 4;
+        assert_eq!(unsafe { yuv_draw_rect(frame, 0, 0, 3, 2, 0, 0, 0) }, 0);
+    }
+}
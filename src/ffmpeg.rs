@@ -0,0 +1,148 @@
+//! Interop with decoded `ffmpeg-next` video frames.
+//!
+//! NV12 frames whose planes share one buffer can be drawn on in place with
+//! [`nv12_view_mut`]. Other NV12 frames and I420 (`YUV420P`) frames are
+//! copied with [`nv12_from_frame`].
+
+use std::ops::Deref;
+use std::slice;
+
+use ffmpeg_next::ffi;
+use ffmpeg_next::format::Pixel as AVPixel;
+use ffmpeg_next::frame::Video;
+use ffmpeg_next::util::color::{Range, Space};
+
+use crate::{ColorMatrix, FrameDescriptor, NV12Image, PixelFormat};
+
+/// The matrix of the frame's color space and range. Frames without a range
+/// are taken as video range, like ffmpeg does for YUV, and frames tagged
+/// neither BT.709 nor anything else as BT.601.
+pub fn color_matrix(frame: &Video) -> ColorMatrix {
+    let bt709 = frame.color_space() == Space::BT709;
+    match (frame.color_range(), bt709) {
+        (Range::JPEG, false) => ColorMatrix::DEFAULT,
+        (Range::JPEG, true) => ColorMatrix::BT709_FULL,
+        (_, false) => ColorMatrix::BT601_VIDEO,
+        (_, true) => ColorMatrix::BT709_VIDEO,
+    }
+}
+
+/// Borrows the planes of an NV12 frame without copying, tagged with its
+/// [`color_matrix`].
+///
+/// Returns `None` if the frame is not NV12 or its Y and UV planes do not lie
+/// in the same buffer. The frame is made writable first, which copies its
+/// data only if the buffer is shared with another frame.
+pub fn nv12_view_mut(frame: &mut Video) -> Option<NV12Image<&mut [u8]>> {
+    if frame.format() != AVPixel::NV12 {
+        return None;
+    }
+    let (width, height) = (frame.width(), frame.height());
+    let matrix = color_matrix(frame);
+    unsafe {
+        if ffi::av_frame_make_writable(frame.as_mut_ptr()) < 0 {
+            return None;
+        }
+        let raw = &*frame.as_ptr();
+        if raw.buf[0].is_null() || raw.linesize[0] < 0 || raw.linesize[1] < 0 {
+            return None;
+        }
+        let y_stride = raw.linesize[0] as u32;
+        let uv_stride = raw.linesize[1] as u32;
+        let start = raw.data[0];
+        let uv_offset = (raw.data[1] as usize).checked_sub(start as usize)?;
+        if uv_offset < y_stride as usize * height as usize {
+            return None;
+        }
+        let len = uv_offset + uv_stride as usize * height.div_ceil(2) as usize;
+        let buf = &*raw.buf[0];
+        let buf_end = buf.data as usize + buf.size;
+        if (start as usize) < buf.data as usize || start as usize + len > buf_end {
+            return None;
+        }
+        let data = slice::from_raw_parts_mut(start, len);
+        Some(
+            NV12Image::from_strided(data, width, height, y_stride, uv_stride)
+                .with_uv_offset(uv_offset as u32)
+                .with_color_matrix(matrix),
+        )
+    }
+}
+
+/// Copies an NV12 or I420 frame into a tightly packed NV12 image tagged
+/// with its [`color_matrix`]. Returns `None` for other pixel formats.
+pub fn nv12_from_frame(frame: &Video) -> Option<NV12Image<Vec<u8>>> {
+    let (width, height) = (frame.width(), frame.height());
+    let (w, h) = (width as usize, height as usize);
+    let mut data = Vec::with_capacity(PixelFormat::NV12.frame_size(width, height));
+    let y_stride = frame.stride(0);
+    for row in frame.data(0).chunks(y_stride).take(h) {
+        data.extend_from_slice(&row[..w]);
+    }
+    let (uv_pairs, uv_rows) = (w.div_ceil(2), h.div_ceil(2));
+    match frame.format() {
+        AVPixel::NV12 => {
+            for row in frame.data(1).chunks(frame.stride(1)).take(uv_rows) {
+                data.extend_from_slice(&row[..uv_pairs * 2]);
+            }
+        }
+        AVPixel::YUV420P => {
+            let u_rows = frame.data(1).chunks(frame.stride(1));
+            let v_rows = frame.data(2).chunks(frame.stride(2));
+            for (u, v) in u_rows.zip(v_rows).take(uv_rows) {
+                for (&u, &v) in u[..uv_pairs].iter().zip(&v[..uv_pairs]) {
+                    data.extend([u, v]);
+                }
+            }
+        }
+        _ => return None,
+    }
+    let uv_stride = uv_pairs as u32 * 2;
+    Some(
+        NV12Image::from_strided(data, width, height, width, uv_stride)
+            .with_color_matrix(color_matrix(frame)),
+    )
+}
+
+/// Copies `img` into a newly allocated NV12 frame, e.g. for sending to an
+/// encoder.
+pub fn frame_from_nv12<T: Deref<Target = [u8]>>(img: &NV12Image<T>) -> Video {
+    let mut frame = Video::new(AVPixel::NV12, img.width, img.height);
+    let y_stride = frame.stride(0);
+    for (y, row) in frame
+        .data_mut(0)
+        .chunks_mut(y_stride)
+        .take(img.height as usize)
+        .enumerate()
+    {
+        row[..img.width as usize].copy_from_slice(img.y_row(y as u32));
+    }
+    let uv_stride = frame.stride(1);
+    for (y, row) in frame
+        .data_mut(1)
+        .chunks_mut(uv_stride)
+        .take(img.height.div_ceil(2) as usize)
+        .enumerate()
+    {
+        let uv = img.uv_row(y as u32);
+        row[..uv.len()].copy_from_slice(uv);
+    }
+    frame
+}
+
+/// Geometry of an NV12, NV21 or I420 frame, including ffmpeg's row padding.
+pub fn frame_descriptor(frame: &Video) -> Option<FrameDescriptor> {
+    let format = match frame.format() {
+        AVPixel::NV12 => PixelFormat::NV12,
+        AVPixel::NV21 => PixelFormat::NV21,
+        AVPixel::YUV420P => PixelFormat::I420,
+        _ => return None,
+    };
+    Some(FrameDescriptor {
+        width: frame.width(),
+        height: frame.height(),
+        y_stride: frame.stride(0) as u32,
+        uv_stride: frame.stride(1) as u32,
+        format,
+    })
+}
@@ -0,0 +1,86 @@
+/// Memory layout of a raw YUV frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PixelFormat {
+    /// 4:2:0, a Y plane followed by one interleaved UV plane.
+    NV12,
+    /// 4:2:0, like NV12 with V before U.
+    NV21,
+    /// 4:2:0, separate Y, U and V planes.
+    I420,
+    /// 4:2:2, packed as `Y0 U Y1 V` for every pair of pixels.
+    YUYV,
+}
+
+impl PixelFormat {
+    /// Number of bytes of one tightly packed `width`x`height` frame.
+    pub fn frame_size(&self, width: u32, height: u32) -> usize {
+        FrameDescriptor::packed(width, height, *self).frame_size()
+    }
+}
+
+/// Geometry of a frame buffer: its size, row strides and pixel format.
+///
+/// For NV12 and NV21 `uv_stride` is the row length of the interleaved
+/// chroma plane, for I420 the row length of each of the U and V planes.
+/// Packed YUYV has a single plane; its `uv_stride` is 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameDescriptor {
+    pub width: u32,
+    pub height: u32,
+    pub y_stride: u32,
+    pub uv_stride: u32,
+    pub format: PixelFormat,
+}
+
+impl FrameDescriptor {
+    /// Describes a frame without row padding.
+    pub fn packed(width: u32, height: u32, format: PixelFormat) -> Self {
+        let chroma_width = width.div_ceil(2);
+        let (y_stride, uv_stride) = match format {
+            PixelFormat::NV12 | PixelFormat::NV21 => (width, chroma_width * 2),
+            PixelFormat::I420 => (width, chroma_width),
+            PixelFormat::YUYV => (chroma_width * 4, 0),
+        };
+        Self {
+            width,
+            height,
+            y_stride,
+            uv_stride,
+            format,
+        }
+    }
+
+    /// Number of bytes of the whole frame, including row padding.
+    pub fn frame_size(&self) -> usize {
+        let luma = self.y_stride as usize * self.height as usize;
+        let chroma_rows = self.height.div_ceil(2) as usize;
+        let chroma_planes = match self.format {
+            PixelFormat::NV12 | PixelFormat::NV21 => 1,
+            PixelFormat::I420 => 2,
+            PixelFormat::YUYV => 0,
+        };
+        luma + chroma_planes * self.uv_stride as usize * chroma_rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_sizes() {
+        assert_eq!(
+            PixelFormat::NV12.frame_size(1920, 1080),
+            1920 * 1080 * 3 / 2
+        );
+        assert_eq!(PixelFormat::I420.frame_size(3, 3), 9 + 2 * 4);
+        let padded = FrameDescriptor {
+            y_stride: 2048,
+            uv_stride: 2048,
+            ..FrameDescriptor::packed(1920, 1080, PixelFormat::NV12)
+        };
+        assert_eq!(padded.frame_size(), 2048 * 1080 * 3 / 2);
+    }
+}
@@ -0,0 +1,138 @@
+use core::ops::{Deref, DerefMut};
+use core::time::Duration;
+
+use crate::NV12Image;
+
+/// Timing and origin of a frame, carried along with its pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FrameMeta {
+    /// Presentation timestamp in `time_base` units, if known.
+    pub pts: Option<i64>,
+    /// Seconds per `pts` unit as `(num, den)`, e.g. `(1, 90_000)` for MPEG
+    /// streams.
+    pub time_base: (u32, u32),
+    /// Position of the frame in its stream, counting from 0.
+    pub index: u64,
+    /// Identifier of the camera, file or stream the frame comes from.
+    pub source: u32,
+}
+
+impl Default for FrameMeta {
+    /// Frame 0 of source 0, without a timestamp, in a 90 kHz time base.
+    fn default() -> Self {
+        Self {
+            pts: None,
+            time_base: (1, 90_000),
+            index: 0,
+            source: 0,
+        }
+    }
+}
+
+impl FrameMeta {
+    /// Frame `index` of source 0, without a timestamp.
+    pub fn new(index: u64) -> Self {
+        Self {
+            index,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_pts(mut self, pts: i64, time_base: (u32, u32)) -> Self {
+        self.pts = Some(pts);
+        self.time_base = time_base;
+        self
+    }
+
+    pub fn with_source(mut self, source: u32) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// The timestamp as a duration. `None` without a timestamp, for negative
+    /// ones and for a time base with a zero denominator.
+    pub fn timestamp(&self) -> Option<Duration> {
+        let pts = u64::try_from(self.pts?).ok()?;
+        let (num, den) = self.time_base;
+        if den == 0 {
+            return None;
+        }
+        let nanos = pts as u128 * num as u128 * 1_000_000_000 / den as u128;
+        let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+        Some(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+    }
+}
+
+/// An NV12 image with its [`FrameMeta`], so pipeline stages keep timing
+/// data without a side channel.
+///
+/// The frame dereferences to its image, so it can be passed to anything
+/// reading or drawing on an [`NV12Image`], such as the writers of
+/// [`io`](crate::io). Operations producing a new image carry the metadata
+/// over with [`map`](Self::map) or [`with_image`](Self::with_image):
+///
+/// ```
+/// use yuvimg::imageops::resize;
+/// use yuvimg::{Frame, FrameMeta, Nv12Buffer, BLACK};
+///
+/// let frame = Frame::new(Nv12Buffer::filled(64, 48, BLACK), FrameMeta::new(7));
+/// let small = frame.with_image(resize(&frame, 32, 24));
+/// assert_eq!(small.meta.index, 7);
+/// ```
+#[derive(Clone)]
+pub struct Frame<T: Deref<Target = [u8]>> {
+    pub image: NV12Image<T>,
+    pub meta: FrameMeta,
+}
+
+impl<T: Deref<Target = [u8]>> Frame<T> {
+    pub fn new(image: NV12Image<T>, meta: FrameMeta) -> Self {
+        Self { image, meta }
+    }
+
+    /// `image` with the metadata of this frame, for images derived from it.
+    pub fn with_image<U: Deref<Target = [u8]>>(&self, image: NV12Image<U>) -> Frame<U> {
+        Frame::new(image, self.meta)
+    }
+
+    /// Transforms the image, keeping the metadata.
+    pub fn map<U, F>(self, f: F) -> Frame<U>
+    where
+        U: Deref<Target = [u8]>,
+        F: FnOnce(NV12Image<T>) -> NV12Image<U>,
+    {
+        Frame::new(f(self.image), self.meta)
+    }
+
+    pub fn into_parts(self) -> (NV12Image<T>, FrameMeta) {
+        (self.image, self.meta)
+    }
+}
+
+impl<T: Deref<Target = [u8]>> Deref for Frame<T> {
+    type Target = NV12Image<T>;
+
+    fn deref(&self) -> &NV12Image<T> {
+        &self.image
+    }
+}
+
+impl<T: Deref<Target = [u8]>> DerefMut for Frame<T> {
+    fn deref_mut(&mut self) -> &mut NV12Image<T> {
+        &mut self.image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_follow_the_time_base() {
+        let meta = FrameMeta::new(3).with_pts(135_000, (1, 90_000));
+        assert_eq!(meta.timestamp(), Some(Duration::from_millis(1500)));
+        assert_eq!(FrameMeta::new(3).timestamp(), None);
+        let meta = meta.with_pts(-1, (1, 25));
+        assert_eq!(meta.timestamp(), None);
+    }
+}
@@ -0,0 +1,459 @@
+//! NV12 to RGBA and RGBA to NV12 conversion in `wgpu` compute shaders.
+//!
+//! Frames are uploaded to storage buffers, converted by one dispatch over
+//! 8x8 workgroups and read back through a staging buffer. For 4K and 8K
+//! batch transcodes this beats the CPU converters once the upload and
+//! readback are amortized; pick the converter at runtime with [`Backend`].
+//!
+//! Whole frames are bound at once, so an 8K RGBA frame needs the default
+//! `max_storage_buffer_binding_size` of 128MiB.
+
+use std::ops::Deref;
+use std::sync::mpsc;
+
+use image::{ImageBuffer, Rgba, RgbaImage};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{
+    BindGroupDescriptor, BindGroupEntry, Buffer, BufferAsyncError, BufferDescriptor, BufferUsages,
+    CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor,
+    Device, Maintain, MapMode, Queue, ShaderModuleDescriptor, ShaderSource,
+};
+
+use crate::convert::{nv12_from_rgb, RgbTables};
+use crate::{ColorMatrix, NV12Image, Nv12Buffer};
+
+/// Side of the square workgroups of both entry points.
+const WORKGROUP: u32 = 8;
+
+/// Both conversions over `array<u32>` storage buffers, so bytes are packed
+/// four to a word. `nv12_to_rgba` runs once per pixel; `rgba_to_nv12` once
+/// per 4x2 pixel group, so every invocation writes whole words of the luma
+/// and chroma rows.
+const CONVERT_WGSL: &str = r#"
+struct Params {
+    width: u32,
+    height: u32,
+    y_stride: u32,
+    uv_stride: u32,
+    uv_offset: u32,
+    y_offset: f32,
+    y_scale: f32,
+    rv: f32,
+    gu: f32,
+    gv: f32,
+    bu: f32,
+    pad: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> src: array<u32>;
+@group(0) @binding(2) var<storage, read_write> dst: array<u32>;
+
+fn src_byte(i: u32) -> u32 {
+    return (src[i / 4u] >> ((i % 4u) * 8u)) & 0xffu;
+}
+
+// Rounds half away from zero, like the entries of `RgbTables`.
+fn round_term(x: f32) -> f32 {
+    return trunc(x + select(0.5, -0.5, x < 0.0));
+}
+
+@compute @workgroup_size(8, 8)
+fn nv12_to_rgba(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let luma = f32(src_byte(id.y * params.y_stride + id.x));
+    let c = params.uv_offset + (id.y / 2u) * params.uv_stride + (id.x / 2u) * 2u;
+    let u = f32(src_byte(c)) - 128.0;
+    let v = f32(src_byte(c + 1u)) - 128.0;
+    let ys = round_term(params.y_scale * (luma - params.y_offset));
+    let rgb = vec3<f32>(
+        ys + round_term(params.rv * v),
+        ys - round_term(params.gu * u) - round_term(params.gv * v),
+        ys + round_term(params.bu * u),
+    );
+    let q = vec3<u32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(255.0)));
+    dst[id.y * params.width + id.x] = q.x | (q.y << 8u) | (q.z << 16u) | (255u << 24u);
+}
+
+fn to_yuv(p: u32) -> vec3<u32> {
+    let r = f32(p & 0xffu);
+    let g = f32((p >> 8u) & 0xffu);
+    let b = f32((p >> 16u) & 0xffu);
+    let yuv = vec3<f32>(
+        0.299 * r + 0.587 * g + 0.114 * b,
+        -0.169 * r - 0.331 * g + 0.5 * b + 128.0,
+        0.5 * r - 0.419 * g - 0.081 * b + 128.0,
+    );
+    return vec3<u32>(clamp(yuv + 0.5, vec3<f32>(0.0), vec3<f32>(255.0)));
+}
+
+@compute @workgroup_size(8, 8)
+fn rgba_to_nv12(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x0 = id.x * 4u;
+    let y0 = id.y * 2u;
+    if (x0 >= params.width || y0 >= params.height) {
+        return;
+    }
+    var u_sum = array<u32, 2>(0u, 0u);
+    var v_sum = array<u32, 2>(0u, 0u);
+    var count = array<u32, 2>(0u, 0u);
+    for (var dy = 0u; dy < 2u && y0 + dy < params.height; dy = dy + 1u) {
+        let y = y0 + dy;
+        var word = 0u;
+        for (var dx = 0u; dx < 4u && x0 + dx < params.width; dx = dx + 1u) {
+            let yuv = to_yuv(src[y * params.width + x0 + dx]);
+            word = word | (yuv.x << (dx * 8u));
+            let block = dx / 2u;
+            u_sum[block] = u_sum[block] + yuv.y;
+            v_sum[block] = v_sum[block] + yuv.z;
+            count[block] = count[block] + 1u;
+        }
+        dst[y * params.y_stride / 4u + id.x] = word;
+    }
+    var uv = 0u;
+    for (var block = 0u; block < 2u; block = block + 1u) {
+        let n = count[block];
+        if (n > 0u) {
+            let u = (u_sum[block] + n / 2u) / n;
+            let v = (v_sum[block] + n / 2u) / n;
+            uv = uv | ((u | (v << 8u)) << (block * 16u));
+        }
+    }
+    dst[(params.uv_offset + id.y * params.uv_stride) / 4u + id.x] = uv;
+}
+"#;
+
+/// The `Params` uniform of [`CONVERT_WGSL`], little-endian.
+fn params_bytes(
+    width: u32,
+    height: u32,
+    y_stride: u32,
+    uv_stride: u32,
+    m: &ColorMatrix,
+) -> Vec<u8> {
+    let words = [width, height, y_stride, uv_stride, y_stride * height];
+    let floats = [m.y_offset, m.y_scale, m.rv, m.gu, m.gv, m.bu];
+    words
+        .iter()
+        .flat_map(|w| w.to_le_bytes())
+        .chain(floats.iter().flat_map(|f| f.to_le_bytes()))
+        .chain([0; 4])
+        .collect()
+}
+
+/// Tightly packed Y and UV planes of `img`, zero padded to whole words.
+fn packed_planes<T: Deref<Target = [u8]>>(img: &NV12Image<T>) -> Vec<u8> {
+    let uv_width = img.width.div_ceil(2) as usize * 2;
+    let uv_rows = img.height.div_ceil(2);
+    let mut data = Vec::with_capacity(
+        img.width as usize * img.height as usize + uv_width * uv_rows as usize + 3,
+    );
+    for y in 0..img.height {
+        data.extend_from_slice(img.y_row(y));
+    }
+    for y in 0..uv_rows {
+        data.extend_from_slice(img.uv_row(y));
+    }
+    data.resize(data.len().div_ceil(4) * 4, 0);
+    data
+}
+
+/// Row stride of the frames [`GpuConverter::rgba_to_nv12`] returns: the
+/// width rounded up to whole words, for both planes.
+fn nv12_stride(width: u32) -> u32 {
+    width.div_ceil(4) * 4
+}
+
+/// Copies the first `size` bytes of `buffer`, which needs
+/// `BufferUsages::COPY_SRC`, through a staging buffer and waits for them.
+/// Work submitted to `queue` earlier finishes first.
+pub fn read_buffer(
+    device: &Device,
+    queue: &Queue,
+    buffer: &Buffer,
+    size: u64,
+) -> Result<Vec<u8>, BufferAsyncError> {
+    let staging = device.create_buffer(&BufferDescriptor {
+        label: Some("yuvimg staging"),
+        size,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+    queue.submit(Some(encoder.finish()));
+    map_read(device, &staging)
+}
+
+/// Maps `staging`, which needs `BufferUsages::MAP_READ`, blocking until the
+/// GPU is done with it, and copies out its contents.
+pub fn map_read(device: &Device, staging: &Buffer) -> Result<Vec<u8>, BufferAsyncError> {
+    let slice = staging.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(Maintain::Wait);
+    // The callback has run once `poll` returns, unless the device was lost.
+    receiver.recv().map_err(|_| BufferAsyncError)??;
+    let data = slice.get_mapped_range().to_vec();
+    staging.unmap();
+    Ok(data)
+}
+
+/// Compute pipelines for both conversions on one device.
+pub struct GpuConverter {
+    device: Device,
+    queue: Queue,
+    to_rgba: ComputePipeline,
+    to_nv12: ComputePipeline,
+}
+
+impl GpuConverter {
+    /// Compiles the conversion shaders for `device`.
+    pub fn new(device: Device, queue: Queue) -> Self {
+        let module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("yuvimg convert"),
+            source: ShaderSource::Wgsl(CONVERT_WGSL.into()),
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: None,
+                module: &module,
+                entry_point,
+            })
+        };
+        let to_rgba = pipeline("nv12_to_rgba");
+        let to_nv12 = pipeline("rgba_to_nv12");
+        Self {
+            device,
+            queue,
+            to_rgba,
+            to_nv12,
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Runs `pipeline` over `groups` workgroups with `src` as input and
+    /// reads back the `dst_size` bytes of output.
+    fn run(
+        &self,
+        pipeline: &ComputePipeline,
+        params: &[u8],
+        src: &[u8],
+        dst_size: u64,
+        groups: (u32, u32),
+    ) -> Result<Vec<u8>, BufferAsyncError> {
+        let init = |label, contents, usage| {
+            self.device.create_buffer_init(&BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage,
+            })
+        };
+        let params = init("yuvimg params", params, BufferUsages::UNIFORM);
+        let src = init("yuvimg src", src, BufferUsages::STORAGE);
+        let dst = self.device.create_buffer(&BufferDescriptor {
+            label: Some("yuvimg dst"),
+            size: dst_size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: src.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: dst.as_entire_binding(),
+                },
+            ],
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor { label: None });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(
+                groups.0.div_ceil(WORKGROUP),
+                groups.1.div_ceil(WORKGROUP),
+                1,
+            );
+        }
+        self.queue.submit(Some(encoder.finish()));
+        read_buffer(&self.device, &self.queue, &dst, dst_size)
+    }
+
+    /// Converts `img` with its [`color_matrix`](NV12Image::color_matrix),
+    /// like [`to_rgb_with`](crate::convert::to_rgb_with), rounding each term
+    /// the same way. Alpha is opaque.
+    pub fn nv12_to_rgba<T: Deref<Target = [u8]>>(
+        &self,
+        img: &NV12Image<T>,
+    ) -> Result<RgbaImage, BufferAsyncError> {
+        let (width, height) = (img.width, img.height);
+        if width == 0 || height == 0 {
+            return Ok(RgbaImage::new(width, height));
+        }
+        let uv_stride = width.div_ceil(2) * 2;
+        let params = params_bytes(width, height, width, uv_stride, &img.color_matrix);
+        let size = width as u64 * height as u64 * 4;
+        let rgba = self.run(
+            &self.to_rgba,
+            &params,
+            &packed_planes(img),
+            size,
+            (width, height),
+        )?;
+        Ok(ImageBuffer::from_raw(width, height, rgba).expect("readback matches the frame size"))
+    }
+
+    /// Converts `img` to NV12, averaging chroma over each 2x2 block like
+    /// [`from_rgb`](crate::convert::from_rgb). Alpha is ignored. Rows of the
+    /// result are padded to a multiple of four bytes.
+    pub fn rgba_to_nv12(&self, img: &RgbaImage) -> Result<Nv12Buffer, BufferAsyncError> {
+        let (width, height) = img.dimensions();
+        if width == 0 || height == 0 {
+            return Ok(nv12_from_rgb(width, height, |_, _| [0; 3]));
+        }
+        let stride = nv12_stride(width);
+        let params = params_bytes(width, height, stride, stride, &ColorMatrix::DEFAULT);
+        let rows = height as u64 + height.div_ceil(2) as u64;
+        let groups = (width.div_ceil(4), height.div_ceil(2));
+        let data = self.run(
+            &self.to_nv12,
+            &params,
+            img.as_raw(),
+            stride as u64 * rows,
+            groups,
+        )?;
+        Ok(NV12Image::from_strided(data, width, height, stride, stride))
+    }
+}
+
+/// Where frame conversions run, chosen at runtime, e.g. falling back to
+/// the CPU when no adapter is available.
+pub enum Backend {
+    Cpu,
+    Gpu(GpuConverter),
+}
+
+impl Backend {
+    /// Converts `img` with its [`color_matrix`](NV12Image::color_matrix).
+    /// Only the GPU backend can fail, when reading back its output.
+    pub fn nv12_to_rgba<T: Deref<Target = [u8]>>(
+        &self,
+        img: &NV12Image<T>,
+    ) -> Result<RgbaImage, BufferAsyncError> {
+        match self {
+            Backend::Cpu => {
+                let tables = RgbTables::new(&img.color_matrix);
+                Ok(ImageBuffer::from_fn(img.width, img.height, |x, y| {
+                    let [r, g, b] = tables.rgb(img.sample(x, y));
+                    Rgba([r, g, b, 255])
+                }))
+            }
+            Backend::Gpu(gpu) => gpu.nv12_to_rgba(img),
+        }
+    }
+
+    /// Converts `img` to NV12, ignoring alpha.
+    pub fn rgba_to_nv12(&self, img: &RgbaImage) -> Result<Nv12Buffer, BufferAsyncError> {
+        match self {
+            Backend::Cpu => Ok(nv12_from_rgb(img.width(), img.height(), |x, y| {
+                let [r, g, b, _] = img.get_pixel(x, y).0;
+                [r, g, b]
+            })),
+            Backend::Gpu(gpu) => gpu.rgba_to_nv12(img),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::YUV;
+
+    #[test]
+    fn cpu_backend_and_buffer_layout() {
+        let rgba = RgbaImage::from_fn(5, 3, |x, y| Rgba([x as u8 * 50, y as u8 * 100, 30, 7]));
+        let nv12 = Backend::Cpu.rgba_to_nv12(&rgba).unwrap();
+        assert_eq!(nv12.y_row(2)[4], YUV::from_rgb([200, 200, 30]).0[0]);
+        let back = Backend::Cpu.nv12_to_rgba(&nv12).unwrap();
+        assert_eq!(back.dimensions(), (5, 3));
+        assert_eq!(back.get_pixel(4, 2).0[3], 255);
+
+        // 5x3 Y bytes and two UV rows of three pairs, padded to 28.
+        let planes = packed_planes(&nv12);
+        assert_eq!(planes.len(), 28);
+        assert_eq!(&planes[15..21], &nv12.uv_plane()[..6]);
+        assert_eq!(nv12_stride(5), 8);
+        let params = params_bytes(5, 3, 8, 8, &ColorMatrix::DEFAULT);
+        assert_eq!(params.len(), 48);
+        assert_eq!(&params[16..20], &24u32.to_le_bytes());
+    }
+
+    /// A converter on the default adapter, or `None` on machines without
+    /// one, such as most CI runners.
+    fn gpu() -> Option<GpuConverter> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+        let descriptor = wgpu::DeviceDescriptor::default();
+        let (device, queue) = pollster::block_on(adapter.request_device(&descriptor, None)).ok()?;
+        Some(GpuConverter::new(device, queue))
+    }
+
+    fn assert_close(gpu: &[u8], cpu: &[u8], what: &str) {
+        assert_eq!(gpu.len(), cpu.len(), "{}", what);
+        for (i, (&g, &c)) in gpu.iter().zip(cpu).enumerate() {
+            assert!(g.abs_diff(c) <= 1, "{} byte {}: {} vs {}", what, i, g, c);
+        }
+    }
+
+    #[test]
+    fn gpu_matches_cpu_backend() {
+        let Some(gpu) = gpu() else {
+            eprintln!("no wgpu adapter, skipping");
+            return;
+        };
+        let gpu = Backend::Gpu(gpu);
+        for (width, height) in [(64, 32), (37, 19)] {
+            let rgba = RgbaImage::from_fn(width, height, |x, y| {
+                Rgba([(x * 7) as u8, (y * 13) as u8, ((x + y) * 5) as u8, 255])
+            });
+            let cpu_nv12 = Backend::Cpu.rgba_to_nv12(&rgba).unwrap();
+            let gpu_nv12 = gpu.rgba_to_nv12(&rgba).unwrap();
+            for y in 0..height {
+                assert_close(gpu_nv12.y_row(y), cpu_nv12.y_row(y), "luma");
+            }
+            for y in 0..height.div_ceil(2) {
+                assert_close(gpu_nv12.uv_row(y), cpu_nv12.uv_row(y), "chroma");
+            }
+            let video = cpu_nv12.with_color_matrix(ColorMatrix::BT709_VIDEO);
+            let cpu_rgba = Backend::Cpu.nv12_to_rgba(&video).unwrap();
+            let gpu_rgba = gpu.nv12_to_rgba(&video).unwrap();
+            assert_close(gpu_rgba.as_raw(), cpu_rgba.as_raw(), "rgba");
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Wrapping of mapped GStreamer video frames, for writing overlay elements
+//! on top of this crate.
+//!
+//! A `VideoFilter` gets each buffer as a mapped [`VideoFrameRef`] in
+//! `transform_frame_ip`; [`from_gst_video_frame`] turns it into an image
+//! that draws directly into the buffer.
+
+use std::ptr::NonNull;
+use std::slice;
+
+use gst_video::{VideoFormat, VideoFrameRef};
+
+use crate::{NV12Image, Plane, YUV420Image};
+
+/// A mapped GStreamer frame in one of the supported formats.
+pub enum GstImage<'a> {
+    NV12(NV12Image<&'a mut [u8]>),
+    I420(YUV420Image<&'a mut [u8]>),
+}
+
+/// Borrows the planes of an NV12 or I420 frame without copying. Returns
+/// `None` for other formats or negative strides.
+///
+/// The plane offsets and strides of the frame's `VideoInfo` (or of its
+/// `VideoMeta`, which GStreamer applies when mapping) are honored, and
+/// planes are allowed to live in separate memories of the buffer.
+pub fn from_gst_video_frame<'a>(
+    frame: &'a mut VideoFrameRef<&mut gst::BufferRef>,
+) -> Option<GstImage<'a>> {
+    let (width, height) = (frame.width(), frame.height());
+    let n_planes = match frame.format() {
+        VideoFormat::Nv12 => 2,
+        VideoFormat::I420 => 3,
+        _ => return None,
+    };
+    let mut strides = [0; 3];
+    for (stride, &s) in strides.iter_mut().zip(frame.plane_stride()) {
+        *stride = u32::try_from(s).ok()?;
+    }
+    let mut planes = [(NonNull::dangling().as_ptr(), 0); 3];
+    for (i, plane) in planes.iter_mut().enumerate().take(n_planes) {
+        let data = frame.plane_data_mut(i as u32).ok()?;
+        *plane = (data.as_mut_ptr(), data.len());
+    }
+    // SAFETY: the planes of a mapped frame are disjoint and stay mapped for
+    // as long as the frame, which is borrowed mutably for `'a`.
+    let [y, u, v] = planes.map(|(ptr, len)| unsafe { slice::from_raw_parts_mut(ptr, len) });
+
+    let image = if n_planes == 2 {
+        GstImage::NV12(NV12Image::from_planes(
+            y, u, width, height, strides[0], strides[1],
+        ))
+    } else {
+        let plane = |data, row_stride| Plane {
+            data,
+            row_stride,
+            pixel_stride: 1,
+        };
+        GstImage::I420(YUV420Image::from_planes(
+            width,
+            height,
+            plane(y, strides[0]),
+            plane(u, strides[1]),
+            plane(v, strides[2]),
+        ))
+    };
+    Some(image)
+}
@@ -0,0 +1,303 @@
+//! NV12 versions of `image::imageops` functions.
+//!
+//! The generic functions read pixels through `get_pixel`, which returns the
+//! luma of the top-left pixel of each 2x2 block, and write them one by one.
+//! These work on the planes directly, keeping full resolution luma and
+//! copying whole rows where the blocks line up.
+
+use core::ops::{Deref, DerefMut};
+
+use alloc::vec;
+
+use crate::{FrameDescriptor, NV12Image, Nv12Buffer, PixelFormat, Region, YUV};
+
+impl<T: DerefMut<Target = [u8]>> NV12Image<T> {
+    /// Writes `pixel(px, py)` to `(x + px, y + py)` for the `width`x`height`
+    /// area at `(x, y)`, which must be inside the image. Luma is written per
+    /// pixel. The chroma of each block is the average over its pixels, taking
+    /// the old chroma for pixels of the block outside the area.
+    pub(crate) fn paste_with<F>(&mut self, x: u32, y: u32, width: u32, height: u32, mut pixel: F)
+    where
+        F: FnMut(u32, u32) -> YUV,
+    {
+        let (right, bottom) = (x + width, y + height);
+        for by in (y & !1..bottom).step_by(2) {
+            for bx in (x & !1..right).step_by(2) {
+                let (_, u_index, v_index) = self.pixel_indices(bx, by);
+                let old = (self.uv_plane()[u_index], self.uv_plane()[v_index]);
+                let (mut u, mut v, mut count) = (0u32, 0u32, 0u32);
+                for py in by..(by + 2).min(self.height) {
+                    for px in bx..(bx + 2).min(self.width) {
+                        let (pu, pv) = if px < x || px >= right || py < y || py >= bottom {
+                            old
+                        } else {
+                            let p = pixel(px - x, py - y);
+                            self.data[(py * self.y_stride + px) as usize] = p.0[0];
+                            (p.0[1], p.0[2])
+                        };
+                        u += u32::from(pu);
+                        v += u32::from(pv);
+                        count += 1;
+                    }
+                }
+                let (_, uv) = self.split_planes_mut();
+                uv[u_index] = ((u + count / 2) / count) as u8;
+                uv[v_index] = ((v + count / 2) / count) as u8;
+            }
+        }
+    }
+}
+
+/// Copies `top` onto `bottom` with its top-left corner at `(x, y)`, clipped
+/// to `bottom`. Like `image::imageops::replace`, which for opaque `YUV`
+/// pixels is also what `overlay` does.
+///
+/// Rows are copied with `memcpy` when the area starts on even coordinates in
+/// both images and ends on a block boundary or the edge of `bottom`.
+pub fn replace<A, B>(bottom: &mut NV12Image<A>, top: &NV12Image<B>, x: i64, y: i64)
+where
+    A: DerefMut<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    let clip = |offset: i64, top_len: u32, bottom_len: u32| {
+        let start = offset.max(0);
+        let end = (offset + i64::from(top_len)).min(i64::from(bottom_len));
+        (
+            start as u32,
+            (start - offset) as u32,
+            (end - start).max(0) as u32,
+        )
+    };
+    let (dst_x, src_x, width) = clip(x, top.width, bottom.width);
+    let (dst_y, src_y, height) = clip(y, top.height, bottom.height);
+    if width == 0 || height == 0 {
+        return;
+    }
+    let aligned = |dst: u32, src: u32, len: u32, bottom_len: u32| {
+        (dst | src) & 1 == 0 && (len & 1 == 0 || dst + len == bottom_len)
+    };
+    if !aligned(dst_x, src_x, width, bottom.width) || !aligned(dst_y, src_y, height, bottom.height)
+    {
+        bottom.paste_with(dst_x, dst_y, width, height, |px, py| {
+            top.sample(src_x + px, src_y + py)
+        });
+        return;
+    }
+    let (left, right) = (dst_x as usize, (dst_x + width) as usize);
+    let src_left = src_x as usize;
+    for row in 0..height {
+        let src = &top.y_row(src_y + row)[src_left..src_left + width as usize];
+        bottom.y_row_mut(dst_y + row)[left..right].copy_from_slice(src);
+    }
+    // Odd widths end on the edge of `bottom`, where the last block still has
+    // a chroma pair.
+    let uv_width = width.div_ceil(2) as usize * 2;
+    let (top_stride, uv_stride) = (top.uv_stride, bottom.uv_stride);
+    for row in 0..height.div_ceil(2) {
+        let src_start = ((src_y / 2 + row) * top_stride) as usize + src_left;
+        let src = &top.uv_plane()[src_start..src_start + uv_width];
+        let start = ((dst_y / 2 + row) * uv_stride) as usize + left;
+        let (_, uv) = bottom.split_planes_mut();
+        uv[start..start + uv_width].copy_from_slice(src);
+    }
+}
+
+/// Bilinearly samples a `src_w`x`src_h` plane read through `get` at
+/// `dst_w`x`dst_h` points, passing them to `put`. Pixel centers are aligned
+/// like `imageops::resize`.
+fn bilinear<G, P>(src_w: u32, src_h: u32, dst_w: u32, dst_h: u32, get: G, mut put: P)
+where
+    G: Fn(u32, u32) -> u8,
+    P: FnMut(u32, u32, u8),
+{
+    // The source position of output sample `o` in 16.16 fixed point, split
+    // into its two neighbours and the weight of the second.
+    let map = |o: u32, src: u32, dst: u32| {
+        let pos = (((2 * u64::from(o) + 1) * u64::from(src)) << 15) / u64::from(dst);
+        let pos = pos.saturating_sub(1 << 15);
+        let i0 = ((pos >> 16) as u32).min(src - 1);
+        (i0, (i0 + 1).min(src - 1), pos as u32 & 0xffff)
+    };
+    for oy in 0..dst_h {
+        let (y0, y1, fy) = map(oy, src_h, dst_h);
+        for ox in 0..dst_w {
+            let (x0, x1, fx) = map(ox, src_w, dst_w);
+            let lerp = |a: u8, b: u8, f: u32| {
+                u64::from(a) * u64::from(0x10000 - f) + u64::from(b) * u64::from(f)
+            };
+            let top = lerp(get(x0, y0), get(x1, y0), fx);
+            let bottom = lerp(get(x0, y1), get(x1, y1), fx);
+            let value = (top * u64::from(0x10000 - fy) + bottom * u64::from(fy) + (1 << 31)) >> 32;
+            put(ox, oy, value as u8);
+        }
+    }
+}
+
+/// Resizes `image` to `width`x`height` with bilinear filtering, scaling the
+/// luma and chroma planes separately. The result is tightly packed and keeps
+/// the image's color matrix.
+pub fn resize<T>(image: &NV12Image<T>, width: u32, height: u32) -> Nv12Buffer
+where
+    T: Deref<Target = [u8]>,
+{
+    let area = Region::new(0, 0, image.width, image.height);
+    resize_region(image, area, width, height)
+}
+
+/// [`resize`] of `area` of `image`, which must be inside the image and start
+/// on even coordinates.
+fn resize_region<T>(image: &NV12Image<T>, area: Region, width: u32, height: u32) -> Nv12Buffer
+where
+    T: Deref<Target = [u8]>,
+{
+    let desc = FrameDescriptor::packed(width, height, PixelFormat::NV12);
+    let data = vec![0; desc.frame_size()];
+    let mut out = NV12Image::from_strided(data, width, height, desc.y_stride, desc.uv_stride)
+        .with_color_matrix(image.color_matrix);
+    if width == 0 || height == 0 || area.is_empty() {
+        return out;
+    }
+    let y_stride = desc.y_stride;
+    let (luma, chroma) = out.split_planes_mut();
+    bilinear(
+        area.width,
+        area.height,
+        width,
+        height,
+        |x, y| image.data[((area.y + y) * image.y_stride + area.x + x) as usize],
+        |x, y, value| luma[(y * y_stride + x) as usize] = value,
+    );
+    let uv = image.uv_plane();
+    let uv_stride = desc.uv_stride;
+    let (cx, cy) = (area.x / 2, area.y / 2);
+    for channel in 0..2 {
+        bilinear(
+            (area.x + area.width).div_ceil(2) - cx,
+            (area.y + area.height).div_ceil(2) - cy,
+            width.div_ceil(2),
+            height.div_ceil(2),
+            |x, y| uv[((cy + y) * image.uv_stride + (cx + x) * 2 + channel) as usize],
+            |x, y, value| chroma[(y * uv_stride + x * 2 + channel) as usize] = value,
+        );
+    }
+    out
+}
+
+/// The scaled length `len * num / den`, rounded to an even number so content
+/// placed at an even offset covers whole 2x2 blocks, and at most `num`.
+fn even_len(len: u32, num: u32, den: u32) -> u32 {
+    let scaled = (u64::from(len) * u64::from(num) + u64::from(den) / 2) / u64::from(den);
+    (((scaled + 1) & !1).min(u64::from(num)) as u32).max(1)
+}
+
+/// Scales `image` to fit in `width`x`height` keeping its aspect ratio, and
+/// centers it on a background of `fill`. The content starts on even
+/// coordinates and has even dimensions unless it spans the whole output, so
+/// its chroma blocks line up with those of the output.
+pub fn letterbox<T>(image: &NV12Image<T>, width: u32, height: u32, fill: YUV) -> Nv12Buffer
+where
+    T: Deref<Target = [u8]>,
+{
+    let mut out = Nv12Buffer::filled(width, height, fill).with_color_matrix(image.color_matrix);
+    let (src_w, src_h) = (image.width, image.height);
+    if width == 0 || height == 0 || src_w == 0 || src_h == 0 {
+        return out;
+    }
+    let (scaled_w, scaled_h) =
+        if u64::from(src_w) * u64::from(height) >= u64::from(src_h) * u64::from(width) {
+            (width, even_len(src_h, width, src_w).min(height))
+        } else {
+            (even_len(src_w, height, src_h).min(width), height)
+        };
+    let scaled = resize(image, scaled_w, scaled_h);
+    let x = ((width - scaled_w) / 2) & !1;
+    let y = ((height - scaled_h) / 2) & !1;
+    replace(&mut out, &scaled, i64::from(x), i64::from(y));
+    out
+}
+
+/// Scales `image` to cover `width`x`height` keeping its aspect ratio, cropping
+/// the middle of whatever sticks out. The complement of [`letterbox`], for
+/// thumbnails that must fill their frame.
+///
+/// The crop starts on even coordinates, so the chroma is not shifted against
+/// the luma.
+pub fn fit_cover<T>(image: &NV12Image<T>, width: u32, height: u32) -> Nv12Buffer
+where
+    T: Deref<Target = [u8]>,
+{
+    let (src_w, src_h) = (image.width, image.height);
+    if width == 0 || height == 0 || src_w == 0 || src_h == 0 {
+        return resize(image, width, height);
+    }
+    let crop = |len: u32, num: u32, den: u32| {
+        let len = (u64::from(len) * u64::from(num) + u64::from(den) / 2) / u64::from(den);
+        len.max(1) as u32
+    };
+    let (crop_w, crop_h) =
+        if u64::from(src_w) * u64::from(height) > u64::from(src_h) * u64::from(width) {
+            (crop(src_h, width, height).min(src_w), src_h)
+        } else {
+            (src_w, crop(src_w, height, width).min(src_h))
+        };
+    let x = ((src_w - crop_w) / 2) & !1;
+    let y = ((src_h - crop_h) / 2) & !1;
+    resize_region(image, Region::new(x, y, crop_w, crop_h), width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLUE, RED};
+
+    #[test]
+    fn replace_keeps_luma_and_averages_chroma() {
+        let mut bottom = Nv12Buffer::filled(6, 4, BLUE);
+        let mut top = Nv12Buffer::filled(2, 2, RED);
+        top.y_row_mut(0)[1] = 7;
+        replace(&mut bottom, &top, 1, 0);
+        assert_eq!(bottom.y_row(0)[..4], [BLUE.0[0], RED.0[0], 7, BLUE.0[0]]);
+        let mixed = YUV::blend_weighted(RED, BLUE, 1, 1);
+        assert_eq!(bottom.uv_row(0)[..2], mixed.0[1..]);
+
+        replace(&mut bottom, &top, 4, 2);
+        assert_eq!(bottom.y_row(2)[4..], [RED.0[0], 7]);
+        assert_eq!(bottom.pixel(4, 2), RED);
+        assert_eq!(bottom.pixel(4, 0), BLUE);
+    }
+
+    #[test]
+    fn letterbox_centers_on_blocks() {
+        let img = Nv12Buffer::filled(16, 9, RED);
+        let out = letterbox(&img, 12, 12, BLUE);
+        // 12x6.75 rounds to 12x8, placed at y = 2.
+        assert_eq!(out.pixel(0, 1), BLUE);
+        assert_eq!(out.pixel(0, 2), RED);
+        assert_eq!(out.pixel(11, 9), RED);
+        assert_eq!(out.pixel(11, 10), BLUE);
+    }
+
+    #[test]
+    fn fit_cover_crops_the_middle() {
+        let mut img = Nv12Buffer::filled(12, 4, BLUE);
+        img.fill_rect(4, 0, 4, 4, RED);
+        let out = fit_cover(&img, 2, 2);
+        assert_eq!(out.as_bytes().len(), 2 * 2 + 2);
+        assert_eq!(out.pixel(0, 0), RED);
+        assert_eq!(out.pixel(1, 1), RED);
+    }
+
+    #[test]
+    fn resize_scales_both_planes() {
+        let img = Nv12Buffer::filled(5, 3, RED);
+        let out = resize(&img, 8, 6);
+        assert_eq!(out.as_bytes().len(), 8 * 6 + 8 * 3);
+        assert_eq!(out.pixel(7, 5), RED);
+
+        let mut ramp = Nv12Buffer::filled(4, 2, RED);
+        ramp.y_row_mut(0).copy_from_slice(&[0, 100, 200, 250]);
+        ramp.y_row_mut(1).copy_from_slice(&[0, 100, 200, 250]);
+        let half = resize(&ramp, 2, 1);
+        assert_eq!(half.y_row(0), [50, 225]);
+    }
+}
@@ -0,0 +1,454 @@
+//! Readers and writers for YUV streams.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::{Frame, FrameMeta, NV12Image, PixelFormat, YuvError};
+
+#[cfg(feature = "async")]
+mod async_reader;
+#[cfg(feature = "async")]
+pub use async_reader::AsyncYuvReader;
+
+/// Reads consecutive frames from a headerless `.yuv` file.
+///
+/// [`next_frame`](Self::next_frame) and [`for_each_frame`](Self::for_each_frame)
+/// read into one internal buffer that is reused for every frame, and are the
+/// way to read long streams. The [`Iterator`] implementation allocates an
+/// owned copy of each frame instead.
+pub struct RawYuvReader<R> {
+    inner: R,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    buf: Vec<u8>,
+    /// Index of the next frame.
+    index: u64,
+    framerate: (u32, u32),
+}
+
+impl RawYuvReader<BufReader<File>> {
+    /// Opens a raw file holding `width`x`height` frames in `format`.
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self::from_reader(
+            BufReader::new(file),
+            width,
+            height,
+            format,
+        ))
+    }
+}
+
+impl<R: Read> RawYuvReader<R> {
+    pub fn from_reader(inner: R, width: u32, height: u32, format: PixelFormat) -> Self {
+        Self {
+            inner,
+            width,
+            height,
+            format,
+            buf: vec![0; format.frame_size(width, height)],
+            index: 0,
+            framerate: (25, 1),
+        }
+    }
+
+    /// Sets the frame rate of the stream as `num / den` fps, from which
+    /// [`try_next_frame`](Self::try_next_frame) derives timestamps. 25 fps
+    /// by default.
+    pub fn with_framerate(mut self, num: u32, den: u32) -> Self {
+        self.framerate = (num, den);
+        self
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Size of one frame in bytes.
+    pub fn frame_size(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Reads the next frame into the internal buffer. Returns `None` at the
+    /// end of the stream and an `UnexpectedEof` error if the stream ends in
+    /// the middle of a frame.
+    pub fn next_frame(&mut self) -> io::Result<Option<&[u8]>> {
+        let mut filled = 0;
+        while filled < self.buf.len() {
+            match self.inner.read(&mut self.buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if filled == 0 {
+            Ok(None)
+        } else if filled < self.buf.len() {
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("truncated frame: {} of {} bytes", filled, self.buf.len()),
+            ))
+        } else {
+            self.index += 1;
+            Ok(Some(&self.buf))
+        }
+    }
+
+    /// Calls `f` with every remaining frame, each read into the internal
+    /// buffer like [`next_frame`](Self::next_frame), so no frame is
+    /// allocated.
+    pub fn for_each_frame<F: FnMut(&[u8])>(&mut self, mut f: F) -> io::Result<()> {
+        while let Some(frame) = self.next_frame()? {
+            f(frame);
+        }
+        Ok(())
+    }
+
+    /// [`next_frame`](Self::next_frame) wrapped as an NV12 image. Fails with
+    /// [`YuvError::UnsupportedFormat`] if the stream is in another format.
+    pub fn try_next_image(&mut self) -> Result<Option<NV12Image<&[u8]>>, YuvError> {
+        if self.format != PixelFormat::NV12 {
+            return Err(YuvError::UnsupportedFormat(self.format));
+        }
+        let (width, height) = (self.width, self.height);
+        match self.next_frame()? {
+            Some(frame) => Ok(Some(NV12Image::try_from(frame, width, height)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Like [`try_next_image`](Self::try_next_image), with the frame's
+    /// index in the stream and a timestamp counting frames at the frame
+    /// rate, in a time base of one frame.
+    pub fn try_next_frame(&mut self) -> Result<Option<Frame<&[u8]>>, YuvError> {
+        let index = self.index;
+        let (num, den) = self.framerate;
+        let meta = FrameMeta::new(index).with_pts(index as i64, (den, num));
+        Ok(self.try_next_image()?.map(|image| Frame::new(image, meta)))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read + Seek> RawYuvReader<R> {
+    /// Positions the reader so that the next frame read is frame `index`.
+    pub fn seek_frame(&mut self, index: u64) -> io::Result<()> {
+        self.inner
+            .seek(SeekFrom::Start(index * self.frame_size() as u64))?;
+        self.index = index;
+        Ok(())
+    }
+
+    /// Number of whole frames in the stream.
+    pub fn frame_count(&mut self) -> io::Result<u64> {
+        let position = self.inner.stream_position()?;
+        let len = self.inner.seek(SeekFrom::End(0))?;
+        self.inner.seek(SeekFrom::Start(position))?;
+        Ok(len / self.frame_size() as u64)
+    }
+}
+
+/// Yields each frame as a new `Vec`, one allocation per frame. Use
+/// [`next_frame`](RawYuvReader::next_frame) or
+/// [`for_each_frame`](RawYuvReader::for_each_frame) to reuse the internal
+/// buffer.
+impl<R: Read> Iterator for RawYuvReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame()
+            .map(|frame| frame.map(<[u8]>::to_vec))
+            .transpose()
+    }
+}
+
+fn check_frame<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    if (img.width, img.height) != (width, height) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "frame size {:?} does not match stream size {:?}",
+                (img.width, img.height),
+                (width, height)
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn write_y_plane<W: Write, T: Deref<Target = [u8]>>(
+    out: &mut W,
+    img: &NV12Image<T>,
+) -> io::Result<()> {
+    for y in 0..img.height {
+        out.write_all(img.y_row(y))?;
+    }
+    Ok(())
+}
+
+/// Appends NV12 frames to a headerless `.yuv` stream, the output side of
+/// [`RawYuvReader`]. Row padding of strided images is dropped, so every
+/// frame is written tightly packed like [`FrameDescriptor::packed`]: odd
+/// sizes keep a U, V pair for the last column and a chroma row for the last
+/// row.
+///
+/// [`FrameDescriptor::packed`]: crate::FrameDescriptor::packed
+pub struct RawYuvWriter<W: Write> {
+    inner: W,
+    width: u32,
+    height: u32,
+}
+
+impl RawYuvWriter<BufWriter<File>> {
+    /// Creates or truncates the file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P, width: u32, height: u32) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(BufWriter::new(file), width, height))
+    }
+
+    /// Opens the file at `path` for appending, creating it if needed.
+    pub fn append<P: AsRef<Path>>(path: P, width: u32, height: u32) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file), width, height))
+    }
+}
+
+impl<W: Write> RawYuvWriter<W> {
+    pub fn new(inner: W, width: u32, height: u32) -> Self {
+        Self {
+            inner,
+            width,
+            height,
+        }
+    }
+
+    pub fn write_frame<T: Deref<Target = [u8]>>(&mut self, img: &NV12Image<T>) -> io::Result<()> {
+        check_frame(img, self.width, self.height)?;
+        write_y_plane(&mut self.inner, img)?;
+        for y in 0..self.height.div_ceil(2) {
+            self.inner.write_all(img.uv_row(y))?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Flushes any buffered frames and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Writes NV12 frames as a YUV4MPEG2 stream, which `ffmpeg` and `mpv` can
+/// read without being told the frame size or pixel format.
+///
+/// Y4M has no semi-planar colorspace, so the interleaved chroma plane of
+/// each frame is split into separate U and V planes of `width.div_ceil(2)`
+/// by `height.div_ceil(2)` samples and the stream is tagged `C420jpeg`.
+pub struct Y4mWriter<W: Write> {
+    inner: W,
+    width: u32,
+    height: u32,
+    framerate: (u32, u32),
+    header_written: bool,
+    row: Vec<u8>,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    /// Creates a writer for `width`x`height` frames at 25 fps. The header is
+    /// written together with the first frame.
+    pub fn new(inner: W, width: u32, height: u32) -> Self {
+        Self {
+            inner,
+            width,
+            height,
+            framerate: (25, 1),
+            header_written: false,
+            row: Vec::with_capacity(width as usize),
+        }
+    }
+
+    /// Sets the frame rate announced in the header as `num / den` fps.
+    pub fn with_framerate(mut self, num: u32, den: u32) -> Self {
+        self.framerate = (num, den);
+        self
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        writeln!(
+            self.inner,
+            "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C420jpeg",
+            self.width, self.height, self.framerate.0, self.framerate.1
+        )
+    }
+
+    /// Appends one frame, writing the stream header first if needed.
+    pub fn write_frame<T: Deref<Target = [u8]>>(&mut self, img: &NV12Image<T>) -> io::Result<()> {
+        check_frame(img, self.width, self.height)?;
+        if !self.header_written {
+            self.write_header()?;
+            self.header_written = true;
+        }
+        self.inner.write_all(b"FRAME\n")?;
+
+        write_y_plane(&mut self.inner, img)?;
+        for plane in 0..2 {
+            for y in 0..self.height.div_ceil(2) {
+                let uv = img.uv_row(y);
+                self.row.clear();
+                self.row.extend(uv.iter().skip(plane).step_by(2));
+                self.inner.write_all(&self.row)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn raw_reader_frames_and_seek() {
+        let frame_size = PixelFormat::NV12.frame_size(4, 2);
+        let data: Vec<u8> = (0..3 * frame_size)
+            .map(|i| (i / frame_size) as u8)
+            .collect();
+        let mut reader = RawYuvReader::from_reader(Cursor::new(data), 4, 2, PixelFormat::NV12);
+
+        assert_eq!(reader.frame_count().unwrap(), 3);
+        reader.seek_frame(2).unwrap();
+        assert_eq!(reader.next_frame().unwrap().unwrap(), &[2; 12][..]);
+        assert!(reader.next_frame().unwrap().is_none());
+
+        reader.seek_frame(1).unwrap();
+        let frame = reader.try_next_frame().unwrap().unwrap();
+        assert_eq!((frame.meta.index, frame.y_row(0)), (1, &[1; 4][..]));
+        let timestamp = frame.meta.timestamp();
+        assert_eq!(timestamp, Some(std::time::Duration::from_millis(40)));
+
+        reader.seek_frame(0).unwrap();
+        let mut firsts = Vec::new();
+        reader
+            .for_each_frame(|frame| firsts.push(frame[0]))
+            .unwrap();
+        assert_eq!(firsts, [0, 1, 2]);
+
+        reader.seek_frame(0).unwrap();
+        let frames: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1], vec![1; 12]);
+    }
+
+    #[test]
+    fn raw_reader_truncated_frame() {
+        let mut reader = RawYuvReader::from_reader(&[0u8; 20][..], 4, 2, PixelFormat::NV12);
+        assert!(reader.next_frame().unwrap().is_some());
+        let err = reader.next_frame().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut reader = RawYuvReader::from_reader(&[0u8; 20][..], 4, 2, PixelFormat::NV12);
+        assert!(reader.try_next_image().unwrap().is_some());
+        assert!(matches!(reader.try_next_image(), Err(YuvError::Io(_))));
+        let mut reader = RawYuvReader::from_reader(&[0u8; 20][..], 2, 2, PixelFormat::YUYV);
+        assert!(matches!(
+            reader.try_next_image(),
+            Err(YuvError::UnsupportedFormat(PixelFormat::YUYV))
+        ));
+    }
+
+    #[test]
+    fn raw_writer_drops_stride_padding() {
+        // 2x2 frame with rows padded to 4 bytes; padding is 0xff.
+        let data = vec![1, 2, 0xff, 0xff, 3, 4, 0xff, 0xff, 5, 6, 0xff, 0xff];
+        let img = NV12Image::from_strided(data, 2, 2, 4, 4);
+
+        let mut writer = RawYuvWriter::new(Vec::new(), 2, 2);
+        writer.write_frame(&img).unwrap();
+        writer.write_frame(&img).unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(out, [1, 2, 3, 4, 5, 6].repeat(2));
+
+        let mut reader = RawYuvReader::from_reader(&out[..], 2, 2, PixelFormat::NV12);
+        assert_eq!(reader.next_frame().unwrap().unwrap(), &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn y4m_header_and_planes() {
+        // 4x2 frame: Y = 0..8, UV = [u0, v0, u1, v1]
+        let mut data: Vec<u8> = (0..8).collect();
+        data.extend([10, 20, 11, 21]);
+        let img = NV12Image::from(data, 4, 2);
+
+        let mut writer = Y4mWriter::new(Vec::new(), 4, 2).with_framerate(30, 1);
+        writer.write_frame(&img).unwrap();
+        writer.write_frame(&img).unwrap();
+        let out = writer.into_inner();
+
+        let header = b"YUV4MPEG2 W4 H2 F30:1 Ip A1:1 C420jpeg\n";
+        assert!(out.starts_with(header));
+        let frame = &out[header.len()..];
+        assert_eq!(&frame[..6], b"FRAME\n");
+        assert_eq!(&frame[6..18], &[0, 1, 2, 3, 4, 5, 6, 7, 10, 11, 20, 21]);
+        assert_eq!(out.len(), header.len() + 2 * (6 + 12));
+    }
+
+    #[test]
+    fn odd_sizes_keep_the_last_chroma() {
+        // 3x3 frame: Y = 0..9, UV rows [u0, v0, u1, v1] of 10.., 20..
+        let mut data: Vec<u8> = (0..9).collect();
+        data.extend([10, 20, 11, 21, 12, 22, 13, 23]);
+        let img = NV12Image::from(data.clone(), 3, 3);
+
+        let mut writer = RawYuvWriter::new(Vec::new(), 3, 3);
+        writer.write_frame(&img).unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(out, data);
+        assert_eq!(out.len(), PixelFormat::NV12.frame_size(3, 3));
+
+        let mut writer = Y4mWriter::new(Vec::new(), 3, 3);
+        writer.write_frame(&img).unwrap();
+        let out = writer.into_inner();
+        let frame = &out[out.len() - 9 - 8..];
+        assert_eq!(&frame[9..], &[10, 11, 12, 13, 20, 21, 22, 23]);
+    }
+
+    #[test]
+    fn y4m_rejects_mismatched_frame() {
+        let img = NV12Image::from(vec![0u8; 6], 2, 2);
+        let mut writer = Y4mWriter::new(Vec::new(), 4, 2);
+        assert!(writer.write_frame(&img).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::PixelFormat;
+
+/// Reads consecutive raw frames from any [`AsyncRead`], such as a socket or
+/// the stdout of an `ffmpeg` child process, as a [`Stream`] of frames.
+///
+/// The stream ends cleanly at a frame boundary and yields an
+/// `UnexpectedEof` error if the input stops in the middle of a frame.
+pub struct AsyncYuvReader<R> {
+    inner: R,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    buf: Vec<u8>,
+    filled: usize,
+}
+
+impl<R: AsyncRead + Unpin> AsyncYuvReader<R> {
+    pub fn new(inner: R, width: u32, height: u32, format: PixelFormat) -> Self {
+        Self {
+            inner,
+            width,
+            height,
+            format,
+            buf: vec![0; format.frame_size(width, height)],
+            filled: 0,
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Size of one frame in bytes.
+    pub fn frame_size(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncYuvReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while this.filled < this.buf.len() {
+            let mut read_buf = ReadBuf::new(&mut this.buf[this.filled..]);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut read_buf))?;
+            let n = read_buf.filled().len();
+            if n == 0 {
+                if this.filled == 0 {
+                    return Poll::Ready(None);
+                }
+                let filled = std::mem::take(&mut this.filled);
+                return Poll::Ready(Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("truncated frame: {} of {} bytes", filled, this.buf.len()),
+                ))));
+            }
+            this.filled += n;
+        }
+        this.filled = 0;
+        Poll::Ready(Some(Ok(this.buf.clone())))
+    }
+}
@@ -1,33 +1,251 @@
-use std::ops::IndexMut;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use image::{GenericImage, GenericImageView, Luma, LumaA, Pixel, Rgb, Rgba};
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::slice;
+
+#[cfg(feature = "std")]
+use image::error::{ParameterError, ParameterErrorKind};
+#[cfg(feature = "std")]
+use image::{
+    GenericImage, GenericImageView, ImageError, ImageResult, Luma, LumaA, Pixel, Rgb, Rgba,
+};
+
+pub mod adjust;
+#[cfg(feature = "std")]
+pub mod annotate;
+#[cfg(feature = "std")]
+pub mod antialias;
+pub mod bayer;
+mod builder;
+#[cfg(feature = "std")]
+pub mod chroma_key;
+pub mod colors;
+pub mod compositor;
+pub mod convert;
+#[cfg(all(
+    any(feature = "macos", feature = "ios"),
+    any(target_os = "macos", target_os = "ios")
+))]
+pub mod corevideo;
+pub mod deinterlace;
+pub mod denoise;
+#[cfg(all(feature = "dmabuf", target_os = "linux"))]
+pub mod dmabuf;
+#[cfg(feature = "emoji")]
+pub mod emoji;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "ffmpeg")]
+pub mod ffmpeg;
+mod fill;
+mod format;
+mod frame;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "gstreamer")]
+pub mod gstreamer;
+pub mod imageops;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "std")]
+pub mod motion;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+#[cfg(all(feature = "std", feature = "opencv"))]
+pub mod opencv;
+#[cfg(feature = "std")]
+pub mod overlay;
+pub mod patterns;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "python")]
+pub mod python;
+mod region;
+#[cfg(feature = "std")]
+mod scaled;
+mod shared;
+mod smooth;
+pub mod stats;
+pub mod tensor;
+#[cfg(feature = "text")]
+pub mod text;
+pub mod v4l2;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(all(feature = "std", feature = "wgpu"))]
+pub mod wgpu;
+mod yuv420;
+mod yuyv;
+
+pub use builder::Nv12ImageBuilder;
+pub use convert::ColorMatrix;
+pub use error::YuvError;
+pub use format::{FrameDescriptor, PixelFormat};
+pub use frame::{Frame, FrameMeta};
+pub use region::Region;
+#[cfg(feature = "std")]
+pub use scaled::ScaledDraw;
+pub use shared::{CowFrame, Nv12Frame};
+pub use smooth::SmoothChromaView;
+pub use yuv420::{Plane, YUV420Image};
+pub use yuyv::YUYVImage;
 
 #[repr(C)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct YUV(pub [u8; 3]);
 
-pub const BLACK: YUV = YUV([0, 0x80, 0x80]);
-pub const WHITE: YUV = YUV([0xff, 0x80, 0x80]);
-pub const RED: YUV = YUV([0x4c, 0x55, 0xff]);
-pub const GREEN: YUV = YUV([0, 0, 0]);
-pub const CYAN: YUV = YUV([0xb3, 0xab, 0x00]);
-pub const BLUE: YUV = YUV([0x1d, 0xff, 0x6b]);
-pub const YELLOW: YUV = YUV([0xe2, 0x00, 0x95]);
+// SAFETY: `YUV` is `repr(C)` over `[u8; 3]`, so it has no padding and every
+// bit pattern is valid.
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for YUV {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for YUV {}
+
+/// Converts with the same full range BT.601 matrix as [`YUV::from_rgb`], in
+/// 16.16 fixed point so it can define constants. Results may differ from
+/// `from_rgb` by one in rounding. See [`colors`] for the CSS palette.
+pub const fn yuv_from_rgb(r: u8, g: u8, b: u8) -> YUV {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    let y = 19_595 * r + 38_470 * g + 7_471 * b;
+    let u = -11_076 * r - 21_692 * g + 32_768 * b;
+    let v = 32_768 * r - 27_460 * g - 5_308 * b;
+    const fn channel(c: i32, offset: i32) -> u8 {
+        let c = ((c + (1 << 15)) >> 16) + offset;
+        if c < 0 {
+            0
+        } else if c > 255 {
+            255
+        } else {
+            c as u8
+        }
+    }
+    YUV([channel(y, 0), channel(u, 128), channel(v, 128)])
+}
+
+pub const BLACK: YUV = yuv_from_rgb(0, 0, 0);
+pub const WHITE: YUV = yuv_from_rgb(255, 255, 255);
+pub const RED: YUV = yuv_from_rgb(255, 0, 0);
+pub const GREEN: YUV = yuv_from_rgb(0, 255, 0);
+pub const CYAN: YUV = yuv_from_rgb(0, 255, 255);
+pub const BLUE: YUV = yuv_from_rgb(0, 0, 255);
+pub const YELLOW: YUV = yuv_from_rgb(255, 255, 0);
 
 impl YUV {
-    fn rgb(&self) -> [u8; 3] {
+    /// Converts with the full range BT.601 matrix, the inverse of
+    /// [`rgb`](Self::rgb).
+    pub fn from_rgb(rgb: [u8; 3]) -> Self {
+        let r = rgb[0] as f32;
+        let g = rgb[1] as f32;
+        let b = rgb[2] as f32;
+        let y = 0.299 * r + 0.587 * g + 0.114 * b;
+        let u = -0.169 * r - 0.331 * g + 0.5 * b + 128.;
+        let v = 0.5 * r - 0.419 * g - 0.081 * b + 128.;
+        YUV([(y + 0.5) as u8, (u + 0.5) as u8, (v + 0.5) as u8])
+    }
+
+    /// Converts to RGB, as `Pixel::to_rgb` does. Uses 16.16 fixed-point
+    /// arithmetic, rounding to nearest and saturating each channel.
+    pub fn rgb(&self) -> [u8; 3] {
+        let y = (self.0[0] as i32) << 16;
+        let u = self.0[1] as i32 - 128;
+        let v = self.0[2] as i32 - 128;
+        let channel = |c: i32| ((c + (1 << 15)) >> 16).clamp(0, 255) as u8;
+        [
+            channel(y + RGB_FIXED[0] * v),
+            channel(y - RGB_FIXED[1] * u - RGB_FIXED[2] * v),
+            channel(y + RGB_FIXED[3] * u),
+        ]
+    }
+
+    /// The photographic negative: luma is inverted and chroma reflected
+    /// about neutral 128, so each color turns into its complement.
+    /// `Pixel::invert` instead flips all channels around 255, which shifts
+    /// the hue.
+    pub fn negate(&self) -> Self {
+        let reflect = |c: u8| (256 - c as u16).min(255) as u8;
+        YUV([255 - self.0[0], reflect(self.0[1]), reflect(self.0[2])])
+    }
+
+    /// Linear interpolation from `self` at `t = 0` to `other` at `t = 1`,
+    /// per channel. `t` is clamped to `0.0..=1.0`.
+    pub fn lerp(&self, other: YUV, t: f32) -> Self {
+        let t = t.clamp(0., 1.);
+        let mut out = self.0;
+        for (a, &b) in out.iter_mut().zip(&other.0) {
+            *a = (*a as f32 + (b as f32 - *a as f32) * t + 0.5) as u8;
+        }
+        YUV(out)
+    }
+
+    /// The per-channel average of `a` and `b` weighted by `wa` and `wb`,
+    /// rounded to nearest. Returns `a` if both weights are zero.
+    pub fn blend_weighted(a: YUV, b: YUV, wa: u32, wb: u32) -> Self {
+        let total = wa as u64 + wb as u64;
+        if total == 0 {
+            return a;
+        }
+        let mut out = a.0;
+        for (c, &d) in out.iter_mut().zip(&b.0) {
+            *c = ((*c as u64 * wa as u64 + d as u64 * wb as u64 + total / 2) / total) as u8;
+        }
+        YUV(out)
+    }
+
+    /// Per-channel saturating addition.
+    pub fn saturating_add(&self, other: YUV) -> Self {
+        let [y, u, v] = self.0;
+        YUV([
+            y.saturating_add(other.0[0]),
+            u.saturating_add(other.0[1]),
+            v.saturating_add(other.0[2]),
+        ])
+    }
+
+    /// Per-channel saturating subtraction.
+    pub fn saturating_sub(&self, other: YUV) -> Self {
+        let [y, u, v] = self.0;
+        YUV([
+            y.saturating_sub(other.0[0]),
+            u.saturating_sub(other.0[1]),
+            v.saturating_sub(other.0[2]),
+        ])
+    }
+
+    /// Floating point variant of [`rgb`](Self::rgb) without quantization,
+    /// each channel in `0.0..=255.0`.
+    pub fn rgb_f32(&self) -> [f32; 3] {
         let y = self.0[0] as f32;
-        let u = self.0[1] as f32;
-        let v = self.0[2] as f32;
-        let r = y + (140. * (v - 128.)) / 100.;
-        let g = y - (34. * (u - 128.)) / 100. - (71. * (v - 128.)) / 100.;
-        let b = y + (177. * (u - 128.)) / 100.;
-        [r as u8, g as u8, b as u8]
+        let u = self.0[1] as f32 - 128.;
+        let v = self.0[2] as f32 - 128.;
+        let [kr, kgu, kgv, kb] = RGB_COEFFICIENTS;
+        [
+            (y + kr * v).clamp(0., 255.),
+            (y - kgu * u - kgv * v).clamp(0., 255.),
+            (y + kb * u).clamp(0., 255.),
+        ]
     }
 }
 
+/// Chroma coefficients of [`YUV::rgb`]: V for red, U and V for green, U for
+/// blue.
+const RGB_COEFFICIENTS: [f32; 4] = [1.40, 0.34, 0.71, 1.77];
+/// [`RGB_COEFFICIENTS`] scaled by 2^16.
+const RGB_FIXED: [i32; 4] = [91_750, 22_282, 46_531, 116_000];
+
+#[cfg(feature = "std")]
 const DEFAULT_MAX_VALUE: u8 = 255;
 
+#[cfg(feature = "std")]
 impl Pixel for YUV {
     type Subpixel = u8;
 
@@ -147,6 +365,7 @@ impl Pixel for YUV {
         }
     }
 
+    // Kept as is for compatibility; `YUV::negate` is the true negative.
     fn invert(&mut self) {
         let yuv = self.0;
 
@@ -164,14 +383,48 @@ impl Pixel for YUV {
     }
 }
 
-pub struct NV12Image<T: IndexMut<usize, Output = u8>> {
+/// How [`NV12Image::set_pixel`] writes a pixel, given that each 2x2 block
+/// of pixels shares one chroma sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChromaWrite {
+    /// Sets the luma of the whole block along with its chroma, so thin
+    /// strokes come out two pixels wide but keep their color.
+    #[default]
+    Block,
+    /// Sets the luma of the pixel alone along with the chroma of its block,
+    /// keeping one pixel detail in the luma.
+    Pixel,
+}
+
+/// A frame in the NV12 layout: a Y plane and an interleaved UV plane at half
+/// resolution in both directions.
+///
+/// Samples are bytes by default. Other sample types `S`, such as `u16` for
+/// the layout of P010 and P016, share the layout, row access and
+/// [`samples`](Self::samples) accessors; the [`YUV`] pixel API and drawing
+/// are for bytes.
+#[derive(Clone)]
+pub struct NV12Image<T, S = u8>
+where
+    T: Deref<Target = [S]>,
+{
     data: T,
+    /// Separate UV plane; when `None` it lives in `data` at `uv_offset`.
+    uv_data: Option<T>,
     width: u32,
     height: u32,
-    gray_size: u32,
+    y_stride: u32,
+    uv_stride: u32,
+    uv_offset: u32,
+    color_matrix: ColorMatrix,
+    chroma_write: ChromaWrite,
 }
 
-impl<T: IndexMut<usize, Output = u8>> NV12Image<T> {
+/// An NV12 image owning its tightly packed planes, as returned by the
+/// conversions and [`patterns`] generators.
+pub type Nv12Buffer = NV12Image<Vec<u8>>;
+
+impl<S: Copy, T: Deref<Target = [S]>> NV12Image<T, S> {
     fn check_bounds(&self, x: u32, y: u32) {
         if x >= self.width || y >= self.height {
             panic!(
@@ -182,85 +435,660 @@ impl<T: IndexMut<usize, Output = u8>> NV12Image<T> {
         }
     }
 
+    fn try_check_bounds(&self, x: u32, y: u32) -> Result<(), YuvError> {
+        if x >= self.width || y >= self.height {
+            return Err(YuvError::OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(())
+    }
+
     fn to_zero_or_even(n: u32) -> u32 {
         n - n % 2
     }
 
+    /// Indices of the luma byte in the Y plane and of the chroma pair in
+    /// [`uv_plane`](Self::uv_plane).
     fn pixel_indices(&self, x: u32, y: u32) -> (usize, usize, usize) {
-        let offset = y * self.width;
-        let y_index = offset + x;
-        let uv_index = self.gray_size + offset / 2 + x;
+        let y_index = y * self.y_stride + x;
+        let uv_index = y / 2 * self.uv_stride + x;
         (y_index as usize, uv_index as usize, uv_index as usize + 1)
     }
 
+    fn uv_plane(&self) -> &[S] {
+        match &self.uv_data {
+            Some(uv) => uv,
+            None => &self.data[self.uv_offset as usize..],
+        }
+    }
+
+    /// Wraps a frame laid out like [`FrameDescriptor::packed`]: rows without
+    /// padding, chroma rows holding one U, V pair per 2x2 block.
     pub fn from(data: T, width: u32, height: u32) -> Self {
+        Self::from_strided(data, width, height, width, width.div_ceil(2) * 2)
+    }
+
+    /// Wraps a frame whose rows are padded: each Y row is `y_stride` bytes
+    /// long, each UV row `uv_stride` bytes, and the UV plane directly follows
+    /// the `height` rows of the Y plane.
+    ///
+    /// With the `strict` feature, debug builds panic here if the geometry
+    /// does not fit `data`, like [`try_from_strided`](Self::try_from_strided)
+    /// would fail.
+    pub fn from_strided(data: T, width: u32, height: u32, y_stride: u32, uv_stride: u32) -> Self {
+        let img = Self::new_strided(data, width, height, y_stride, uv_stride);
+        img.strict_validate();
+        img
+    }
+
+    fn new_strided(data: T, width: u32, height: u32, y_stride: u32, uv_stride: u32) -> Self {
         Self {
             data,
+            uv_data: None,
             width,
             height,
-            gray_size: width * height,
+            y_stride,
+            uv_stride,
+            uv_offset: y_stride * height,
+            color_matrix: ColorMatrix::DEFAULT,
+            chroma_write: ChromaWrite::Block,
+        }
+    }
+
+    /// Wraps a frame whose Y and UV planes live in separate buffers, as
+    /// with multi-planar V4L2 or GStreamer buffers. Checked like
+    /// [`from_strided`](Self::from_strided) with the `strict` feature.
+    pub fn from_planes(
+        y: T,
+        uv: T,
+        width: u32,
+        height: u32,
+        y_stride: u32,
+        uv_stride: u32,
+    ) -> Self {
+        let img = Self::new_planes(y, uv, width, height, y_stride, uv_stride);
+        img.strict_validate();
+        img
+    }
+
+    fn new_planes(y: T, uv: T, width: u32, height: u32, y_stride: u32, uv_stride: u32) -> Self {
+        Self {
+            data: y,
+            uv_data: Some(uv),
+            width,
+            height,
+            y_stride,
+            uv_stride,
+            uv_offset: 0,
+            color_matrix: ColorMatrix::DEFAULT,
+            chroma_write: ChromaWrite::Block,
+        }
+    }
+
+    /// Checks that the strides fit the width and that the planes hold all
+    /// their rows.
+    fn validate(&self) -> Result<(), YuvError> {
+        let chroma_row = self.width.div_ceil(2) * 2;
+        for (stride, min) in [(self.y_stride, self.width), (self.uv_stride, chroma_row)] {
+            if stride < min {
+                return Err(YuvError::StrideTooSmall { stride, min });
+            }
+        }
+        let y_len = self.y_stride as usize * self.height as usize;
+        let uv_len = self.uv_stride as usize * self.height.div_ceil(2) as usize;
+        let (len, needed) = match &self.uv_data {
+            Some(_) if self.data.len() < y_len => (self.data.len(), y_len),
+            Some(uv) => (uv.len(), uv_len),
+            None if (self.uv_offset as usize) < y_len => {
+                return Err(YuvError::PlanesOverlap {
+                    uv_offset: self.uv_offset as usize,
+                    y_len,
+                })
+            }
+            None => (self.data.len(), self.uv_offset as usize + uv_len),
+        };
+        if len < needed {
+            return Err(YuvError::BufferTooSmall { len, needed });
+        }
+        Ok(())
+    }
+
+    /// Panics with the geometry error if the `strict` feature is enabled in a
+    /// debug build and [`validate`](Self::validate) fails.
+    fn strict_validate(&self) {
+        #[cfg(all(feature = "strict", debug_assertions))]
+        if let Err(e) = self.validate() {
+            panic!("invalid NV12 geometry: {}", e)
+        }
+    }
+
+    /// Panics with the plane and row if the `strict` feature is enabled in a
+    /// debug build and `start..end` does not fit in a buffer of `len`.
+    #[cfg_attr(
+        not(all(feature = "strict", debug_assertions)),
+        allow(unused_variables)
+    )]
+    fn strict_check(plane: &'static str, row: u32, start: usize, end: usize, len: usize) {
+        #[cfg(all(feature = "strict", debug_assertions))]
+        if end > len {
+            let e = YuvError::RowOutOfRange {
+                plane,
+                row,
+                start,
+                end,
+                len,
+            };
+            panic!("{}", e)
+        }
+    }
+
+    fn check_y_row(&self, row: u32) {
+        let start = row as usize * self.y_stride as usize;
+        let end = start + self.width as usize;
+        Self::strict_check("Y", row, start, end, self.data.len());
+    }
+
+    fn check_uv_row(&self, row: u32) {
+        let (offset, len) = match &self.uv_data {
+            Some(uv) => (0, uv.len()),
+            None => (self.uv_offset as usize, self.data.len()),
+        };
+        let start = offset + row as usize * self.uv_stride as usize;
+        let end = start + self.width.div_ceil(2) as usize * 2;
+        Self::strict_check("UV", row, start, end, len);
+    }
+
+    /// Wraps a frame laid out like [`FrameDescriptor::packed`], checking that
+    /// `data` holds all of it so later accesses cannot panic, unlike
+    /// [`from`](Self::from).
+    pub fn try_from(data: T, width: u32, height: u32) -> Result<Self, YuvError> {
+        Self::try_from_strided(data, width, height, width, width.div_ceil(2) * 2)
+    }
+
+    /// Like [`from_strided`](Self::from_strided), but checks the strides and
+    /// the buffer length.
+    pub fn try_from_strided(
+        data: T,
+        width: u32,
+        height: u32,
+        y_stride: u32,
+        uv_stride: u32,
+    ) -> Result<Self, YuvError> {
+        let img = Self::new_strided(data, width, height, y_stride, uv_stride);
+        img.validate()?;
+        Ok(img)
+    }
+
+    /// Like [`from_planes`](Self::from_planes), but checks the strides and
+    /// the plane lengths.
+    pub fn try_from_planes(
+        y: T,
+        uv: T,
+        width: u32,
+        height: u32,
+        y_stride: u32,
+        uv_stride: u32,
+    ) -> Result<Self, YuvError> {
+        let img = Self::new_planes(y, uv, width, height, y_stride, uv_stride);
+        img.validate()?;
+        Ok(img)
+    }
+
+    /// Moves the start of the UV plane to byte `uv_offset` of the buffer, for
+    /// layouts with a gap between the planes. Has no effect on images built
+    /// with [`from_planes`](Self::from_planes).
+    pub fn with_uv_offset(mut self, uv_offset: u32) -> Self {
+        self.uv_offset = uv_offset;
+        if self.uv_data.is_none() {
+            self.strict_validate();
         }
+        self
+    }
+
+    /// Records the matrix and range the samples are encoded with, see
+    /// [`color_matrix`](Self::color_matrix). Defaults to
+    /// [`ColorMatrix::DEFAULT`].
+    pub fn with_color_matrix(mut self, color_matrix: ColorMatrix) -> Self {
+        self.color_matrix = color_matrix;
+        self
+    }
+
+    /// Sets how [`set_pixel`](NV12Image::set_pixel) and drawing through
+    /// `GenericImage` write pixels. Defaults to [`ChromaWrite::Block`].
+    pub fn with_chroma_write(mut self, chroma_write: ChromaWrite) -> Self {
+        self.chroma_write = chroma_write;
+        self
     }
 
+    /// The matrix and range the samples are encoded with, for building
+    /// [`RgbTables`](convert::RgbTables) that convert them correctly.
+    pub fn color_matrix(&self) -> ColorMatrix {
+        self.color_matrix
+    }
+
+    pub fn chroma_write(&self) -> ChromaWrite {
+        self.chroma_write
+    }
+
+    pub fn strides(&self) -> (u32, u32) {
+        (self.y_stride, self.uv_stride)
+    }
+
+    /// Returns the backing buffer, or only the Y plane of an image built with
+    /// [`from_planes`](Self::from_planes).
     pub fn take_data(self) -> T {
         self.data
     }
 
+    /// Returns the backing buffer and, if the image has one, the separate UV
+    /// plane.
+    pub fn into_planes(self) -> (T, Option<T>) {
+        (self.data, self.uv_data)
+    }
+
+    /// Borrows the image as a view of its planes.
+    pub fn view(&self) -> NV12Image<&[S], S> {
+        NV12Image {
+            data: &self.data,
+            uv_data: self.uv_data.as_deref(),
+            width: self.width,
+            height: self.height,
+            y_stride: self.y_stride,
+            uv_stride: self.uv_stride,
+            uv_offset: self.uv_offset,
+            color_matrix: self.color_matrix,
+            chroma_write: self.chroma_write,
+        }
+    }
+
+    /// Moves the planes into another storage type with `f`, keeping the
+    /// layout.
+    fn map_storage<U, F>(self, mut f: F) -> NV12Image<U, S>
+    where
+        U: Deref<Target = [S]>,
+        F: FnMut(T) -> U,
+    {
+        NV12Image {
+            data: f(self.data),
+            uv_data: self.uv_data.map(&mut f),
+            width: self.width,
+            height: self.height,
+            y_stride: self.y_stride,
+            uv_stride: self.uv_stride,
+            uv_offset: self.uv_offset,
+            color_matrix: self.color_matrix,
+            chroma_write: self.chroma_write,
+        }
+    }
+
     pub fn ref_data(&self) -> &T {
         &self.data
     }
+
+    /// The `width` luma bytes of row `y`, without padding.
+    pub fn y_row(&self, y: u32) -> &[S] {
+        self.check_y_row(y);
+        let start = (y * self.y_stride) as usize;
+        &self.data[start..start + self.width as usize]
+    }
+
+    /// The interleaved chroma bytes of chroma row `y`, which covers image
+    /// rows `2 * y` and `2 * y + 1`: one U, V pair per 2x2 block, so
+    /// `width` bytes rounded up to even, without padding.
+    pub fn uv_row(&self, y: u32) -> &[S] {
+        self.check_uv_row(y);
+        let start = (y * self.uv_stride) as usize;
+        &self.uv_plane()[start..start + self.width.div_ceil(2) as usize * 2]
+    }
+
+    /// The `[Y, U, V]` samples of the 2x2 block containing `(x, y)`, read
+    /// from its top-left pixel. [`pixel`](NV12Image::pixel) for any sample
+    /// type.
+    pub fn samples(&self, x: u32, y: u32) -> [S; 3] {
+        self.check_bounds(x, y);
+        let x = Self::to_zero_or_even(x);
+        let y = Self::to_zero_or_even(y);
+        self.check_y_row(y);
+        self.check_uv_row(y / 2);
+        let indices = self.pixel_indices(x, y);
+        let uv = self.uv_plane();
+        [self.data[indices.0], uv[indices.1], uv[indices.2]]
+    }
 }
 
-impl<T: IndexMut<usize, Output = u8>> GenericImageView for NV12Image<T> {
-    type Pixel = YUV;
+impl<S: Copy, T: DerefMut<Target = [S]>> NV12Image<T, S> {
+    /// Mutable [`y_row`](Self::y_row).
+    pub fn y_row_mut(&mut self, y: u32) -> &mut [S] {
+        self.check_y_row(y);
+        let start = (y * self.y_stride) as usize;
+        &mut self.data[start..start + self.width as usize]
+    }
 
-    fn dimensions(&self) -> (u32, u32) {
-        (self.width, self.height)
+    /// Mutable [`uv_row`](Self::uv_row).
+    pub fn uv_row_mut(&mut self, y: u32) -> &mut [S] {
+        self.check_uv_row(y);
+        let start = (y * self.uv_stride) as usize;
+        let len = self.width.div_ceil(2) as usize * 2;
+        &mut self.split_planes_mut().1[start..start + len]
     }
 
-    fn bounds(&self) -> (u32, u32, u32, u32) {
-        (0, 0, self.width, self.height)
+    /// [`set_pixel`](NV12Image::set_pixel) for any sample type.
+    pub fn set_samples(&mut self, x: u32, y: u32, samples: [S; 3]) {
+        let block = self.chroma_write == ChromaWrite::Block;
+        self.write_samples(x, y, samples, block)
     }
 
-    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+    /// Writes the chroma of the block containing `(x, y)`, and the luma of
+    /// the whole block or only of `(x, y)`.
+    fn write_samples(&mut self, x: u32, y: u32, samples: [S; 3], block: bool) {
         self.check_bounds(x, y);
+        let (px, py) = (x, y);
         let x = Self::to_zero_or_even(x);
         let y = Self::to_zero_or_even(y);
         let indices = self.pixel_indices(x, y);
+        let y_stride = self.y_stride as usize;
+        // The block is cut short at the right and bottom edges of frames
+        // with odd dimensions.
+        let columns = (self.width - x).min(2) as usize;
+        let rows = (self.height - y).min(2) as usize;
+        self.check_y_row(y + rows as u32 - 1);
+        self.check_uv_row(y / 2);
+        let (luma, uv) = self.split_planes_mut();
+        if block {
+            for row in 0..rows {
+                let start = indices.0 + row * y_stride;
+                luma[start..start + columns].fill(samples[0]);
+            }
+        } else {
+            luma[py as usize * y_stride + px as usize] = samples[0];
+        }
+        uv[indices.1] = samples[1];
+        uv[indices.2] = samples[2];
+    }
+
+    /// The Y plane and the UV plane, both starting at their first row.
+    fn split_planes_mut(&mut self) -> (&mut [S], &mut [S]) {
+        match &mut self.uv_data {
+            Some(uv) => (&mut self.data, uv),
+            None => self.data.split_at_mut(self.uv_offset as usize),
+        }
+    }
+
+    /// Splits the image into horizontal bands of `rows` rows (rounded up to
+    /// an even number; the last band may be shorter). The bands borrow
+    /// disjoint parts of both planes, so they can be drawn on from separate
+    /// threads.
+    pub fn split_rows_mut(&mut self, rows: u32) -> Vec<NV12Image<&mut [S], S>> {
+        let rows = rows.max(1).div_ceil(2) * 2;
+        let (width, height) = (self.width, self.height);
+        let (y_stride, uv_stride) = (self.y_stride, self.uv_stride);
+        let (color_matrix, chroma_write) = (self.color_matrix, self.chroma_write);
+        let (mut luma, mut chroma) = self.split_planes_mut();
+        let mut bands = Vec::new();
+        let mut top = 0;
+        while top < height {
+            let band = rows.min(height - top);
+            let (y_len, uv_len) = if top + band == height {
+                (luma.len(), chroma.len())
+            } else {
+                ((band * y_stride) as usize, (band / 2 * uv_stride) as usize)
+            };
+            let y = split_off_front(&mut luma, y_len);
+            let uv = split_off_front(&mut chroma, uv_len);
+            let image = NV12Image::from_planes(y, uv, width, band, y_stride, uv_stride)
+                .with_color_matrix(color_matrix)
+                .with_chroma_write(chroma_write);
+            bands.push(image);
+            top += band;
+        }
+        bands
+    }
+}
+
+fn split_off_front<'a, S>(buf: &mut &'a mut [S], len: usize) -> &'a mut [S] {
+    let (head, tail) = mem::take(buf).split_at_mut(len);
+    *buf = tail;
+    head
+}
+
+impl<T: Deref<Target = [u8]>> NV12Image<T> {
+    /// The luma of `(x, y)` itself with the chroma of its 2x2 block, where
+    /// `get_pixel` reads the whole block from its top-left pixel.
+    fn sample(&self, x: u32, y: u32) -> YUV {
+        let y_index = (y * self.y_stride + x) as usize;
+        let (_, u_index, v_index) = self.pixel_indices(Self::to_zero_or_even(x), y);
+        let uv = self.uv_plane();
+        YUV([self.data[y_index], uv[u_index], uv[v_index]])
+    }
+
+    pub fn descriptor(&self) -> FrameDescriptor {
+        FrameDescriptor {
+            width: self.width,
+            height: self.height,
+            y_stride: self.y_stride,
+            uv_stride: self.uv_stride,
+            format: PixelFormat::NV12,
+        }
+    }
+
+    /// The whole backing buffer, including any row padding. Only the Y plane
+    /// for images built with [`from_planes`](Self::from_planes).
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The pixel of the 2x2 block containing `(x, y)`, read from its top-left
+    /// pixel. Same as `GenericImageView::get_pixel`, without needing the
+    /// `std` feature.
+    pub fn pixel(&self, x: u32, y: u32) -> YUV {
+        YUV(self.samples(x, y))
+    }
+
+    /// Like [`pixel`](Self::pixel), returning an error instead of panicking
+    /// when `(x, y)` is outside the image.
+    pub fn try_pixel(&self, x: u32, y: u32) -> Result<YUV, YuvError> {
+        self.try_check_bounds(x, y)?;
+        Ok(self.pixel(x, y))
+    }
+
+    /// [`pixel`](Self::pixel) without bounds checks, for hot loops over
+    /// geometry that is already clipped to the image.
+    ///
+    /// # Safety
+    ///
+    /// `(x, y)` must be inside the image and the buffer must hold the whole
+    /// frame, as checked by [`from_bytes`](NV12Image::from_bytes).
+    pub unsafe fn get_pixel_unchecked(&self, x: u32, y: u32) -> YUV {
+        self.check_y_row(y & !1);
+        self.check_uv_row(y / 2);
+        let (y_index, u_index, v_index) = self.pixel_indices(x & !1, y & !1);
+        let uv = self.uv_plane();
         YUV([
-            self.data[indices.0],
-            self.data[indices.1],
-            self.data[indices.2],
+            *self.data.get_unchecked(y_index),
+            *uv.get_unchecked(u_index),
+            *uv.get_unchecked(v_index),
         ])
     }
 }
 
-impl<T: IndexMut<usize, Output = u8>> GenericImage for NV12Image<T> {
+impl<T: DerefMut<Target = [u8]>> NV12Image<T> {
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Sets the 2x2 block containing `(x, y)` to `pixel`, all of its luma
+    /// or only that of `(x, y)` depending on
+    /// [`chroma_write`](NV12Image::chroma_write). Same as
+    /// `GenericImage::put_pixel`.
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: YUV) {
+        self.set_samples(x, y, pixel.0)
+    }
+
+    /// Like [`set_pixel`](Self::set_pixel), returning an error instead of
+    /// panicking when `(x, y)` is outside the image.
+    pub fn try_set_pixel(&mut self, x: u32, y: u32, pixel: YUV) -> Result<(), YuvError> {
+        self.try_check_bounds(x, y)?;
+        self.set_pixel(x, y, pixel);
+        Ok(())
+    }
+
+    /// [`set_pixel`](Self::set_pixel) without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`get_pixel_unchecked`](Self::get_pixel_unchecked). In
+    /// addition the whole 2x2 block must be inside the image, which always
+    /// holds for even dimensions.
+    pub unsafe fn put_pixel_unchecked(&mut self, x: u32, y: u32, pixel: YUV) {
+        self.check_y_row(y | 1);
+        self.check_uv_row(y / 2);
+        let (y_index, u_index, v_index) = self.pixel_indices(x & !1, y & !1);
+        let y_stride = self.y_stride as usize;
+        let block = self.chroma_write == ChromaWrite::Block;
+        let (luma, uv) = self.split_planes_mut();
+        if block {
+            *luma.get_unchecked_mut(y_index) = pixel.0[0];
+            *luma.get_unchecked_mut(y_index + 1) = pixel.0[0];
+            *luma.get_unchecked_mut(y_index + y_stride) = pixel.0[0];
+            *luma.get_unchecked_mut(y_index + y_stride + 1) = pixel.0[0];
+        } else {
+            *luma.get_unchecked_mut(y as usize * y_stride + x as usize) = pixel.0[0];
+        }
+        *uv.get_unchecked_mut(u_index) = pixel.0[1];
+        *uv.get_unchecked_mut(v_index) = pixel.0[2];
+    }
+}
+
+impl<'a> NV12Image<&'a [u8]> {
+    /// Borrows a tightly packed frame, or returns `None` if `bytes` is too
+    /// short for `width`x`height`.
+    pub fn from_bytes(bytes: &'a [u8], width: u32, height: u32) -> Option<Self> {
+        let len = PixelFormat::NV12.frame_size(width, height);
+        Some(Self::from(bytes.get(..len)?, width, height))
+    }
+}
+
+impl<'a> NV12Image<&'a mut [u8]> {
+    /// Mutable counterpart of [`NV12Image::from_bytes`].
+    pub fn from_bytes_mut(bytes: &'a mut [u8], width: u32, height: u32) -> Option<Self> {
+        let len = PixelFormat::NV12.frame_size(width, height);
+        Some(Self::from(bytes.get_mut(..len)?, width, height))
+    }
+
+    /// Wraps `len` bytes at `ptr` holding a frame laid out as described by
+    /// `desc`, such as a mapped dma-buf or a buffer owned by a C library.
+    /// Returns `None` if `desc` is not NV12 or `len` is too short for it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads and writes of `len` bytes for `'a`, and
+    /// the memory must not be accessed through any other pointer while the
+    /// image is alive.
+    pub unsafe fn from_raw_parts(ptr: *mut u8, len: usize, desc: FrameDescriptor) -> Option<Self> {
+        if desc.format != PixelFormat::NV12 || len < desc.frame_size() {
+            return None;
+        }
+        let data = slice::from_raw_parts_mut(ptr, len);
+        Some(Self::from_strided(
+            data,
+            desc.width,
+            desc.height,
+            desc.y_stride,
+            desc.uv_stride,
+        ))
+    }
+
+    /// Like [`from_raw_parts`](Self::from_raw_parts) for a frame whose Y and
+    /// UV planes are not contiguous, e.g. two dma-buf objects exported for
+    /// one surface. Returns `None` if `desc` is not NV12.
+    ///
+    /// # Safety
+    ///
+    /// `y` must be valid for reads and writes of `desc.y_stride * height`
+    /// bytes and `uv` of `desc.uv_stride * ceil(height / 2)` bytes for `'a`.
+    /// The two ranges must not overlap, and neither may be accessed through
+    /// any other pointer while the image is alive.
+    pub unsafe fn from_raw_planes(y: *mut u8, uv: *mut u8, desc: FrameDescriptor) -> Option<Self> {
+        if desc.format != PixelFormat::NV12 {
+            return None;
+        }
+        let y_len = desc.y_stride as usize * desc.height as usize;
+        let uv_len = desc.uv_stride as usize * desc.height.div_ceil(2) as usize;
+        Some(Self::from_planes(
+            slice::from_raw_parts_mut(y, y_len),
+            slice::from_raw_parts_mut(uv, uv_len),
+            desc.width,
+            desc.height,
+            desc.y_stride,
+            desc.uv_stride,
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Deref<Target = [u8]>> GenericImageView for NV12Image<T> {
+    type Pixel = YUV;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        (0, 0, self.width, self.height)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        self.pixel(x, y)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: DerefMut<Target = [u8]>> GenericImage for NV12Image<T> {
+    /// Always panics: the samples of a pixel are spread over two planes, so
+    /// there is no `YUV` to borrow. None of the `imageops` functions need it.
     fn get_pixel_mut(&mut self, _: u32, _: u32) -> &mut Self::Pixel {
-        todo!()
+        panic!("NV12Image has no addressable pixels, use put_pixel instead")
     }
 
     fn put_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
-        self.check_bounds(x, y);
-        let x = Self::to_zero_or_even(x);
-        let y = Self::to_zero_or_even(y);
-        let indices = self.pixel_indices(x, y);
-        self.data[indices.0] = pixel.0[0];
-        self.data[indices.0 + 1] = pixel.0[0];
-        self.data[indices.0 + self.width as usize] = pixel.0[0];
-        self.data[indices.0 + self.width as usize + 1] = pixel.0[0];
-        self.data[indices.1] = pixel.0[1];
-        self.data[indices.2] = pixel.0[2];
+        self.set_pixel(x, y, pixel)
     }
 
+    /// `YUV` has no alpha, so this replaces the pixel. Unlike `put_pixel`
+    /// only the luma of `(x, y)` is written, as `imageops::overlay` writes
+    /// every pixel of the top image.
     fn blend_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
-        self.put_pixel(x, y, pixel)
+        self.write_samples(x, y, pixel.0, false)
+    }
+
+    /// Copies the luma of every pixel of `other` and the average chroma of
+    /// the pixels in each 2x2 block, instead of the last one written.
+    fn copy_from<O>(&mut self, other: &O, x: u32, y: u32) -> ImageResult<()>
+    where
+        O: GenericImageView<Pixel = Self::Pixel>,
+    {
+        let (width, height) = other.dimensions();
+        if u64::from(x) + u64::from(width) > u64::from(self.width)
+            || u64::from(y) + u64::from(height) > u64::from(self.height)
+        {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+        self.paste_with(x, y, width, height, |px, py| other.get_pixel(px, py));
+        Ok(())
     }
 }
 
-pub struct NV12Image2<T: IndexMut<usize, Output = u8>>(pub NV12Image<T>);
+#[cfg(feature = "std")]
+pub struct NV12Image2<T: Deref<Target = [u8]>>(pub NV12Image<T>);
 
-impl<T: IndexMut<usize, Output = u8>> GenericImageView for NV12Image2<T> {
+#[cfg(feature = "std")]
+impl<T: Deref<Target = [u8]>> GenericImageView for NV12Image2<T> {
     type Pixel = YUV;
 
     fn dimensions(&self) -> (u32, u32) {
@@ -276,9 +1104,10 @@ impl<T: IndexMut<usize, Output = u8>> GenericImageView for NV12Image2<T> {
     }
 }
 
-impl<T: IndexMut<usize, Output = u8>> GenericImage for NV12Image2<T> {
+#[cfg(feature = "std")]
+impl<T: DerefMut<Target = [u8]>> GenericImage for NV12Image2<T> {
     fn get_pixel_mut(&mut self, _: u32, _: u32) -> &mut Self::Pixel {
-        todo!()
+        panic!("NV12Image2 has no addressable pixels, use put_pixel instead")
     }
 
     fn put_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
@@ -290,7 +1119,7 @@ impl<T: IndexMut<usize, Output = u8>> GenericImage for NV12Image2<T> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use std::{
         fs::File,
@@ -305,6 +1134,143 @@ mod tests {
 
     use super::*;
     #[test]
+    fn from_bytes_checks_length() {
+        let mut buf = vec![0u8; 4 * 2 * 3 / 2];
+        assert!(NV12Image::from_bytes(&buf[..11], 4, 2).is_none());
+        let mut img = NV12Image::from_bytes_mut(&mut buf, 4, 2).unwrap();
+        img.put_pixel(2, 0, WHITE);
+        assert_eq!(img.as_bytes()[..4], [0, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn separate_planes() {
+        let mut y = vec![0u8; 8];
+        let mut uv = vec![0u8; 4];
+        let mut img = NV12Image::from_planes(&mut y[..], &mut uv[..], 2, 2, 4, 4);
+        img.put_pixel(1, 1, YUV([1, 2, 3]));
+        assert_eq!(img.get_pixel(0, 0).0, [1, 2, 3]);
+        assert_eq!((y, uv), (vec![1, 1, 0, 0, 1, 1, 0, 0], vec![2, 3, 0, 0]));
+    }
+
+    #[test]
+    fn fixed_point_rgb_matches_float() {
+        for yuv in [BLACK, WHITE, RED, CYAN, BLUE, YELLOW, YUV([16, 240, 16])] {
+            let fixed = yuv.rgb();
+            for (a, b) in fixed.iter().zip(yuv.rgb_f32()) {
+                assert!((*a as f32 - b).abs() <= 0.5 + 1e-3, "{:?} vs {}", fixed, b);
+            }
+        }
+        assert_eq!(WHITE.rgb(), [255, 255, 255]);
+    }
+
+    #[test]
+    fn const_conversion_matches_from_rgb() {
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(17) {
+                for b in (0..=255).step_by(51) {
+                    let (c, f) = (yuv_from_rgb(r, g, b), YUV::from_rgb([r, g, b]));
+                    assert!(
+                        c.0.iter().zip(f.0).all(|(a, b)| a.abs_diff(b) <= 1),
+                        "{:?} {:?}",
+                        c,
+                        f
+                    );
+                }
+            }
+        }
+        assert_eq!(RED, YUV([0x4c, 0x55, 0xff]));
+        assert_eq!(GREEN, YUV([0x96, 0x2c, 0x15]));
+    }
+
+    #[test]
+    fn try_constructors_validate() {
+        assert!(NV12Image::try_from(vec![0u8; 24], 4, 4).is_ok());
+        assert!(matches!(
+            NV12Image::try_from(vec![0u8; 23], 4, 4),
+            Err(YuvError::BufferTooSmall {
+                len: 23,
+                needed: 24
+            })
+        ));
+        assert!(matches!(
+            NV12Image::try_from_strided(vec![0u8; 64], 4, 4, 3, 4),
+            Err(YuvError::StrideTooSmall { stride: 3, min: 4 })
+        ));
+        assert!(matches!(
+            NV12Image::try_from_planes(vec![0u8; 9], vec![0u8; 7], 3, 3, 3, 4),
+            Err(YuvError::BufferTooSmall { len: 7, needed: 8 })
+        ));
+        let mut img = NV12Image::try_from(vec![0u8; 9 + 8], 3, 3).unwrap();
+        img.try_set_pixel(2, 2, RED).unwrap();
+        assert_eq!(img.try_pixel(2, 2).unwrap(), RED);
+        assert!(matches!(
+            img.try_pixel(3, 0),
+            Err(YuvError::OutOfBounds { x: 3, .. })
+        ));
+    }
+
+    #[cfg(all(feature = "strict", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "UV plane row 1 spans 12..16 of a buffer of 14")]
+    fn strict_mode_reports_the_row() {
+        let img = NV12Image::from_strided(vec![0u8; 14], 4, 2, 4, 4).with_uv_offset(8);
+        img.uv_row(0);
+        img.uv_row(1);
+    }
+
+    #[cfg(all(feature = "strict", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "invalid NV12 geometry: buffer of 10 bytes is smaller than 12")]
+    fn strict_mode_checks_construction() {
+        NV12Image::from(vec![0u8; 10], 4, 2);
+    }
+
+    #[test]
+    fn sixteen_bit_samples() {
+        let mut img: NV12Image<Vec<u16>, u16> = NV12Image::from(vec![0; 24], 4, 4);
+        img.set_samples(3, 1, [1023, 64, 960]);
+        assert_eq!(img.samples(2, 0), [1023, 64, 960]);
+        assert_eq!(img.y_row(1), &[0, 0, 1023, 1023]);
+        let bands = img.split_rows_mut(2);
+        assert_eq!(bands[1].uv_row(0), &[0; 4]);
+    }
+
+    #[test]
+    fn pixel_arithmetic() {
+        assert_eq!(BLACK.lerp(WHITE, 0.5), YUV([128, 128, 128]));
+        assert_eq!(BLACK.lerp(WHITE, 2.), WHITE);
+        assert_eq!(YUV::blend_weighted(BLACK, WHITE, 3, 1), YUV([64, 128, 128]));
+        assert_eq!(YUV::blend_weighted(RED, WHITE, 0, 0), RED);
+        assert_eq!(WHITE.saturating_add(RED), YUV([255, 0xd5, 0xff]));
+        assert_eq!(RED.saturating_sub(WHITE), YUV([0, 0, 0x7f]));
+    }
+
+    #[test]
+    fn unchecked_accessors_match_checked() {
+        let mut a = NV12Image::from((0..24).collect::<Vec<u8>>(), 4, 4);
+        let mut b = NV12Image::from((0..24).collect::<Vec<u8>>(), 4, 4);
+        unsafe {
+            assert_eq!(a.get_pixel_unchecked(3, 3).0, a.get_pixel(3, 3).0);
+            a.put_pixel_unchecked(3, 1, RED);
+        }
+        b.put_pixel(3, 1, RED);
+        assert_eq!(a.take_data(), b.take_data());
+    }
+
+    #[test]
+    fn from_raw_parts_checks_descriptor() {
+        let mut buf = vec![0u8; 6];
+        let desc = FrameDescriptor::packed(2, 2, PixelFormat::NV12);
+        unsafe {
+            assert!(NV12Image::from_raw_parts(buf.as_mut_ptr(), 5, desc).is_none());
+            let i420 = FrameDescriptor::packed(2, 2, PixelFormat::I420);
+            assert!(NV12Image::from_raw_parts(buf.as_mut_ptr(), 6, i420).is_none());
+            let mut img = NV12Image::from_raw_parts(buf.as_mut_ptr(), 6, desc).unwrap();
+            img.put_pixel(0, 0, YUV([1, 2, 3]));
+        }
+        assert_eq!(buf, [1, 1, 1, 1, 2, 3]);
+    }
+    #[test]
     fn draw_box() {
         let mut yuv_file = File::open("data/1.yuv").unwrap();
         let mut yuv_buf = Vec::new();
@@ -344,6 +1310,23 @@ mod tests {
             "测试",
         );
 
+        let mut out_file = File::create("1.out.yuv").unwrap();
+        out_file.write_all(img.0.ref_data()).unwrap();
+        // ffmpeg -s 1920*1080 -pix_fmt nv12 -i 1.out.yuv 1.jpg -y
+    }
+    #[test]
+    fn draw_box_scaled() {
+        let mut yuv_file = File::open("data/1.yuv").unwrap();
+        let mut yuv_buf = Vec::new();
+        yuv_file.read_to_end(&mut yuv_buf).unwrap();
+
+        // Same coordinates as draw_box, drawn on the half resolution view.
+        let mut img = ScaledDraw::half(NV12Image::from(yuv_buf, 1920, 1080));
+        draw_hollow_rect_mut(&mut img, Rect::at(101, 100).of_size(201, 100), GREEN);
+        let font_data: &[u8] = include_bytes!("../data/fonts/wqy-microhei/WenQuanYiMicroHei.ttf");
+        let font = Font::try_from_bytes(font_data).unwrap();
+        draw_text_mut(&mut img, BLUE, 101, 101, Scale::uniform(48.), &font, "测试");
+
         let mut out_file = File::create("1.out.yuv").unwrap();
         out_file.write_all(img.0.ref_data()).unwrap();
         // ffmpeg -s 1920*1080 -pix_fmt nv12 -i 1.out.yuv 1.jpg -y
@@ -0,0 +1,242 @@
+//! Quality metrics comparing two NV12 frames of the same size.
+
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+use crate::NV12Image;
+
+/// PSNR in dB of each plane and of all samples together, weighted by their
+/// count like ffmpeg's `psnr` filter. Identical planes give infinity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlanePsnr {
+    pub y: f64,
+    pub u: f64,
+    pub v: f64,
+    pub overall: f64,
+}
+
+pub(crate) fn check_sizes<A, B>(a: &NV12Image<A>, b: &NV12Image<B>)
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    assert_eq!(
+        (a.width, a.height),
+        (b.width, b.height),
+        "frames differ in size"
+    );
+}
+
+fn psnr_of(sse: u64, count: u64) -> f64 {
+    if sse == 0 {
+        return f64::INFINITY;
+    }
+    let mse = sse as f64 / count as f64;
+    10. * (255. * 255. / mse).log10()
+}
+
+/// PSNR between `a` and `b`. Panics if their sizes differ.
+pub fn psnr<A, B>(a: &NV12Image<A>, b: &NV12Image<B>) -> PlanePsnr
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    check_sizes(a, b);
+    let mut sse = [0u64; 3];
+    for y in 0..a.height {
+        for (&p, &q) in a.y_row(y).iter().zip(b.y_row(y)) {
+            sse[0] += (p as i32 - q as i32).pow(2) as u64;
+        }
+    }
+    let (chroma_width, chroma_height) = (a.width.div_ceil(2) as usize, a.height.div_ceil(2));
+    for row in 0..chroma_height as usize {
+        let a_row = &a.uv_plane()[row * a.uv_stride as usize..][..chroma_width * 2];
+        let b_row = &b.uv_plane()[row * b.uv_stride as usize..][..chroma_width * 2];
+        for (i, (&p, &q)) in a_row.iter().zip(b_row).enumerate() {
+            sse[1 + i % 2] += (p as i32 - q as i32).pow(2) as u64;
+        }
+    }
+    let luma_count = a.width as u64 * a.height as u64;
+    let chroma_count = chroma_width as u64 * chroma_height as u64;
+    PlanePsnr {
+        y: psnr_of(sse[0], luma_count),
+        u: psnr_of(sse[1], chroma_count),
+        v: psnr_of(sse[2], chroma_count),
+        overall: psnr_of(sse.iter().sum(), luma_count + 2 * chroma_count),
+    }
+}
+
+/// SSIM of one window from the sums of its `n` samples.
+fn window_ssim(n: f64, sum_a: f64, sum_b: f64, sum_aa: f64, sum_bb: f64, sum_ab: f64) -> f64 {
+    const C1: f64 = (0.01 * 255.) * (0.01 * 255.);
+    const C2: f64 = (0.03 * 255.) * (0.03 * 255.);
+    let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+    let var_a = sum_aa / n - mean_a * mean_a;
+    let var_b = sum_bb / n - mean_b * mean_b;
+    let cov = sum_ab / n - mean_a * mean_b;
+    (2. * mean_a * mean_b + C1) * (2. * cov + C2)
+        / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2))
+}
+
+/// Mean SSIM of the luma planes over 8x8 windows placed every 4 pixels, the
+/// windowing used by x264 and ffmpeg's `ssim` filter. Frames smaller than
+/// 8x8 are compared as one window. Panics if the sizes differ.
+/// [`ssim_y_gaussian`] uses the 11x11 Gaussian window instead.
+pub fn ssim_y<A, B>(a: &NV12Image<A>, b: &NV12Image<B>) -> f64
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    check_sizes(a, b);
+    let window = |x0: u32, y0: u32, width: u32, height: u32| {
+        let mut sums = [0f64; 5];
+        for y in y0..y0 + height {
+            let rows = a.y_row(y)[x0 as usize..]
+                .iter()
+                .zip(&b.y_row(y)[x0 as usize..]);
+            for (&p, &q) in rows.take(width as usize) {
+                let (p, q) = (p as f64, q as f64);
+                sums[0] += p;
+                sums[1] += q;
+                sums[2] += p * p;
+                sums[3] += q * q;
+                sums[4] += p * q;
+            }
+        }
+        let n = (width * height) as f64;
+        window_ssim(n, sums[0], sums[1], sums[2], sums[3], sums[4])
+    };
+    let (width, height) = (a.width, a.height);
+    if width < 8 || height < 8 {
+        return window(0, 0, width, height);
+    }
+    let (mut total, mut count) = (0., 0);
+    for y in (0..=height - 8).step_by(4) {
+        for x in (0..=width - 8).step_by(4) {
+            total += window(x, y, 8, 8);
+            count += 1;
+        }
+    }
+    total / count as f64
+}
+
+/// The normalized 11-tap Gaussian of standard deviation 1.5, applied along
+/// rows and columns for the 11x11 window of [`ssim_y_gaussian`].
+fn gaussian_kernel() -> [f64; 11] {
+    let mut kernel: [f64; 11] = core::array::from_fn(|i| {
+        let d = i as f64 - 5.;
+        (-d * d / (2. * 1.5 * 1.5)).exp()
+    });
+    let sum: f64 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|w| *w /= sum);
+    kernel
+}
+
+/// Mean SSIM of the luma planes over an 11x11 Gaussian window of standard
+/// deviation 1.5 at every position where it fits, as in the reference
+/// implementation of Wang et al. (without the downsampling the later
+/// MATLAB `ssim.m` adds for large frames). Frames smaller than 11x11 fall
+/// back to [`ssim_y`]. Panics if the sizes differ.
+pub fn ssim_y_gaussian<A, B>(a: &NV12Image<A>, b: &NV12Image<B>) -> f64
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    check_sizes(a, b);
+    let (width, height) = (a.width, a.height);
+    if width < 11 || height < 11 {
+        return ssim_y(a, b);
+    }
+    let kernel = gaussian_kernel();
+    let columns = width as usize - 10;
+    // Weighted sums of a, b, a², b² and ab along each row, for the 11 rows
+    // of the current window position.
+    let filter_row = |y: u32| -> Vec<[f64; 5]> {
+        let (row_a, row_b) = (a.y_row(y), b.y_row(y));
+        (0..columns)
+            .map(|x| {
+                let mut sums = [0f64; 5];
+                for (k, &w) in kernel.iter().enumerate() {
+                    let (p, q) = (row_a[x + k] as f64, row_b[x + k] as f64);
+                    sums[0] += w * p;
+                    sums[1] += w * q;
+                    sums[2] += w * p * p;
+                    sums[3] += w * q * q;
+                    sums[4] += w * p * q;
+                }
+                sums
+            })
+            .collect()
+    };
+    let mut rows: VecDeque<_> = (0..10).map(filter_row).collect();
+    let (mut total, mut count) = (0., 0u64);
+    for y in 10..height {
+        rows.push_back(filter_row(y));
+        for x in 0..columns {
+            let mut sums = [0f64; 5];
+            for (row, &w) in rows.iter().zip(&kernel) {
+                for (sum, v) in sums.iter_mut().zip(&row[x]) {
+                    *sum += w * v;
+                }
+            }
+            total += window_ssim(1., sums[0], sums[1], sums[2], sums[3], sums[4]);
+            count += 1;
+        }
+        rows.pop_front();
+    }
+    total / count as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn psnr_of_planes() {
+        let a = NV12Image::from(vec![100u8; 4 * 2 * 3 / 2], 4, 2);
+        assert_eq!(psnr(&a, &a).overall, f64::INFINITY);
+
+        let mut data = vec![100u8; 12];
+        data[0] = 110;
+        data[9] = 90;
+        let b = NV12Image::from(data, 4, 2);
+        let p = psnr(&a, &b);
+        assert_eq!(p.u, f64::INFINITY);
+        assert!((p.y - 10. * (255f64.powi(2) / (100. / 8.)).log10()).abs() < 1e-9);
+        assert!((p.v - 10. * (255f64.powi(2) / 50.).log10()).abs() < 1e-9);
+        assert!((p.overall - 10. * (255f64.powi(2) / (200. / 12.)).log10()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ssim_drops_with_noise() {
+        let data: Vec<u8> = (0..16 * 16 * 3 / 2).map(|i| (i * 7 % 256) as u8).collect();
+        let a = NV12Image::from(data.clone(), 16, 16);
+        assert!((ssim_y(&a, &a) - 1.).abs() < 1e-12);
+        let noisy: Vec<u8> = data
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| v ^ ((i % 5) as u8 * 9))
+            .collect();
+        let b = NV12Image::from(noisy, 16, 16);
+        let ssim = ssim_y(&a, &b);
+        assert!(ssim < 1. && ssim > 0.5, "{}", ssim);
+        assert!((ssim_y_gaussian(&a, &a) - 1.).abs() < 1e-12);
+        assert!(ssim_y_gaussian(&a, &b) < 1.);
+    }
+
+    #[test]
+    fn gaussian_ssim_of_a_single_window() {
+        // One window over a flat 11x11 frame and a copy with the center
+        // raised by 100. With w = 0.0707622 the center weight of the
+        // Gaussian, mean_b = 100 + 100w, var_b = 100² w (1 - w) and the
+        // covariance is 0, so SSIM = (2 * 100 mean_b + C1) / (100² + mean_b²
+        // + C1) * C2 / (var_b + C2).
+        let a = NV12Image::from(vec![100u8; 11 * 11 + 6 * 12], 11, 11);
+        let mut b = a.clone();
+        b.y_row_mut(5)[5] = 200;
+        let ssim = ssim_y_gaussian(&a, &b);
+        assert!((ssim - 0.0815365341).abs() < 1e-9, "{}", ssim);
+        // The uniform 8x8 window weighs the center less.
+        assert!(ssim_y(&a, &b) > ssim + 0.1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Frame access over memory-mapped raw `.yuv` files.
+//!
+//! Frames are borrowed straight from the mapping, so recordings larger than
+//! RAM can be annotated in place one frame at a time.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::{NV12Image, PixelFormat};
+
+/// The size of one `width`x`height` frame. Empty frames are rejected, as a
+/// file would hold any number of them.
+fn frame_size(width: u32, height: u32) -> io::Result<usize> {
+    match PixelFormat::NV12.frame_size(width, height) {
+        0 => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("empty frame geometry {}x{}", width, height),
+        )),
+        size => Ok(size),
+    }
+}
+
+/// The bytes of frame `index`, or `None` if they are past `usize::MAX`.
+fn frame_range(index: usize, frame_size: usize) -> Option<Range<usize>> {
+    let start = index.checked_mul(frame_size)?;
+    Some(start..start.checked_add(frame_size)?)
+}
+
+/// A raw NV12 file mapped read-only.
+pub struct MappedYuvFile {
+    map: Mmap,
+    width: u32,
+    height: u32,
+    frame_size: usize,
+}
+
+impl MappedYuvFile {
+    /// Maps the file at `path`. Fails with `InvalidInput` if `width` or
+    /// `height` is 0.
+    ///
+    /// The file must not be truncated or modified by another process while
+    /// it is mapped.
+    pub fn open<P: AsRef<Path>>(path: P, width: u32, height: u32) -> io::Result<Self> {
+        let frame_size = frame_size(width, height)?;
+        let file = File::open(path)?;
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            map,
+            width,
+            height,
+            frame_size,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.map.len() / self.frame_size
+    }
+
+    pub fn frame(&self, index: usize) -> Option<NV12Image<&[u8]>> {
+        let data = self.map.get(frame_range(index, self.frame_size)?)?;
+        Some(NV12Image::from(data, self.width, self.height))
+    }
+}
+
+/// A raw NV12 file mapped read-write. Drawing on a frame writes through to
+/// the file.
+pub struct MappedYuvFileMut {
+    map: MmapMut,
+    width: u32,
+    height: u32,
+    frame_size: usize,
+}
+
+impl MappedYuvFileMut {
+    /// Maps the file at `path` for reading and writing. Fails with
+    /// `InvalidInput` if `width` or `height` is 0.
+    ///
+    /// The file must not be truncated or modified by another process while
+    /// it is mapped.
+    pub fn open<P: AsRef<Path>>(path: P, width: u32, height: u32) -> io::Result<Self> {
+        let frame_size = frame_size(width, height)?;
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self {
+            map,
+            width,
+            height,
+            frame_size,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.map.len() / self.frame_size
+    }
+
+    pub fn frame(&self, index: usize) -> Option<NV12Image<&[u8]>> {
+        let data = self.map.get(frame_range(index, self.frame_size)?)?;
+        Some(NV12Image::from(data, self.width, self.height))
+    }
+
+    pub fn frame_mut(&mut self, index: usize) -> Option<NV12Image<&mut [u8]>> {
+        let data = self.map.get_mut(frame_range(index, self.frame_size)?)?;
+        Some(NV12Image::from(data, self.width, self.height))
+    }
+
+    /// Writes modified pages back to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.map.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_of_a_mapped_file() {
+        let path = std::env::temp_dir().join(format!("yuvimg-mmap-{}.yuv", std::process::id()));
+        // Two and a half 4x2 frames of 12 bytes.
+        std::fs::write(&path, (0..30).collect::<Vec<u8>>()).unwrap();
+
+        let read = MappedYuvFile::open(&path, 4, 2).unwrap();
+        assert_eq!(read.frame_count(), 2);
+        assert_eq!(read.frame(1).unwrap().y_row(0), [12, 13, 14, 15]);
+        assert!(read.frame(2).is_none());
+        assert!(read.frame(usize::MAX).is_none());
+        drop(read);
+
+        let mut write = MappedYuvFileMut::open(&path, 4, 2).unwrap();
+        write.frame_mut(0).unwrap().y_row_mut(1)[0] = 99;
+        assert_eq!(write.frame(0).unwrap().y_row(1)[0], 99);
+        assert!(write.frame_mut(usize::MAX / 2).is_none());
+        drop(write);
+
+        let empty = MappedYuvFile::open(&path, 0, 2).err().unwrap();
+        assert_eq!(empty.kind(), io::ErrorKind::InvalidInput);
+        assert!(MappedYuvFileMut::open(&path, 4, 0).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
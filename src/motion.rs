@@ -0,0 +1,111 @@
+//! Frame differencing on the luma plane, for motion-triggered recording.
+
+use std::ops::Deref;
+
+use image::{GrayImage, Luma};
+
+use crate::metrics::check_sizes;
+use crate::NV12Image;
+
+/// A mask with one pixel per pixel of the frames, 255 where the luma of `prev`
+/// and `curr` differs by more than `threshold` and 0 elsewhere. Panics if the
+/// sizes differ.
+pub fn diff_mask<A, B>(prev: &NV12Image<A>, curr: &NV12Image<B>, threshold: u8) -> GrayImage
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    check_sizes(prev, curr);
+    let mut mask = GrayImage::new(prev.width, prev.height);
+    for y in 0..prev.height {
+        let rows = prev.y_row(y).iter().zip(curr.y_row(y));
+        for (x, (&p, &q)) in rows.enumerate() {
+            if p.abs_diff(q) > threshold {
+                mask.put_pixel(x as u32, y, Luma([255]));
+            }
+        }
+    }
+    mask
+}
+
+/// Like [`diff_mask`], with one pixel per `block_size`x`block_size` block of
+/// the frames (partial blocks at the right and bottom edges included). A block
+/// is set when its mean absolute luma difference exceeds `threshold`, which
+/// ignores sensor noise better than per-pixel differences.
+pub fn diff_mask_blocks<A, B>(
+    prev: &NV12Image<A>,
+    curr: &NV12Image<B>,
+    threshold: u8,
+    block_size: u32,
+) -> GrayImage
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    check_sizes(prev, curr);
+    assert!(block_size > 0, "block size must not be zero");
+    let (columns, rows) = (
+        prev.width.div_ceil(block_size),
+        prev.height.div_ceil(block_size),
+    );
+    let mut sums = vec![0u64; (columns * rows) as usize];
+    for y in 0..prev.height {
+        let sums = &mut sums[(y / block_size * columns) as usize..];
+        let pixels = prev.y_row(y).iter().zip(curr.y_row(y));
+        for (x, (&p, &q)) in pixels.enumerate() {
+            sums[x / block_size as usize] += p.abs_diff(q) as u64;
+        }
+    }
+    GrayImage::from_fn(columns, rows, |bx, by| {
+        let width = block_size.min(prev.width - bx * block_size) as u64;
+        let height = block_size.min(prev.height - by * block_size) as u64;
+        let sum = sums[(by * columns + bx) as usize];
+        Luma([if sum > threshold as u64 * width * height {
+            255
+        } else {
+            0
+        }])
+    })
+}
+
+/// Whether the mean absolute luma difference between `prev` and `curr` is at
+/// most `diff_threshold`, as for a frozen feed. Encoded sources rarely repeat
+/// frames exactly, so a threshold of about 1 tolerates compression noise.
+/// Panics if the sizes differ.
+pub fn is_static<A, B>(prev: &NV12Image<A>, curr: &NV12Image<B>, diff_threshold: f64) -> bool
+where
+    A: Deref<Target = [u8]>,
+    B: Deref<Target = [u8]>,
+{
+    check_sizes(prev, curr);
+    let mut sum = 0u64;
+    for y in 0..prev.height {
+        for (&p, &q) in prev.y_row(y).iter().zip(curr.y_row(y)) {
+            sum += p.abs_diff(q) as u64;
+        }
+    }
+    let count = prev.width as u64 * prev.height as u64;
+    sum as f64 <= diff_threshold * count as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_changed_pixels_and_blocks() {
+        let prev = NV12Image::from(vec![100u8; 16 * 8 * 3 / 2], 16, 8);
+        let mut data = vec![100u8; 16 * 8 * 3 / 2];
+        data[3 * 16 + 10] = 200;
+        data[16 * 4] = 110;
+        let curr = NV12Image::from(data, 16, 8);
+        let mask = diff_mask(&prev, &curr, 20);
+        assert_eq!(mask.get_pixel(10, 3).0, [255]);
+        assert_eq!(mask.as_raw().iter().filter(|&&m| m != 0).count(), 1);
+        let blocks = diff_mask_blocks(&prev, &curr, 1, 8);
+        assert_eq!(blocks.dimensions(), (2, 1));
+        assert_eq!(blocks.as_raw(), &vec![0, 255]);
+        assert!(is_static(&prev, &curr, 1.));
+        assert!(!is_static(&prev, &curr, 0.5));
+    }
+}
@@ -0,0 +1,105 @@
+//! `ndarray` views of the planes of an NV12 image.
+//!
+//! The views honor the row strides of the image, so no data is copied. The
+//! UV view has one row per chroma row, with U and V interleaved along the
+//! columns, so frames with odd dimensions have `height.div_ceil(2)` rows of
+//! `width.div_ceil(2) * 2` samples.
+
+use core::ops::{Deref, DerefMut};
+
+use ndarray::{ArrayView2, ArrayViewMut2, ShapeBuilder};
+
+use crate::NV12Image;
+
+fn plane_len(rows: usize, stride: usize, width: usize) -> usize {
+    match rows {
+        0 => 0,
+        rows => (rows - 1) * stride + width,
+    }
+}
+
+const GEOMETRY: &str = "buffer too small for the image geometry";
+
+impl<T: Deref<Target = [u8]>> NV12Image<T> {
+    fn plane_shapes(&self) -> ((usize, usize, usize), (usize, usize, usize)) {
+        let y = (
+            self.height as usize,
+            self.width as usize,
+            self.y_stride as usize,
+        );
+        let uv = (
+            self.height.div_ceil(2) as usize,
+            self.width.div_ceil(2) as usize * 2,
+            self.uv_stride as usize,
+        );
+        (y, uv)
+    }
+
+    /// The Y plane as a `(height, width)` array.
+    pub fn y_array(&self) -> ArrayView2<'_, u8> {
+        let ((rows, cols, stride), _) = self.plane_shapes();
+        let plane = &self.data[..plane_len(rows, stride, cols)];
+        ArrayView2::from_shape((rows, cols).strides((stride, 1)), plane).expect(GEOMETRY)
+    }
+
+    /// The interleaved UV plane as a `(height.div_ceil(2), width.div_ceil(2)
+    /// * 2)` array.
+    pub fn uv_array(&self) -> ArrayView2<'_, u8> {
+        let (_, (rows, cols, stride)) = self.plane_shapes();
+        let plane = &self.uv_plane()[..plane_len(rows, stride, cols)];
+        ArrayView2::from_shape((rows, cols).strides((stride, 1)), plane).expect(GEOMETRY)
+    }
+}
+
+impl<T: DerefMut<Target = [u8]>> NV12Image<T> {
+    pub fn y_array_mut(&mut self) -> ArrayViewMut2<'_, u8> {
+        self.planes_mut().0
+    }
+
+    pub fn uv_array_mut(&mut self) -> ArrayViewMut2<'_, u8> {
+        self.planes_mut().1
+    }
+
+    /// Mutable views of both planes at once.
+    pub fn planes_mut(&mut self) -> (ArrayViewMut2<'_, u8>, ArrayViewMut2<'_, u8>) {
+        let ((y_rows, y_cols, y_stride), (uv_rows, uv_cols, uv_stride)) = self.plane_shapes();
+        let (y, uv) = self.split_planes_mut();
+        let y = &mut y[..plane_len(y_rows, y_stride, y_cols)];
+        let uv = &mut uv[..plane_len(uv_rows, uv_stride, uv_cols)];
+        (
+            ArrayViewMut2::from_shape((y_rows, y_cols).strides((y_stride, 1)), y).expect(GEOMETRY),
+            ArrayViewMut2::from_shape((uv_rows, uv_cols).strides((uv_stride, 1)), uv)
+                .expect(GEOMETRY),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_follow_strides() {
+        // 4x2 with rows padded to 6 bytes.
+        let data: Vec<u8> = (0..18).collect();
+        let img = NV12Image::from_strided(data, 4, 2, 6, 6);
+        let y = img.y_array();
+        assert_eq!(y.dim(), (2, 4));
+        assert_eq!(y[[1, 3]], 9);
+        let uv = img.uv_array();
+        assert_eq!(uv.dim(), (1, 4));
+        assert_eq!((uv[[0, 0]], uv[[0, 3]]), (12, 15));
+    }
+
+    #[test]
+    fn odd_sizes_keep_the_last_chroma() {
+        let mut img = NV12Image::try_from(vec![0u8; 9 + 8], 3, 3).unwrap();
+        assert_eq!(img.y_array().dim(), (3, 3));
+        assert_eq!(img.uv_array().dim(), (2, 4));
+        let (mut y, mut uv) = img.planes_mut();
+        y[[2, 2]] = 1;
+        uv[[1, 3]] = 2;
+        assert_eq!(img.y_row(2), [0, 0, 1]);
+        assert_eq!(img.uv_row(1), [0, 0, 0, 2]);
+    }
+}
@@ -0,0 +1,91 @@
+//! Conversions between NV12 images and OpenCV `Mat`s.
+//!
+//! NV12 is represented the way OpenCV's `COLOR_YUV2BGR_NV12` expects it: a
+//! single channel `Mat` of `height * 3 / 2` rows, the Y plane on top of the
+//! interleaved UV plane. BGR conversions use the same full range BT.601
+//! matrix as the rest of this crate.
+
+use std::ops::Deref;
+
+use opencv::core::{Mat, Scalar, Vec3b, VecN, CV_8UC1, CV_8UC3};
+use opencv::prelude::*;
+
+use crate::convert::nv12_from_rgb;
+use crate::NV12Image;
+
+fn bad_arg(message: String) -> opencv::Error {
+    opencv::Error::new(opencv::core::StsBadArg, message)
+}
+
+/// Copies `img` into a `(height * 3 / 2, width)` `CV_8UC1` `Mat`.
+pub fn nv12_to_mat<T: Deref<Target = [u8]>>(img: &NV12Image<T>) -> opencv::Result<Mat> {
+    let (width, height) = (img.width as i32, img.height as i32);
+    let mut mat =
+        Mat::new_rows_cols_with_default(height + height / 2, width, CV_8UC1, Scalar::all(0.))?;
+    for y in 0..img.height {
+        mat.at_row_mut::<u8>(y as i32)?
+            .copy_from_slice(img.y_row(y));
+    }
+    for y in 0..img.height / 2 {
+        mat.at_row_mut::<u8>(height + y as i32)?
+            .copy_from_slice(&img.uv_row(y)[..img.width as usize]);
+    }
+    Ok(mat)
+}
+
+/// Copies a `(height * 3 / 2, width)` `CV_8UC1` NV12 `Mat` into an image.
+pub fn nv12_from_mat(mat: &Mat) -> opencv::Result<NV12Image<Vec<u8>>> {
+    if mat.typ() != CV_8UC1 || mat.rows() % 3 != 0 {
+        return Err(bad_arg(format!(
+            "expected a CV_8UC1 Mat of height * 3 / 2 rows, got type {} with {} rows",
+            mat.typ(),
+            mat.rows()
+        )));
+    }
+    let mut data = Vec::with_capacity(mat.rows() as usize * mat.cols() as usize);
+    for row in 0..mat.rows() {
+        data.extend_from_slice(mat.at_row::<u8>(row)?);
+    }
+    let height = mat.rows() as u32 / 3 * 2;
+    Ok(NV12Image::from(data, mat.cols() as u32, height))
+}
+
+/// Converts `img` to a `CV_8UC3` BGR `Mat`.
+pub fn nv12_to_bgr_mat<T: Deref<Target = [u8]>>(img: &NV12Image<T>) -> opencv::Result<Mat> {
+    let mut mat = Mat::new_rows_cols_with_default(
+        img.height as i32,
+        img.width as i32,
+        CV_8UC3,
+        Scalar::all(0.),
+    )?;
+    for y in 0..img.height {
+        let row = mat.at_row_mut::<Vec3b>(y as i32)?;
+        for (x, px) in row.iter_mut().enumerate() {
+            let [r, g, b] = img.sample(x as u32, y).rgb();
+            *px = VecN([b, g, r]);
+        }
+    }
+    Ok(mat)
+}
+
+/// Converts a `CV_8UC3` BGR `Mat` to NV12, averaging chroma over each 2x2
+/// block.
+pub fn nv12_from_bgr_mat(mat: &Mat) -> opencv::Result<NV12Image<Vec<u8>>> {
+    if mat.typ() != CV_8UC3 {
+        return Err(bad_arg(format!(
+            "expected a CV_8UC3 Mat, got type {}",
+            mat.typ()
+        )));
+    }
+    let rows = (0..mat.rows())
+        .map(|row| mat.at_row::<Vec3b>(row))
+        .collect::<opencv::Result<Vec<_>>>()?;
+    Ok(nv12_from_rgb(
+        mat.cols() as u32,
+        mat.rows() as u32,
+        |x, y| {
+            let [b, g, r] = rows[y as usize][x as usize].0;
+            [r, g, b]
+        },
+    ))
+}
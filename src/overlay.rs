@@ -0,0 +1,345 @@
+//! Compositing of RGBA content, masked frames and heatmaps onto NV12 frames.
+
+use std::ops::{Deref, DerefMut};
+
+use image::{GenericImageView, GrayImage, Rgba};
+
+use crate::{NV12Image, Region, YUV};
+
+fn blend(dst: u8, src: u8, alpha: u32) -> u8 {
+    ((dst as u32 * (255 - alpha) + src as u32 * alpha + 127) / 255) as u8
+}
+
+/// How the color channels of RGBA content relate to its alpha.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Color independent of alpha, as stored by most image files.
+    #[default]
+    Straight,
+    /// Color already multiplied by alpha, as rendered by most GPU
+    /// pipelines. Blending it as straight alpha darkens soft edges.
+    Premultiplied,
+}
+
+impl AlphaMode {
+    /// The straight color and alpha of `rgba`.
+    pub fn straight(&self, rgba: [u8; 4]) -> ([u8; 3], u32) {
+        let [r, g, b, a] = rgba;
+        match self {
+            AlphaMode::Premultiplied if a != 0 && a != 255 => {
+                let a = a as u32;
+                let c = |c: u8| ((c as u32 * 255 + a / 2) / a).min(255) as u8;
+                ([c(r), c(g), c(b)], a)
+            }
+            _ => ([r, g, b], a as u32),
+        }
+    }
+}
+
+/// Alpha-blends `top`, with color relative to alpha as `mode` says, onto
+/// `img` with its top-left corner at `(x, y)`. Parts of `top` outside `img`
+/// are skipped.
+///
+/// Luma is blended per pixel. Each chroma sample is blended with the average
+/// color of the overlay pixels covering its 2x2 block, weighted by their
+/// alpha, so partially covered blocks keep part of the original chroma.
+pub fn overlay_rgba_mut<T, I>(img: &mut NV12Image<T>, top: &I, x: i64, y: i64, mode: AlphaMode)
+where
+    T: DerefMut<Target = [u8]>,
+    I: GenericImageView<Pixel = Rgba<u8>>,
+{
+    let (width, height) = top.dimensions();
+    blend_region_mut(img, x, y, width, height, |dx, dy| {
+        let (rgb, alpha) = mode.straight(top.get_pixel(dx, dy).0);
+        (YUV::from_rgb(rgb), alpha)
+    });
+}
+
+/// Copies `src` onto `dst` with its top-left corner at `position`, each
+/// pixel blended by the coverage of `mask` (255 copies, 0 keeps `dst`).
+/// Chroma is blended per 2x2 block like in [`overlay_rgba_mut`]. This gives
+/// non-rectangular overlays such as rounded panels or segmentation
+/// highlights. Panics if `mask` is not the size of `src`.
+pub fn copy_masked<T, U>(
+    dst: &mut NV12Image<T>,
+    src: &NV12Image<U>,
+    mask: &GrayImage,
+    position: (i64, i64),
+) where
+    T: DerefMut<Target = [u8]>,
+    U: Deref<Target = [u8]>,
+{
+    assert_eq!(
+        mask.dimensions(),
+        (src.width, src.height),
+        "mask does not match the source size"
+    );
+    let mask = mask.as_raw();
+    let (x, y) = position;
+    blend_region_mut(dst, x, y, src.width, src.height, |dx, dy| {
+        let alpha = mask[(dy * src.width + dx) as usize];
+        (src.sample(dx, dy), alpha as u32)
+    });
+}
+
+/// Color scales for [`overlay_heatmap_mut`], mapping `0.0..=1.0` to RGB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colormap {
+    /// Blue through cyan, yellow and red.
+    Jet,
+    /// Perceptually uniform dark purple to yellow, matplotlib's default.
+    Viridis,
+    /// Perceptually uniform black through purple and orange to pale yellow.
+    Inferno,
+    Gray,
+}
+
+impl Colormap {
+    /// Nine evenly spaced colors of the scale, interpolated in between.
+    fn stops(&self) -> [[u8; 3]; 9] {
+        match self {
+            Colormap::Jet => [
+                [0, 0, 128],
+                [0, 0, 255],
+                [0, 128, 255],
+                [0, 255, 255],
+                [128, 255, 128],
+                [255, 255, 0],
+                [255, 128, 0],
+                [255, 0, 0],
+                [128, 0, 0],
+            ],
+            Colormap::Viridis => [
+                [0x44, 0x01, 0x54],
+                [0x47, 0x2d, 0x7b],
+                [0x3b, 0x52, 0x8b],
+                [0x2c, 0x72, 0x8e],
+                [0x21, 0x91, 0x8c],
+                [0x28, 0xae, 0x80],
+                [0x5e, 0xc9, 0x62],
+                [0xad, 0xdc, 0x30],
+                [0xfd, 0xe7, 0x25],
+            ],
+            Colormap::Inferno => [
+                [0x00, 0x00, 0x04],
+                [0x1f, 0x0c, 0x48],
+                [0x55, 0x0f, 0x6d],
+                [0x88, 0x22, 0x6a],
+                [0xba, 0x36, 0x55],
+                [0xe3, 0x59, 0x33],
+                [0xf9, 0x8e, 0x09],
+                [0xf9, 0xcb, 0x35],
+                [0xfc, 0xff, 0xa4],
+            ],
+            Colormap::Gray => core::array::from_fn(|i| [(i * 255 / 8) as u8; 3]),
+        }
+    }
+
+    /// The color of `value`, clamped to `0.0..=1.0`.
+    pub fn rgb(&self, value: f32) -> [u8; 3] {
+        let stops = self.stops();
+        let pos = value.clamp(0., 1.) * 8.;
+        let i = (pos as usize).min(7);
+        let f = pos - i as f32;
+        let mut rgb = [0; 3];
+        for (c, out) in rgb.iter_mut().enumerate() {
+            let (a, b) = (stops[i][c] as f32, stops[i + 1][c] as f32);
+            *out = (a + (b - a) * f).round() as u8;
+        }
+        rgb
+    }
+}
+
+/// Stretches `heatmap`, `size.0`x`size.1` values in row-major order, over
+/// `rect` of `img` with bilinear interpolation, colors it with `colormap` and
+/// blends it with opacity `alpha` (0 to 1). Values are expected in
+/// `0.0..=1.0` and clamped; NaN values are left transparent.
+///
+/// A contiguous `ndarray::Array2<f32>` can be passed as
+/// `array.as_slice().unwrap()` with `(array.ncols(), array.nrows())`. Panics
+/// if `heatmap` is not `size.0 * size.1` long.
+pub fn overlay_heatmap_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    heatmap: &[f32],
+    size: (u32, u32),
+    rect: Region,
+    colormap: Colormap,
+    alpha: f32,
+) {
+    let (columns, rows) = size;
+    assert_eq!(
+        heatmap.len(),
+        columns as usize * rows as usize,
+        "heatmap does not match its size"
+    );
+    if heatmap.is_empty() || rect.is_empty() {
+        return;
+    }
+    let alpha = (alpha.clamp(0., 1.) * 255.).round() as u32;
+    let at = |x: u32, y: u32| heatmap[(y * columns + x) as usize];
+    let axis = |d: u32, len: u32, count: u32| {
+        let pos = ((d as f32 + 0.5) * count as f32 / len as f32 - 0.5).max(0.);
+        let i0 = (pos as u32).min(count - 1);
+        (i0, (i0 + 1).min(count - 1), pos - i0 as f32)
+    };
+    let (x, y) = (rect.x as i64, rect.y as i64);
+    blend_region_mut(img, x, y, rect.width, rect.height, |dx, dy| {
+        let (x0, x1, fx) = axis(dx, rect.width, columns);
+        let (y0, y1, fy) = axis(dy, rect.height, rows);
+        let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * fx;
+        let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * fx;
+        let value = top + (bottom - top) * fy;
+        if value.is_nan() {
+            return (YUV([0, 128, 128]), 0);
+        }
+        (YUV::from_rgb(colormap.rgb(value)), alpha)
+    });
+}
+
+/// Blends the `width`x`height` region at `(x, y)` with `source`, which
+/// returns the color and alpha of each pixel relative to the region. Chroma
+/// is blended per 2x2 block like in [`overlay_rgba_mut`], weighing the
+/// covered pixels against all pixels of the block inside the image, so the
+/// clipped blocks of odd-size frames are replaced by an opaque overlay too.
+pub(crate) fn blend_region_mut<T, F>(
+    img: &mut NV12Image<T>,
+    x: i64,
+    y: i64,
+    width: u32,
+    height: u32,
+    source: F,
+) where
+    T: DerefMut<Target = [u8]>,
+    F: Fn(u32, u32) -> (YUV, u32),
+{
+    let x0 = x.max(0);
+    let y0 = y.max(0);
+    let x1 = (x + width as i64).min(img.width as i64);
+    let y1 = (y + height as i64).min(img.height as i64);
+    if x0 >= x1 || y0 >= y1 {
+        return;
+    }
+    let source = |dx: i64, dy: i64| source((dx - x) as u32, (dy - y) as u32);
+    let (img_width, img_height) = (img.width as i64, img.height as i64);
+    let y_stride = img.y_stride as usize;
+    let uv_stride = img.uv_stride as usize;
+    let (luma, chroma) = img.split_planes_mut();
+
+    for dy in y0..y1 {
+        let row = dy as usize * y_stride;
+        for dx in x0..x1 {
+            let (color, alpha) = source(dx, dy);
+            if alpha != 0 {
+                let i = row + dx as usize;
+                luma[i] = blend(luma[i], color.0[0], alpha);
+            }
+        }
+    }
+
+    for by in y0 / 2..(y1 + 1) / 2 {
+        let row = by as usize * uv_stride;
+        for bx in x0 / 2..(x1 + 1) / 2 {
+            let (mut sum_a, mut sum_u, mut sum_v) = (0, 0, 0);
+            for dy in (by * 2).max(y0)..(by * 2 + 2).min(y1) {
+                for dx in (bx * 2).max(x0)..(bx * 2 + 2).min(x1) {
+                    let (color, alpha) = source(dx, dy);
+                    sum_a += alpha;
+                    sum_u += alpha * color.0[1] as u32;
+                    sum_v += alpha * color.0[2] as u32;
+                }
+            }
+            if sum_a == 0 {
+                continue;
+            }
+            let i = row + bx as usize * 2;
+            let count =
+                ((bx * 2 + 2).min(img_width) - bx * 2) * ((by * 2 + 2).min(img_height) - by * 2);
+            let total = count as u32 * 255;
+            let keep = total - sum_a;
+            chroma[i] = ((chroma[i] as u32 * keep + sum_u) / total) as u8;
+            chroma[i + 1] = ((chroma[i + 1] as u32 * keep + sum_v) / total) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbaImage;
+
+    use super::*;
+
+    #[test]
+    fn opaque_overlay_replaces_covered_blocks() {
+        let mut img = NV12Image::from(vec![0x10; 4 * 4 * 3 / 2], 4, 4);
+        let top = RgbaImage::from_pixel(2, 2, Rgba([255, 255, 255, 255]));
+        overlay_rgba_mut(&mut img, &top, 2, 2, AlphaMode::Straight);
+
+        let data = img.take_data();
+        assert_eq!(
+            data[..16],
+            [
+                0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0xff, 0xff, 0x10, 0x10,
+                0xff, 0xff,
+            ]
+        );
+        assert_eq!(data[16..], [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x80, 0x80]);
+    }
+
+    #[test]
+    fn opaque_overlay_replaces_clipped_blocks() {
+        let mut img = NV12Image::from(vec![0x10; 3 * 3 + 2 * 4], 3, 3);
+        let top = RgbaImage::from_pixel(3, 3, Rgba([255, 255, 255, 255]));
+        overlay_rgba_mut(&mut img, &top, 0, 0, AlphaMode::Straight);
+        assert_eq!(img.take_data()[9..], [0x80; 8]);
+    }
+
+    #[test]
+    fn masked_copy_follows_coverage() {
+        let mut dst = NV12Image::from(vec![0; 4 * 2 * 3 / 2], 4, 2);
+        let src = crate::Nv12Buffer::filled(2, 2, YUV([200, 100, 100]));
+        let mask = GrayImage::from_raw(2, 2, vec![255, 0, 255, 255]).unwrap();
+        copy_masked(&mut dst, &src, &mask, (2, 0));
+        let data = dst.take_data();
+        assert_eq!(data[..8], [0, 0, 200, 0, 0, 0, 200, 200]);
+        assert_eq!(data[8..], [0, 0, 75, 75]);
+    }
+
+    #[test]
+    fn heatmap_is_stretched_and_colored() {
+        let mut img = NV12Image::from(vec![0; 4 * 2 * 3 / 2], 4, 2);
+        let heatmap = [0.0, 1.0];
+        let rect = Region::new(0, 0, 4, 2);
+        overlay_heatmap_mut(&mut img, &heatmap, (2, 1), rect, Colormap::Gray, 1.0);
+        let row = img.y_row(0);
+        assert_eq!((row[0], row[3]), (0, 255));
+        assert!(row[0] < row[1] && row[1] < row[2] && row[2] < row[3]);
+        assert_eq!(Colormap::Viridis.rgb(1.0), [0xfd, 0xe7, 0x25]);
+    }
+
+    #[test]
+    fn overlay_is_clipped() {
+        let mut img = NV12Image::from(vec![0; 4 * 2 * 3 / 2], 4, 2);
+        let top = RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 128]));
+        overlay_rgba_mut(&mut img, &top, 3, -1, AlphaMode::Straight);
+        overlay_rgba_mut(&mut img, &top, 10, 10, AlphaMode::Straight);
+        let data = img.take_data();
+        assert_eq!(data[..8], [0, 0, 0, 128, 0, 0, 0, 128]);
+    }
+
+    #[test]
+    fn premultiplied_edges_keep_their_color() {
+        let white = YUV::from_rgb([255, 255, 255]).0[0];
+        let mut straight = crate::Nv12Buffer::filled(2, 2, YUV::from_rgb([0, 0, 0]));
+        let mut premultiplied = straight.clone();
+        let top = RgbaImage::from_pixel(2, 2, Rgba([128, 128, 128, 128]));
+        overlay_rgba_mut(&mut straight, &top, 0, 0, AlphaMode::Straight);
+        overlay_rgba_mut(&mut premultiplied, &top, 0, 0, AlphaMode::Premultiplied);
+        let expected = YUV::from_rgb([0, 0, 0]).0[0] as u32 * 127 / 255 + white as u32 * 128 / 255;
+        assert!(premultiplied.y_row(0)[0].abs_diff(expected as u8) <= 1);
+        assert!(straight.y_row(0)[0] < premultiplied.y_row(0)[0]);
+        assert_eq!(
+            AlphaMode::Premultiplied.straight([64, 0, 32, 64]),
+            ([255, 0, 128], 64)
+        );
+    }
+}
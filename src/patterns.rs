@@ -0,0 +1,288 @@
+//! Generated test patterns, for validating encoder and display pipelines
+//! without binary fixtures.
+//!
+//! Colors are full range BT.601, like [`YUV::from_rgb`], so they convert back
+//! to the intended RGB with [`YUV::rgb`].
+
+use alloc::vec;
+
+use crate::convert::nv12_from_fn;
+use crate::{FrameDescriptor, NV12Image, Nv12Buffer, PixelFormat, YUV};
+
+/// Intensity of the color bars of [`color_bars_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarLevel {
+    /// 75% bars, as in SMPTE EG 1.
+    Percent75,
+    /// 100% bars.
+    Percent100,
+}
+
+fn gray(level: u8) -> YUV {
+    YUV::from_rgb([level; 3])
+}
+
+/// SMPTE color bars at 75% intensity, see [`color_bars_with`].
+pub fn color_bars(width: u32, height: u32) -> Nv12Buffer {
+    color_bars_with(width, height, BarLevel::Percent75)
+}
+
+/// SMPTE color bars: seven bars of gray, yellow, cyan, green, magenta, red
+/// and blue over the top two thirds, the reversed castellation strip below
+/// them, and -I, 100% white, +Q and a PLUGE along the bottom quarter.
+///
+/// The PLUGE's below-black step cannot be represented in full range, so it is
+/// black; the above-black step is 4% gray.
+pub fn color_bars_with(width: u32, height: u32, level: BarLevel) -> Nv12Buffer {
+    let l = match level {
+        BarLevel::Percent75 => 191,
+        BarLevel::Percent100 => 255,
+    };
+    let bars = [
+        [l, l, l],
+        [l, l, 0],
+        [0, l, l],
+        [0, l, 0],
+        [l, 0, l],
+        [l, 0, 0],
+        [0, 0, l],
+    ]
+    .map(YUV::from_rgb);
+    let black = gray(0);
+    let castellations = [bars[6], black, bars[4], black, bars[2], black, bars[0]];
+    let bottom = [
+        YUV::from_rgb([0, 62, 105]),
+        gray(255),
+        YUV::from_rgb([59, 0, 107]),
+        black,
+    ];
+    let pluge = [black, black, gray(10)];
+    nv12_from_fn(width, height, |x, y| {
+        let bar = (x * 7 / width) as usize;
+        if y < height * 2 / 3 {
+            bars[bar]
+        } else if y < height * 3 / 4 {
+            castellations[bar]
+        } else if bar < 5 {
+            // The first four bottom patches are 5/4 bars wide.
+            bottom[(x * 28 / width / 5) as usize]
+        } else if bar == 5 {
+            pluge[(x * 21 / width - 15) as usize]
+        } else {
+            black
+        }
+    })
+}
+
+/// Direction in which a ramp increases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From left to right.
+    Horizontal,
+    /// From top to bottom.
+    Vertical,
+}
+
+/// Step `i` of `steps` evenly spread over `0..=255`.
+fn ramp_value(i: u32, steps: u32) -> u8 {
+    match steps {
+        0 | 1 => 0,
+        _ => (i as u64 * 255 / (steps as u64 - 1)) as u8,
+    }
+}
+
+/// A gray ramp from black to white, for spotting banding and range errors.
+pub fn luma_ramp(width: u32, height: u32, direction: Direction) -> Nv12Buffer {
+    nv12_from_fn(width, height, |x, y| {
+        let luma = match direction {
+            Direction::Horizontal => ramp_value(x, width),
+            Direction::Vertical => ramp_value(y, height),
+        };
+        YUV([luma, 0x80, 0x80])
+    })
+}
+
+/// A chroma sweep at constant `luma`: U runs from 0 to 255 from left to
+/// right and V from top to bottom, covering the whole chroma plane. A
+/// matrix mismatch shows as shifted hues, clipping as flat areas.
+pub fn chroma_sweep(width: u32, height: u32, luma: u8) -> Nv12Buffer {
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    nv12_from_fn(width, height, |x, y| {
+        YUV([
+            luma,
+            ramp_value(x / 2, chroma_width),
+            ramp_value(y / 2, chroma_height),
+        ])
+    })
+}
+
+/// A black and white checkerboard of `cell`x`cell` squares, starting with
+/// white at the top left. Odd cell sizes put luma edges inside chroma blocks,
+/// which shows the chroma siting.
+pub fn checkerboard(width: u32, height: u32, cell: u32) -> Nv12Buffer {
+    assert!(cell > 0, "cell size must not be zero");
+    nv12_from_fn(width, height, |x, y| {
+        if (x / cell + y / cell) % 2 == 1 {
+            gray(0)
+        } else {
+            gray(255)
+        }
+    })
+}
+
+/// A circular zone plate: concentric luma rings whose spatial frequency
+/// rises linearly from the center to the Nyquist limit at the edge of the
+/// inscribed circle, beyond which it aliases. The standard stimulus for
+/// judging scaling filters.
+#[cfg(feature = "std")]
+pub fn zone_plate(width: u32, height: u32) -> Nv12Buffer {
+    let radius = (width.min(height) as f64 / 2.).max(1.);
+    let (cx, cy) = (width as f64 / 2., height as f64 / 2.);
+    nv12_from_fn(width, height, |x, y| {
+        let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+        let phase = std::f64::consts::PI * (dx * dx + dy * dy) / (2. * radius);
+        YUV([(127.5 + 127.5 * phase.cos()).round() as u8, 0x80, 0x80])
+    })
+}
+
+/// Distribution of the samples of [`noise_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Distribution {
+    /// Approximately Gaussian, as the sum of 12 uniform samples.
+    Gaussian,
+    /// Uniform over a range centered on the mean.
+    Uniform,
+}
+
+/// Options for [`noise_with`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseOptions {
+    pub distribution: Distribution,
+    /// Standard deviation of the samples around mid-gray and neutral chroma.
+    pub sigma: f32,
+    /// Whether the chroma is noise too, or neutral gray.
+    pub chroma: bool,
+}
+
+/// The SplitMix64 generator: tiny, fast and good enough for test data.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform sample in `0.0..1.0`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A sample of `distribution` with mean 0 and standard deviation 1.
+    fn next_normalized(&mut self, distribution: Distribution) -> f32 {
+        match distribution {
+            Distribution::Gaussian => (0..12).map(|_| self.next_f32()).sum::<f32>() - 6.,
+            // The standard deviation of a uniform distribution over
+            // `-a..a` is `a / sqrt(3)`.
+            Distribution::Uniform => (self.next_f32() * 2. - 1.) * 1.732_050_8,
+        }
+    }
+}
+
+/// Gaussian noise with standard deviation `sigma` on all planes, see
+/// [`noise_with`].
+pub fn noise(width: u32, height: u32, seed: u64, sigma: f32) -> Nv12Buffer {
+    let options = NoiseOptions {
+        distribution: Distribution::Gaussian,
+        sigma,
+        chroma: true,
+    };
+    noise_with(width, height, seed, options)
+}
+
+/// A frame of independent random samples around mid-gray, clamped at 0 and
+/// 255. Such frames are incompressible, for stress-testing encoders and
+/// benchmarking converters. The same `seed` always gives the same frame.
+pub fn noise_with(width: u32, height: u32, seed: u64, options: NoiseOptions) -> Nv12Buffer {
+    let desc = FrameDescriptor::packed(width, height, PixelFormat::NV12);
+    let mut data = vec![0x80; desc.frame_size()];
+    let luma_len = (desc.y_stride * height) as usize;
+    let noisy = if options.chroma {
+        &mut data[..]
+    } else {
+        &mut data[..luma_len]
+    };
+    let mut rng = SplitMix64(seed);
+    for sample in noisy {
+        let value = 128. + rng.next_normalized(options.distribution) * options.sigma;
+        *sample = (value + 0.5).clamp(0., 255.) as u8;
+    }
+    NV12Image::from_strided(data, width, height, desc.y_stride, desc.uv_stride)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_bars_layout() {
+        let img = color_bars_with(140, 120, BarLevel::Percent100);
+        assert_eq!(img.pixel(0, 0).0, YUV::from_rgb([255; 3]).0);
+        assert_eq!(img.pixel(130, 70).0, YUV::from_rgb([0, 0, 255]).0);
+        assert_eq!(img.pixel(130, 85).0, YUV::from_rgb([255; 3]).0);
+        assert_eq!(img.pixel(30, 100).0, YUV::from_rgb([255; 3]).0);
+        assert_eq!(img.pixel(116, 100).0, gray(10).0);
+        assert_eq!(color_bars(140, 120).pixel(0, 0).0, gray(191).0);
+    }
+
+    #[test]
+    fn checkerboard_and_zone_plate() {
+        let board = checkerboard(6, 4, 3);
+        assert_eq!(board.y_row(0), &[255, 255, 255, 0, 0, 0]);
+        assert_eq!(board.y_row(3), &[0, 0, 0, 255, 255, 255]);
+        #[cfg(feature = "std")]
+        {
+            let plate = zone_plate(64, 64);
+            assert!(plate.y_row(32)[32] > 250);
+            assert!(plate.uv_row(0).iter().all(|&c| c == 0x80));
+        }
+    }
+
+    #[test]
+    fn noise_is_seeded() {
+        let a = noise(32, 32, 7, 20.);
+        assert_eq!(a.as_bytes(), noise(32, 32, 7, 20.).as_bytes());
+        assert_ne!(a.as_bytes(), noise(32, 32, 8, 20.).as_bytes());
+        let luma = &a.as_bytes()[..32 * 32];
+        let mean = luma.iter().map(|&l| l as f32).sum::<f32>() / luma.len() as f32;
+        let variance = luma.iter().map(|&l| (l as f32 - mean).powi(2)).sum::<f32>() / 1024.;
+        assert!(
+            (mean - 128.).abs() < 3. && (variance - 400.).abs() < 80.,
+            "{} {}",
+            mean,
+            variance
+        );
+        let options = NoiseOptions {
+            distribution: Distribution::Uniform,
+            sigma: 10.,
+            chroma: false,
+        };
+        let gray = noise_with(32, 32, 7, options);
+        assert!(gray.as_bytes()[32 * 32..].iter().all(|&c| c == 0x80));
+        assert!(gray.y_row(0).iter().all(|&l| l.abs_diff(128) <= 18));
+    }
+
+    #[test]
+    fn ramps_and_sweeps() {
+        let ramp = luma_ramp(256, 2, Direction::Horizontal);
+        assert!(ramp.y_row(1).iter().enumerate().all(|(x, &l)| l == x as u8));
+        let ramp = luma_ramp(2, 4, Direction::Vertical);
+        assert_eq!((ramp.y_row(0)[1], ramp.y_row(3)[0]), (0, 255));
+        let sweep = chroma_sweep(6, 4, 100);
+        assert_eq!(sweep.uv_row(0), &[0, 0, 127, 0, 255, 0]);
+        assert_eq!(sweep.uv_row(1), &[0, 255, 127, 255, 255, 255]);
+    }
+}
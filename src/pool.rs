@@ -0,0 +1,133 @@
+//! Recycling of frame buffers, so steady-state pipelines do not allocate per
+//! frame.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use crate::{FrameDescriptor, NV12Image, Nv12Buffer, PixelFormat};
+
+struct Shared {
+    desc: FrameDescriptor,
+    capacity: usize,
+    idle: Mutex<Vec<Vec<u8>>>,
+}
+
+/// Hands out packed [`Nv12Buffer`]s of one resolution and takes their memory
+/// back when they are dropped.
+///
+/// Clones share the same buffers, and the pool can be used from several
+/// threads. A recycled frame keeps the contents of its previous use.
+#[derive(Clone)]
+pub struct FramePool {
+    shared: Arc<Shared>,
+}
+
+impl FramePool {
+    /// A pool of `width`x`height` frames with `capacity` buffers allocated up
+    /// front. At most `capacity` idle buffers are kept; buffers returned
+    /// beyond that are freed.
+    pub fn new(width: u32, height: u32, capacity: usize) -> Self {
+        let desc = FrameDescriptor::packed(width, height, PixelFormat::NV12);
+        let idle = (0..capacity).map(|_| vec![0; desc.frame_size()]).collect();
+        Self {
+            shared: Arc::new(Shared {
+                desc,
+                capacity,
+                idle: Mutex::new(idle),
+            }),
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.shared.desc.width, self.shared.desc.height)
+    }
+
+    /// The number of buffers waiting to be reused.
+    pub fn idle(&self) -> usize {
+        self.shared.idle.lock().unwrap().len()
+    }
+
+    /// A frame from the pool, or a newly allocated one if all buffers are in
+    /// use.
+    pub fn get(&self) -> PooledFrame {
+        let desc = &self.shared.desc;
+        let data = self.shared.idle.lock().unwrap().pop();
+        let data = data.unwrap_or_else(|| vec![0; desc.frame_size()]);
+        let image =
+            NV12Image::from_strided(data, desc.width, desc.height, desc.y_stride, desc.uv_stride);
+        PooledFrame {
+            image: Some(image),
+            pool: self.shared.clone(),
+        }
+    }
+}
+
+/// A frame borrowed from a [`FramePool`]. Derefs to [`Nv12Buffer`] and
+/// returns its buffer to the pool on drop.
+pub struct PooledFrame {
+    image: Option<Nv12Buffer>,
+    pool: Arc<Shared>,
+}
+
+impl PooledFrame {
+    /// Takes the frame out of the pool for good.
+    pub fn detach(mut self) -> Nv12Buffer {
+        self.image.take().unwrap()
+    }
+}
+
+impl Deref for PooledFrame {
+    type Target = Nv12Buffer;
+
+    fn deref(&self) -> &Nv12Buffer {
+        self.image.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledFrame {
+    fn deref_mut(&mut self) -> &mut Nv12Buffer {
+        self.image.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledFrame {
+    fn drop(&mut self) {
+        let (data, uv_data) = match self.image.take() {
+            Some(image) => image.into_planes(),
+            None => return,
+        };
+        // The frame may have been replaced through `DerefMut`; only take back
+        // buffers with the pool's layout.
+        if uv_data.is_some() || data.len() != self.pool.desc.frame_size() {
+            return;
+        }
+        let mut idle = self.pool.idle.lock().unwrap();
+        if idle.len() < self.pool.capacity {
+            idle.push(data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RED;
+
+    #[test]
+    fn reuses_returned_buffers() {
+        let pool = FramePool::new(4, 4, 1);
+        let mut frame = pool.get();
+        frame.fill(RED);
+        let ptr = frame.as_bytes().as_ptr();
+        assert_eq!(pool.idle(), 0);
+        let extra = pool.get();
+        drop(frame);
+        drop(extra);
+        assert_eq!(pool.idle(), 1);
+        let frame = pool.get();
+        assert_eq!(frame.as_bytes().as_ptr(), ptr);
+        assert_eq!(frame.pixel(0, 0), RED);
+        assert_eq!(frame.detach().pixel(3, 3), RED);
+        assert_eq!(pool.idle(), 0);
+    }
+}
@@ -0,0 +1,138 @@
+//! Python bindings, exposing `yuvimg.Nv12Frame` and `yuvimg.Font`.
+//!
+//! Build an importable module with
+//! `cargo rustc --release --features python,pyo3/extension-module --crate-type cdylib`
+//! and rename the library to `yuvimg.so` (`yuvimg.pyd` on Windows):
+//!
+//! ```python
+//! import numpy as np, yuvimg
+//! frame = yuvimg.Nv12Frame(np.fromfile("1.yuv", np.uint8), 1920, 1080)
+//! font = yuvimg.Font(open("font.ttf", "rb").read())
+//! frame.draw_rect(100, 100, 200, 100, (0, 128, 128))
+//! frame.draw_text(font, 100, 100, 48.0, "label", (255, 128, 128))
+//! rgb = frame.to_rgb_numpy()  # (1080, 1920, 3)
+//! ```
+//!
+//! Colors are `(y, u, v)` tuples.
+
+use imageproc::drawing::{draw_hollow_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use numpy::{IntoPyArray, PyArray1, PyArray3, PyReadonlyArrayDyn, ToPyArray};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rusttype::{Font, Scale};
+
+use crate::convert::to_rgb_bytes;
+use crate::{NV12Image, PixelFormat, YUV};
+
+/// A parsed TrueType or OpenType font.
+#[pyclass(name = "Font")]
+pub struct PyFont(Font<'static>);
+
+#[pymethods]
+impl PyFont {
+    #[new]
+    fn new(data: Vec<u8>) -> PyResult<Self> {
+        Font::try_from_vec(data)
+            .map(PyFont)
+            .ok_or_else(|| PyValueError::new_err("cannot parse font data"))
+    }
+}
+
+/// A tightly packed NV12 frame, copied from a numpy array.
+#[pyclass]
+pub struct Nv12Frame {
+    image: NV12Image<Vec<u8>>,
+}
+
+#[pymethods]
+impl Nv12Frame {
+    /// Copies a `width`x`height` frame from a contiguous `uint8` array of
+    /// any shape, e.g. the `(height * 3 / 2, width)` arrays used by OpenCV.
+    #[new]
+    fn new(array: PyReadonlyArrayDyn<'_, u8>, width: u32, height: u32) -> PyResult<Self> {
+        let bytes = array
+            .as_slice()
+            .map_err(|_| PyValueError::new_err("array must be C-contiguous"))?;
+        let len = PixelFormat::NV12.frame_size(width, height);
+        if bytes.len() != len {
+            return Err(PyValueError::new_err(format!(
+                "a {}x{} frame has {} bytes, the array has {}",
+                width,
+                height,
+                len,
+                bytes.len()
+            )));
+        }
+        Ok(Self {
+            image: NV12Image::from(bytes.to_vec(), width, height),
+        })
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.image.width
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.image.height
+    }
+
+    /// Draws a one pixel wide rectangle outline.
+    fn draw_rect(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: (u8, u8, u8),
+    ) -> PyResult<()> {
+        if width == 0 || height == 0 {
+            return Err(PyValueError::new_err("rectangle must not be empty"));
+        }
+        let rect = Rect::at(x, y).of_size(width, height);
+        draw_hollow_rect_mut(&mut self.image, rect, YUV([color.0, color.1, color.2]));
+        Ok(())
+    }
+
+    /// Draws `text` with its top-left corner at `(x, y)` and a glyph height
+    /// of `scale` pixels.
+    fn draw_text(
+        &mut self,
+        font: PyRef<'_, PyFont>,
+        x: i32,
+        y: i32,
+        scale: f32,
+        text: &str,
+        color: (u8, u8, u8),
+    ) {
+        draw_text_mut(
+            &mut self.image,
+            YUV([color.0, color.1, color.2]),
+            x,
+            y,
+            Scale::uniform(scale),
+            &font.0,
+            text,
+        );
+    }
+
+    /// The frame bytes as a flat `uint8` array.
+    fn to_numpy<'py>(&self, py: Python<'py>) -> &'py PyArray1<u8> {
+        self.image.as_bytes().to_pyarray(py)
+    }
+
+    /// The frame converted to a `(height, width, 3)` RGB array.
+    fn to_rgb_numpy<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray3<u8>> {
+        let shape = [self.image.height as usize, self.image.width as usize, 3];
+        to_rgb_bytes(&self.image).into_pyarray(py).reshape(shape)
+    }
+}
+
+#[pymodule]
+fn yuvimg(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Nv12Frame>()?;
+    m.add_class::<PyFont>()?;
+    Ok(())
+}
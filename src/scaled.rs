@@ -0,0 +1,133 @@
+use std::ops::{Deref, DerefMut};
+
+use image::{GenericImage, GenericImageView};
+
+use crate::{NV12Image, NV12Image2, YUV};
+
+/// Presents a downscaled view like [`NV12Image2`] at `factor` times its
+/// resolution, so the same coordinates, sizes and font scales draw the same
+/// picture on any view.
+///
+/// Each pixel of the view covers `factor`x`factor` pixels of the wrapper;
+/// drawing on any of them writes the view pixel.
+///
+/// ```
+/// use imageproc::{drawing::draw_hollow_rect_mut, rect::Rect};
+/// use yuvimg::{Nv12Buffer, ScaledDraw, BLACK, GREEN};
+///
+/// // Coordinates of the full 1920x1080 frame, drawn on the half
+/// // resolution view.
+/// let mut canvas = ScaledDraw::half(Nv12Buffer::filled(1920, 1080, BLACK));
+/// draw_hollow_rect_mut(&mut canvas, Rect::at(101, 100).of_size(201, 100), GREEN);
+/// ```
+pub struct ScaledDraw<I> {
+    inner: I,
+    factor: u32,
+}
+
+impl<I: GenericImageView> ScaledDraw<I> {
+    /// Panics if `factor` is zero.
+    pub fn new(inner: I, factor: u32) -> Self {
+        assert!(factor > 0, "scale factor must not be zero");
+        Self { inner, factor }
+    }
+
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+impl<T: Deref<Target = [u8]>> ScaledDraw<NV12Image2<T>> {
+    /// The [`NV12Image2`] view of `img`, addressed in the coordinates of
+    /// `img` itself.
+    pub fn half(img: NV12Image<T>) -> Self {
+        Self::new(NV12Image2(img), 2)
+    }
+}
+
+impl<I> Deref for ScaledDraw<I> {
+    type Target = I;
+
+    fn deref(&self) -> &I {
+        &self.inner
+    }
+}
+
+impl<I> DerefMut for ScaledDraw<I> {
+    fn deref_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+}
+
+impl<I: GenericImageView<Pixel = YUV>> GenericImageView for ScaledDraw<I> {
+    type Pixel = YUV;
+
+    fn dimensions(&self) -> (u32, u32) {
+        let (width, height) = self.inner.dimensions();
+        (width * self.factor, height * self.factor)
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        let (width, height) = self.dimensions();
+        (0, 0, width, height)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        self.inner.get_pixel(x / self.factor, y / self.factor)
+    }
+}
+
+#[allow(deprecated)]
+impl<I: GenericImage<Pixel = YUV>> GenericImage for ScaledDraw<I> {
+    /// Always panics: a view pixel covers several pixels of the wrapper, so
+    /// there is no single pixel to borrow.
+    fn get_pixel_mut(&mut self, _: u32, _: u32) -> &mut Self::Pixel {
+        panic!("ScaledDraw has no addressable pixels, use put_pixel instead")
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.inner
+            .put_pixel(x / self.factor, y / self.factor, pixel)
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.put_pixel(x, y, pixel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Nv12Buffer, BLACK, RED};
+
+    #[test]
+    fn maps_full_resolution_coordinates() {
+        let mut canvas = ScaledDraw::half(Nv12Buffer::filled(8, 6, BLACK));
+        assert_eq!(canvas.dimensions(), (8, 6));
+        canvas.put_pixel(5, 5, RED);
+        assert_eq!(canvas.get_pixel(4, 4), RED);
+        let img = canvas.into_inner().0;
+        assert_eq!(img.pixel(4, 4), RED);
+        assert_eq!(img.pixel(2, 4), BLACK);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn blends_by_replacing() {
+        let mut canvas = ScaledDraw::half(Nv12Buffer::filled(8, 6, BLACK));
+        canvas.blend_pixel(7, 1, RED);
+        assert_eq!(canvas.get_pixel(6, 0), RED);
+    }
+
+    #[test]
+    #[should_panic(expected = "ScaledDraw has no addressable pixels")]
+    #[allow(deprecated)]
+    fn get_pixel_mut_panics() {
+        let mut canvas = ScaledDraw::half(Nv12Buffer::filled(8, 6, BLACK));
+        canvas.get_pixel_mut(0, 0);
+    }
+}
@@ -0,0 +1,177 @@
+//! Frames shared between threads.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use image::{GenericImage, GenericImageView};
+
+use crate::{NV12Image, Nv12Buffer, YUV};
+
+/// A read-only NV12 frame whose planes are reference counted, so clones are
+/// cheap and can be handed to several analysis threads at once. A producer
+/// fills an [`Nv12Buffer`](crate::Nv12Buffer) and publishes it with
+/// [`into_shared`](NV12Image::into_shared).
+pub type Nv12Frame = NV12Image<Arc<[u8]>>;
+
+impl<S: Copy> NV12Image<Vec<S>, S> {
+    /// Moves the planes behind an [`Arc`]. This copies them once into the
+    /// reference counted allocation.
+    pub fn into_shared(self) -> NV12Image<Arc<[S]>, S> {
+        self.map_storage(Arc::from)
+    }
+}
+
+impl<S: Copy> NV12Image<Arc<[S]>, S> {
+    /// Copies the planes into a frame that can be drawn on.
+    pub fn to_owned_buffer(&self) -> NV12Image<Vec<S>, S> {
+        self.clone().map_storage(|plane| plane.to_vec())
+    }
+}
+
+/// A frame that borrows a shared [`Nv12Frame`] until it is first modified,
+/// and then works on a private copy. Pipelines that annotate only some
+/// frames copy only those.
+///
+/// With the `std` feature it implements `GenericImage`, so drawing functions
+/// trigger the copy on their first write.
+#[derive(Clone)]
+pub enum CowFrame {
+    Shared(Nv12Frame),
+    Owned(Nv12Buffer),
+}
+
+impl CowFrame {
+    pub fn new(frame: Nv12Frame) -> Self {
+        CowFrame::Shared(frame)
+    }
+
+    /// Whether the frame has been copied.
+    pub fn is_owned(&self) -> bool {
+        matches!(self, CowFrame::Owned(_))
+    }
+
+    /// Borrows the current contents.
+    pub fn view(&self) -> NV12Image<&[u8]> {
+        match self {
+            CowFrame::Shared(frame) => frame.view(),
+            CowFrame::Owned(buffer) => buffer.view(),
+        }
+    }
+
+    /// The frame for writing, copying the shared planes first if they have
+    /// not been copied yet.
+    pub fn to_mut(&mut self) -> &mut Nv12Buffer {
+        if let CowFrame::Shared(frame) = self {
+            *self = CowFrame::Owned(frame.to_owned_buffer());
+        }
+        match self {
+            CowFrame::Owned(buffer) => buffer,
+            CowFrame::Shared(_) => unreachable!(),
+        }
+    }
+
+    /// The frame to pass on, shared again. The original frame if nothing
+    /// was modified.
+    pub fn into_shared(self) -> Nv12Frame {
+        match self {
+            CowFrame::Shared(frame) => frame,
+            CowFrame::Owned(buffer) => buffer.into_shared(),
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> YUV {
+        self.view().pixel(x, y)
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: YUV) {
+        self.to_mut().set_pixel(x, y, pixel)
+    }
+}
+
+impl From<Nv12Frame> for CowFrame {
+    fn from(frame: Nv12Frame) -> Self {
+        CowFrame::new(frame)
+    }
+}
+
+#[cfg(feature = "std")]
+impl GenericImageView for CowFrame {
+    type Pixel = YUV;
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.view().dimensions()
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        let (width, height) = self.dimensions();
+        (0, 0, width, height)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        self.pixel(x, y)
+    }
+}
+
+#[cfg(feature = "std")]
+impl GenericImage for CowFrame {
+    /// Always panics, without copying a shared frame first: like
+    /// [`NV12Image`], the frame has no `YUV` to borrow.
+    fn get_pixel_mut(&mut self, _: u32, _: u32) -> &mut Self::Pixel {
+        panic!("CowFrame has no addressable pixels, use put_pixel instead")
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.set_pixel(x, y, pixel)
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.set_pixel(x, y, pixel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Nv12Buffer, RED};
+
+    #[test]
+    fn shared_frames_are_cheap_to_clone_across_threads() {
+        let mut img = Nv12Buffer::filled(4, 4, RED);
+        img.set_pixel(0, 0, crate::BLUE);
+        let frame = img.into_shared();
+        let copy = frame.clone();
+        assert!(Arc::ptr_eq(frame.ref_data(), copy.ref_data()));
+        let pixel = std::thread::spawn(move || copy.pixel(2, 2)).join().unwrap();
+        assert_eq!(pixel, RED);
+        assert_eq!(frame.to_owned_buffer().pixel(0, 0), crate::BLUE);
+    }
+
+    #[test]
+    fn cow_frame_copies_on_first_write() {
+        let frame = Nv12Buffer::filled(4, 4, RED).into_shared();
+        let untouched = CowFrame::new(frame.clone());
+        assert!(Arc::ptr_eq(
+            untouched.into_shared().ref_data(),
+            frame.ref_data()
+        ));
+        let mut cow = CowFrame::from(frame.clone());
+        assert_eq!(cow.pixel(0, 0), RED);
+        cow.set_pixel(0, 0, crate::BLUE);
+        assert!(cow.is_owned());
+        assert_eq!(cow.pixel(0, 0), crate::BLUE);
+        assert_eq!(frame.pixel(0, 0), RED);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[allow(deprecated)]
+    fn get_pixel_mut_panics_without_copying() {
+        let mut cow = CowFrame::new(Nv12Buffer::filled(4, 4, RED).into_shared());
+        let borrowed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cow.get_pixel_mut(0, 0);
+        }));
+        assert!(borrowed.is_err());
+        assert!(!cow.is_owned());
+    }
+}
@@ -0,0 +1,80 @@
+use core::ops::Deref;
+
+#[cfg(feature = "std")]
+use image::GenericImageView;
+
+use crate::{NV12Image, YUV};
+
+/// A read-only view of an NV12 image whose pixels have their own luma and
+/// chroma interpolated bilinearly from the four nearest chroma samples,
+/// instead of the chroma of their 2x2 block. Crops read through it show no
+/// chroma blocking, at the cost of slightly softer color edges.
+///
+/// Chroma samples are taken to sit at the center of their block.
+pub struct SmoothChromaView<T: Deref<Target = [u8]>>(pub NV12Image<T>);
+
+impl<T: Deref<Target = [u8]>> SmoothChromaView<T> {
+    /// The neighbouring chroma samples of pixel `p` along one axis of length
+    /// `len`, and the weight of the second in quarters.
+    fn neighbours(p: u32, len: u32) -> (u32, u32, u32) {
+        let max = len.div_ceil(2) as i64 - 1;
+        let pos = 2 * i64::from(p) - 1;
+        let i0 = pos.div_euclid(4).clamp(0, max);
+        let i1 = (pos.div_euclid(4) + 1).clamp(0, max);
+        (i0 as u32, i1 as u32, pos.rem_euclid(4) as u32)
+    }
+
+    /// The pixel at `(x, y)`. Same as `GenericImageView::get_pixel`.
+    pub fn pixel(&self, x: u32, y: u32) -> YUV {
+        let img = &self.0;
+        img.check_bounds(x, y);
+        let (x0, x1, fx) = Self::neighbours(x, img.width);
+        let (y0, y1, fy) = Self::neighbours(y, img.height);
+        let uv = img.uv_plane();
+        let at = |cx: u32, cy: u32, channel: u32| {
+            u32::from(uv[(cy * img.uv_stride + cx * 2 + channel) as usize])
+        };
+        let chroma = |channel: u32| {
+            let top = at(x0, y0, channel) * (4 - fx) + at(x1, y0, channel) * fx;
+            let bottom = at(x0, y1, channel) * (4 - fx) + at(x1, y1, channel) * fx;
+            ((top * (4 - fy) + bottom * fy + 8) / 16) as u8
+        };
+        let luma = img.data[(y * img.y_stride + x) as usize];
+        YUV([luma, chroma(0), chroma(1)])
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Deref<Target = [u8]>> GenericImageView for SmoothChromaView<T> {
+    type Pixel = YUV;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.0.width, self.0.height)
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        (0, 0, self.0.width, self.0.height)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        self.pixel(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Nv12Buffer;
+
+    #[test]
+    fn interpolates_chroma_between_blocks() {
+        let mut img = Nv12Buffer::filled(4, 2, YUV([50, 0, 100]));
+        img.set_pixel(2, 0, YUV([60, 200, 100]));
+        let view = SmoothChromaView(img);
+        let row: Vec<[u8; 3]> = (0..4).map(|x| view.pixel(x, 1).0).collect();
+        assert_eq!(
+            row,
+            [[50, 0, 100], [50, 50, 100], [60, 150, 100], [60, 200, 100]]
+        );
+    }
+}
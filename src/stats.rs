@@ -0,0 +1,165 @@
+//! Statistics computed directly from the planes of NV12 frames.
+
+use core::ops::Deref;
+
+use crate::{NV12Image, Region};
+
+/// Histogram of the luma of every pixel.
+pub fn histogram_y<T: Deref<Target = [u8]>>(img: &NV12Image<T>) -> [u32; 256] {
+    histogram_y_region(img, Region::new(0, 0, img.width, img.height))
+}
+
+/// Histogram of the luma of the pixels in `region`, clipped to the image.
+pub fn histogram_y_region<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    region: Region,
+) -> [u32; 256] {
+    let region = region.clip(img.width, img.height);
+    let mut histogram = [0; 256];
+    for y in region.y..region.y + region.height {
+        let row = &img.y_row(y)[region.x as usize..(region.x + region.width) as usize];
+        for &luma in row {
+            histogram[luma as usize] += 1;
+        }
+    }
+    histogram
+}
+
+/// Luma statistics of a region, see [`stats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegionStats {
+    pub mean: f64,
+    pub variance: f64,
+    pub min: u8,
+    pub max: u8,
+    /// Number of pixels the statistics cover.
+    pub count: u64,
+}
+
+impl RegionStats {
+    #[cfg(feature = "std")]
+    pub fn stddev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+/// Mean, variance, minimum and maximum of the luma in `region`, clipped to
+/// the image. Returns `None` if nothing of the region is inside the image.
+pub fn stats<T: Deref<Target = [u8]>>(img: &NV12Image<T>, region: Region) -> Option<RegionStats> {
+    let region = region.clip(img.width, img.height);
+    if region.is_empty() {
+        return None;
+    }
+    let (mut sum, mut sum_sq, mut min, mut max) = (0u64, 0u64, u8::MAX, u8::MIN);
+    for y in region.y..region.y + region.height {
+        let row = &img.y_row(y)[region.x as usize..(region.x + region.width) as usize];
+        for &luma in row {
+            sum += luma as u64;
+            sum_sq += luma as u64 * luma as u64;
+            min = min.min(luma);
+            max = max.max(luma);
+        }
+    }
+    let count = region.width as u64 * region.height as u64;
+    let mean = sum as f64 / count as f64;
+    Some(RegionStats {
+        mean,
+        variance: (sum_sq as f64 / count as f64 - mean * mean).max(0.),
+        min,
+        max,
+        count,
+    })
+}
+
+/// Average `[U, V]` of the chroma blocks `region` touches, or `None` if
+/// nothing of the region is inside the image.
+pub fn chroma_mean<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    region: Region,
+) -> Option<[f64; 2]> {
+    let region = region.clip(img.width, img.height);
+    if region.is_empty() {
+        return None;
+    }
+    let (bx0, bx1) = (region.x / 2, (region.x + region.width).div_ceil(2));
+    let (by0, by1) = (region.y / 2, (region.y + region.height).div_ceil(2));
+    let (mut u, mut v) = (0u64, 0u64);
+    for by in by0..by1 {
+        let row = img.uv_row(by);
+        for bx in bx0..bx1 {
+            let i = bx as usize * 2;
+            u += row[i] as u64;
+            v += row[i + 1] as u64;
+        }
+    }
+    let count = ((bx1 - bx0) * (by1 - by0)) as f64;
+    Some([u as f64 / count, v as f64 / count])
+}
+
+/// Whether at least `pct` percent of the pixels have a luma of at most
+/// `luma_threshold`, as on signal loss or a covered lens. Video range black
+/// is 16, so a threshold a little above that, with `pct` around 98, suits
+/// most sources.
+pub fn is_black_frame<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    luma_threshold: u8,
+    pct: f32,
+) -> bool {
+    let histogram = histogram_y(img);
+    let dark: u64 = histogram[..=luma_threshold as usize]
+        .iter()
+        .map(|&count| count as u64)
+        .sum();
+    let total = img.width as u64 * img.height as u64;
+    dark as f64 >= total as f64 * pct as f64 / 100.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histograms() {
+        let mut data: Vec<u8> = (0..16).collect();
+        data.extend([0; 8]);
+        let img = NV12Image::from(data, 4, 4);
+        let histogram = histogram_y(&img);
+        assert_eq!(histogram.iter().sum::<u32>(), 16);
+        assert_eq!(histogram[5], 1);
+        let region = histogram_y_region(&img, Region::new(3, 2, 4, 4));
+        assert_eq!(region.iter().sum::<u32>(), 2);
+        assert_eq!((region[11], region[15]), (1, 1));
+    }
+
+    #[test]
+    fn region_stats() {
+        let mut data: Vec<u8> = (0..16).collect();
+        data.extend([10, 20, 30, 40, 50, 60, 70, 80]);
+        let img = NV12Image::from(data, 4, 4);
+        let s = stats(&img, Region::new(0, 0, 2, 2)).unwrap();
+        assert_eq!((s.min, s.max, s.count), (0, 5, 4));
+        assert_eq!((s.mean, s.variance), (2.5, 4.25));
+        assert!(stats(&img, Region::new(4, 0, 2, 2)).is_none());
+        assert_eq!(chroma_mean(&img, Region::new(1, 1, 2, 1)), Some([20., 30.]));
+    }
+
+    #[test]
+    fn chroma_of_odd_widths() {
+        let data = vec![0, 0, 0, 0, 0, 0, 10, 20, 30, 40];
+        let img = NV12Image::try_from(data, 3, 2).unwrap();
+        assert_eq!(img.uv_row(0), [10, 20, 30, 40]);
+        assert_eq!(chroma_mean(&img, Region::new(2, 0, 1, 2)), Some([30., 40.]));
+        assert_eq!(chroma_mean(&img, Region::new(0, 0, 3, 2)), Some([20., 30.]));
+    }
+
+    #[test]
+    fn black_frames() {
+        let mut data = vec![16u8; 16];
+        data[0] = 200;
+        data.extend([128; 8]);
+        let img = NV12Image::from(data, 4, 4);
+        assert!(is_black_frame(&img, 20, 90.));
+        assert!(!is_black_frame(&img, 20, 99.));
+        assert!(!is_black_frame(&img, 15, 50.));
+    }
+}
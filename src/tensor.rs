@@ -0,0 +1,262 @@
+//! Conversion of a region of an NV12 frame into a normalized `f32` tensor,
+//! for feeding neural networks.
+//!
+//! Cropping, bilinear resizing, RGB conversion and normalization happen in a
+//! single pass over the output, reading the planes directly.
+
+use core::ops::Deref;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{NV12Image, Region, YuvError};
+
+/// Order of the values in the tensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TensorLayout {
+    /// RGB planes one after the other, shape `(3, height, width)`.
+    CHW,
+    /// RGB interleaved per pixel, shape `(height, width, 3)`.
+    HWC,
+    /// Luma only, shape `(height, width)`, without RGB conversion.
+    Luma,
+}
+
+impl TensorLayout {
+    pub fn channels(&self) -> usize {
+        match self {
+            TensorLayout::CHW | TensorLayout::HWC => 3,
+            TensorLayout::Luma => 1,
+        }
+    }
+}
+
+/// Mapping of the 8-bit channel values to tensor values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    /// The values as they are, `0.0..=255.0`.
+    Raw,
+    /// `0.0..=1.0`.
+    Unit,
+    /// `-1.0..=1.0`.
+    Symmetric,
+    /// `(value / 255 - mean) / std` per channel. Only the first channel is
+    /// used for [`TensorLayout::Luma`].
+    MeanStd { mean: [f32; 3], std: [f32; 3] },
+}
+
+impl Normalization {
+    /// The mean and standard deviation of ImageNet, used by most pretrained
+    /// classification models.
+    pub const IMAGENET: Self = Normalization::MeanStd {
+        mean: [0.485, 0.456, 0.406],
+        std: [0.229, 0.224, 0.225],
+    };
+
+    fn apply(&self, value: f32, channel: usize) -> f32 {
+        match self {
+            Normalization::Raw => value,
+            Normalization::Unit => value / 255.,
+            Normalization::Symmetric => value / 127.5 - 1.,
+            Normalization::MeanStd { mean, std } => (value / 255. - mean[channel]) / std[channel],
+        }
+    }
+}
+
+/// The two source samples around output sample `o` of a `dst` long axis
+/// mapped onto `len` samples starting at `start`, and the weight of the
+/// second. Chroma samples sit at the center of their block, so the same
+/// mapping applies to the chroma plane with `start` and `len` halved.
+///
+/// Samples are clamped to `min..=max`, the edges of the region, so taps at
+/// the border repeat it like resizing a crop would.
+fn neighbours(o: u32, dst: u32, start: f32, len: f32, (min, max): (u32, u32)) -> (u32, u32, f32) {
+    let pos = (start + (o as f32 + 0.5) * len / dst as f32 - 0.5).max(min as f32);
+    let i0 = (pos as u32).min(max);
+    (i0, (i0 + 1).min(max), pos - pos as u32 as f32)
+}
+
+/// `rect` of `img`, resized to `size` and converted into a tensor. See
+/// [`to_tensor_into`].
+pub fn to_tensor<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    rect: Region,
+    size: (u32, u32),
+    layout: TensorLayout,
+    normalization: Normalization,
+) -> Vec<f32> {
+    let len = size.0 as usize * size.1 as usize * layout.channels();
+    let mut out = vec![0.; len];
+    to_tensor_into(img, rect, size, layout, normalization, &mut out);
+    out
+}
+
+/// Writes `rect` of `img`, bilinearly resized to `size` (width, height), into
+/// `out` as RGB converted with the image's color matrix, or as luma. `rect`
+/// is clipped to the image.
+///
+/// Panics if the clipped `rect` is empty or `out` is not `width * height *
+/// channels` long; [`try_to_tensor_into`] reports these as errors.
+pub fn to_tensor_into<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    rect: Region,
+    size: (u32, u32),
+    layout: TensorLayout,
+    normalization: Normalization,
+    out: &mut [f32],
+) {
+    let rect = rect.clip(img.width, img.height);
+    assert!(!rect.is_empty(), "tensor region is outside the image");
+    let (width, height) = size;
+    let plane = width as usize * height as usize;
+    assert_eq!(out.len(), plane * layout.channels(), "tensor buffer size");
+    let m = img.color_matrix;
+    let uv = img.uv_plane();
+    let (last_x, last_y) = (rect.x + rect.width - 1, rect.y + rect.height - 1);
+    let (x_bounds, y_bounds) = ((rect.x, last_x), (rect.y, last_y));
+    let (cx_bounds, cy_bounds) = ((rect.x / 2, last_x / 2), (rect.y / 2, last_y / 2));
+    let lerp = |a: u8, b: u8, f: f32| a as f32 + (b as f32 - a as f32) * f;
+
+    for oy in 0..height {
+        let (y0, y1, fy) = neighbours(oy, height, rect.y as f32, rect.height as f32, y_bounds);
+        let (cy0, cy1, fcy) = neighbours(
+            oy,
+            height,
+            rect.y as f32 / 2.,
+            rect.height as f32 / 2.,
+            cy_bounds,
+        );
+        for ox in 0..width {
+            let (x0, x1, fx) = neighbours(ox, width, rect.x as f32, rect.width as f32, x_bounds);
+            let luma = |x: u32, y: u32| img.data[(y * img.y_stride + x) as usize];
+            let top = lerp(luma(x0, y0), luma(x1, y0), fx);
+            let bottom = lerp(luma(x0, y1), luma(x1, y1), fx);
+            let luma = top + (bottom - top) * fy;
+            let i = (oy * width + ox) as usize;
+            if layout == TensorLayout::Luma {
+                out[i] = normalization.apply(luma, 0);
+                continue;
+            }
+
+            let (cx0, cx1, fcx) = neighbours(
+                ox,
+                width,
+                rect.x as f32 / 2.,
+                rect.width as f32 / 2.,
+                cx_bounds,
+            );
+            let chroma = |channel: u32| {
+                let at = |x: u32, y: u32| uv[(y * img.uv_stride + x * 2 + channel) as usize];
+                let top = lerp(at(cx0, cy0), at(cx1, cy0), fcx);
+                let bottom = lerp(at(cx0, cy1), at(cx1, cy1), fcx);
+                top + (bottom - top) * fcy - 128.
+            };
+            let (u, v) = (chroma(0), chroma(1));
+            let ys = m.y_scale * (luma - m.y_offset);
+            let rgb = [ys + m.rv * v, ys - m.gu * u - m.gv * v, ys + m.bu * u];
+            for (c, value) in rgb.into_iter().enumerate() {
+                let value = normalization.apply(value.clamp(0., 255.), c);
+                match layout {
+                    TensorLayout::CHW => out[c * plane + i] = value,
+                    _ => out[i * 3 + c] = value,
+                }
+            }
+        }
+    }
+}
+
+/// Like [`to_tensor_into`], but returns an error instead of panicking:
+/// [`YuvError::OutOfBounds`] with the corner of `rect` if it lies outside the
+/// image, or [`YuvError::LengthMismatch`] for an `out` of the wrong length.
+pub fn try_to_tensor_into<T: Deref<Target = [u8]>>(
+    img: &NV12Image<T>,
+    rect: Region,
+    size: (u32, u32),
+    layout: TensorLayout,
+    normalization: Normalization,
+    out: &mut [f32],
+) -> Result<(), YuvError> {
+    if rect.clip(img.width, img.height).is_empty() {
+        return Err(YuvError::OutOfBounds {
+            x: rect.x,
+            y: rect.y,
+            width: img.width,
+            height: img.height,
+        });
+    }
+    let expected = size.0 as usize * size.1 as usize * layout.channels();
+    if out.len() != expected {
+        return Err(YuvError::LengthMismatch {
+            len: out.len(),
+            expected,
+        });
+    }
+    to_tensor_into(img, rect, size, layout, normalization, out);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Nv12Buffer, BLUE, RED};
+
+    #[test]
+    fn crops_scales_and_normalizes() {
+        let mut img = Nv12Buffer::filled(8, 4, BLUE);
+        img.fill_rect(4, 0, 4, 4, RED);
+        let rect = Region::new(4, 0, 4, 4);
+        let chw = to_tensor(&img, rect, (2, 2), TensorLayout::CHW, Normalization::Unit);
+        let [r, g, b] = RED.rgb_f32().map(|c| c / 255.);
+        for (c, expected) in [r, g, b].into_iter().enumerate() {
+            for &value in &chw[c * 4..c * 4 + 4] {
+                assert!((value - expected).abs() < 1e-3, "{} {}", value, expected);
+            }
+        }
+
+        let luma = to_tensor(&img, rect, (2, 1), TensorLayout::Luma, Normalization::Raw);
+        assert_eq!(luma, [RED.0[0] as f32; 2]);
+    }
+
+    #[test]
+    fn samples_stay_inside_the_region() {
+        let mut img = Nv12Buffer::filled(8, 4, BLUE);
+        img.fill_rect(4, 0, 4, 4, RED);
+        let rect = Region::new(0, 0, 4, 4);
+        let luma = to_tensor(&img, rect, (8, 4), TensorLayout::Luma, Normalization::Raw);
+        assert_eq!(luma, [BLUE.0[0] as f32; 32]);
+        let rgb = to_tensor(&img, rect, (8, 4), TensorLayout::HWC, Normalization::Raw);
+        let edge = to_tensor(&img, rect, (1, 1), TensorLayout::HWC, Normalization::Raw);
+        assert_eq!(rgb[7 * 3..8 * 3], edge[..]);
+    }
+
+    #[test]
+    fn try_to_tensor_into_checks_arguments() {
+        let img = Nv12Buffer::filled(8, 4, BLUE);
+        let mut out = [0.; 4];
+        assert!(matches!(
+            try_to_tensor_into(
+                &img,
+                Region::new(8, 0, 2, 2),
+                (2, 2),
+                TensorLayout::Luma,
+                Normalization::Raw,
+                &mut out
+            ),
+            Err(YuvError::OutOfBounds { x: 8, y: 0, .. })
+        ));
+        assert!(matches!(
+            try_to_tensor_into(
+                &img,
+                Region::new(0, 0, 2, 2),
+                (2, 2),
+                TensorLayout::CHW,
+                Normalization::Raw,
+                &mut out
+            ),
+            Err(YuvError::LengthMismatch {
+                len: 4,
+                expected: 12
+            })
+        ));
+    }
+}
@@ -0,0 +1,698 @@
+//! Text rendering with a cache of rasterized glyphs, for labels and
+//! timestamps that are burnt into every frame.
+//!
+//! [`draw_paragraph_mut`] and [`TextRenderer::draw_paragraph_mut`] lay out
+//! multi-line text: they wrap lines at spaces and between CJK characters,
+//! following the usual line breaking rules for CJK punctuation, and never
+//! separate a combining mark from its base character.
+//!
+//! Fonts loaded from their bytes with [`FontStack::from_bytes`] are shaped
+//! with `rustybuzz`, a port of HarfBuzz: combining marks are positioned by
+//! the font's mark attachment, and ligatures and contextual forms, e.g. of
+//! Arabic or Devanagari, are applied. Fonts given as a `rusttype::Font` are
+//! placed by advance and kerning only. Runs of right-to-left text are shaped
+//! right to left, but lines mixing directions are not reordered.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::DerefMut;
+
+use rusttype::{point, Font, GlyphId, Scale, VMetrics};
+use rustybuzz::{Face, UnicodeBuffer};
+
+use crate::annotate::CoverageMask;
+use crate::overlay::blend_region_mut;
+use crate::{NV12Image, Region, YUV};
+
+/// Font index in the stack, glyph and scale.
+type GlyphKey = (usize, GlyphId, u32, u32);
+
+/// Coverage of one glyph rasterized at the origin, 0-255 per pixel.
+struct GlyphBitmap {
+    left: i32,
+    top: i32,
+    width: u32,
+    height: u32,
+    coverage: Vec<u8>,
+}
+
+fn rasterize(font: &Font, id: GlyphId, scale: Scale) -> GlyphBitmap {
+    let glyph = font.glyph(id).scaled(scale).positioned(point(0.0, 0.0));
+    let bb = match glyph.pixel_bounding_box() {
+        Some(bb) => bb,
+        None => {
+            return GlyphBitmap {
+                left: 0,
+                top: 0,
+                width: 0,
+                height: 0,
+                coverage: Vec::new(),
+            }
+        }
+    };
+    let (width, height) = (bb.width() as u32, bb.height() as u32);
+    let mut coverage = vec![0; width as usize * height as usize];
+    glyph.draw(|x, y, v| coverage[(y * width + x) as usize] = (v * 255.0 + 0.5) as u8);
+    GlyphBitmap {
+        left: bb.min.x,
+        top: bb.min.y,
+        width,
+        height,
+        coverage,
+    }
+}
+
+/// Horizontal alignment of the lines of a paragraph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// How [`TextRenderer::draw_paragraph_mut`] draws text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    pub color: YUV,
+    pub scale: Scale,
+    pub align: Align,
+    /// Distance between baselines, as a multiple of the font's line height.
+    pub line_spacing: f32,
+}
+
+impl TextStyle {
+    /// Left aligned text with the font's line height.
+    pub fn new(color: YUV, scale: Scale) -> Self {
+        Self {
+            color,
+            scale,
+            align: Align::Left,
+            line_spacing: 1.0,
+        }
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+}
+
+/// A [`TextStyle`] with the fonts to draw in, for [`draw_paragraph_mut`].
+#[derive(Clone, Copy, Debug)]
+pub struct ParagraphStyle<'s, 'f> {
+    pub fonts: &'s FontStack<'f>,
+    pub text: TextStyle,
+}
+
+impl<'s, 'f> ParagraphStyle<'s, 'f> {
+    pub fn new(fonts: &'s FontStack<'f>, text: TextStyle) -> Self {
+        Self { fonts, text }
+    }
+}
+
+/// Draws `text` into `rect` like [`TextRenderer::draw_paragraph_mut`], in
+/// the fonts of `style`. The glyphs are rasterized for this call only; keep
+/// a [`TextRenderer`] for text drawn on every frame. Returns the height of
+/// the drawn lines.
+pub fn draw_paragraph_mut<T: DerefMut<Target = [u8]>>(
+    img: &mut NV12Image<T>,
+    rect: Region,
+    text: &str,
+    style: &ParagraphStyle,
+) -> u32 {
+    TextRenderer::with_fonts(style.fonts.clone()).draw_paragraph_mut(img, rect, text, &style.text)
+}
+
+/// Marks that are drawn over the preceding character.
+fn is_combining(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0300..=0x036f
+            | 0x1ab0..=0x1aff
+            | 0x1dc0..=0x1dff
+            | 0x200d
+            | 0x20d0..=0x20ff
+            | 0x3099..=0x309a
+            | 0xfe00..=0xfe0f
+            | 0xfe20..=0xfe2f
+    )
+}
+
+/// CJK characters, between which lines may break without a space.
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x11ff
+            | 0x2e80..=0x9fff
+            | 0xac00..=0xd7af
+            | 0xf900..=0xfaff
+            | 0xff00..=0xffef
+            | 0x20000..=0x3ffff
+    )
+}
+
+/// Characters that must not start a line.
+fn no_break_before(c: char) -> bool {
+    ",.;:!?)]}%、。，．：；？！）」』】》〉〕］｝・ー々ぁぃぅぇぉっゃゅょァィゥェォッャュョ"
+        .contains(c)
+}
+
+/// Characters that must not end a line.
+fn no_break_after(c: char) -> bool {
+    "([{（「『【《〈〔［｛".contains(c)
+}
+
+/// A character with its combining marks.
+struct Cluster {
+    start: usize,
+    end: usize,
+    base: char,
+    width: f32,
+    /// Whether a line may break before the cluster.
+    break_before: bool,
+}
+
+/// Fonts searched in order for each character, so text mixing scripts, e.g.
+/// Latin with CJK or symbols, renders without missing glyph boxes when no
+/// single font covers all of it.
+///
+/// Combining marks use the font of their base character if it has them.
+/// Lines are laid out with the largest ascent and descent of the fonts, the
+/// baseline being shared.
+#[derive(Clone)]
+pub struct FontStack<'f> {
+    fonts: Vec<Font<'f>>,
+    /// The shaping tables of each font, for fonts loaded from their bytes.
+    faces: Vec<Option<Face<'f>>>,
+}
+
+impl fmt::Debug for FontStack<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shaped: Vec<bool> = self.faces.iter().map(Option::is_some).collect();
+        f.debug_struct("FontStack")
+            .field("fonts", &self.fonts)
+            .field("shaped", &shaped)
+            .finish()
+    }
+}
+
+/// A glyph placed on the baseline, with the font it comes from.
+struct Placed {
+    font: usize,
+    id: GlyphId,
+    x: f32,
+    /// Offset from the baseline, downwards.
+    y: f32,
+    /// The kerning before the glyph plus its advance.
+    width: f32,
+    /// Byte offset in the text of the first character the glyph shows.
+    cluster: usize,
+}
+
+/// Parses the first font of `data` for rasterizing and for shaping.
+fn parse(data: &[u8]) -> Option<(Font<'_>, Face<'_>)> {
+    Some((Font::try_from_bytes(data)?, Face::from_slice(data, 0)?))
+}
+
+impl<'f> FontStack<'f> {
+    /// A stack of `font` alone, placed without shaping.
+    pub fn new(font: Font<'f>) -> Self {
+        Self {
+            fonts: vec![font],
+            faces: vec![None],
+        }
+    }
+
+    /// A stack of the first font of `data`, shaped with `rustybuzz`.
+    /// Returns `None` if the font cannot be parsed.
+    pub fn from_bytes(data: &'f [u8]) -> Option<Self> {
+        let (font, face) = parse(data)?;
+        Some(Self {
+            fonts: vec![font],
+            faces: vec![Some(face)],
+        })
+    }
+
+    /// Adds `font` to search after the fonts already in the stack.
+    pub fn with_fallback(mut self, font: Font<'f>) -> Self {
+        self.fonts.push(font);
+        self.faces.push(None);
+        self
+    }
+
+    /// Like [`with_fallback`](Self::with_fallback) with the first font of
+    /// `data`, shaped with `rustybuzz`. Returns `None` if the font cannot be
+    /// parsed.
+    pub fn with_fallback_bytes(mut self, data: &'f [u8]) -> Option<Self> {
+        let (font, face) = parse(data)?;
+        self.fonts.push(font);
+        self.faces.push(Some(face));
+        Some(self)
+    }
+
+    /// Whether glyphs of font `font` are placed by `rustybuzz`.
+    pub fn is_shaped(&self, font: usize) -> bool {
+        self.faces[font].is_some()
+    }
+
+    pub fn fonts(&self) -> &[Font<'f>] {
+        &self.fonts
+    }
+
+    fn has_glyph(&self, font: usize, c: char) -> bool {
+        self.fonts[font].glyph(c).id() != GlyphId(0)
+    }
+
+    /// The index of the first font with a glyph for `c`, or of the first
+    /// font if none has one.
+    pub fn font_for(&self, c: char) -> usize {
+        (0..self.fonts.len())
+            .find(|&i| self.has_glyph(i, c))
+            .unwrap_or(0)
+    }
+
+    /// Whether any of the fonts has a glyph for `c`.
+    pub fn covers(&self, c: char) -> bool {
+        (0..self.fonts.len()).any(|i| self.has_glyph(i, c))
+    }
+
+    pub fn v_metrics(&self, scale: Scale) -> VMetrics {
+        let mut metrics = self.fonts[0].v_metrics(scale);
+        for font in &self.fonts[1..] {
+            let v = font.v_metrics(scale);
+            metrics.ascent = metrics.ascent.max(v.ascent);
+            metrics.descent = metrics.descent.min(v.descent);
+            metrics.line_gap = metrics.line_gap.max(v.line_gap);
+        }
+        metrics
+    }
+
+    /// Splits `text` into runs of characters drawn with the same font, as
+    /// `(font, start, end)` byte ranges.
+    fn runs(&self, text: &str) -> Vec<(usize, usize, usize)> {
+        let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+        for (i, c) in text.char_indices() {
+            let end = i + c.len_utf8();
+            let font = match runs.last() {
+                Some(&(font, ..)) if is_combining(c) && self.has_glyph(font, c) => font,
+                _ => self.font_for(c),
+            };
+            match runs.last_mut() {
+                Some(run) if run.0 == font => run.2 = end,
+                _ => runs.push((font, i, end)),
+            }
+        }
+        runs
+    }
+
+    /// Places the glyphs of `text` on a baseline starting at x 0: shaped
+    /// runs as `rustybuzz` positions them, other runs one glyph per
+    /// character with kerning between them.
+    fn place(&self, scale: Scale, text: &str) -> Vec<Placed> {
+        let mut placed = Vec::with_capacity(text.len());
+        let mut x = 0.0;
+        for (font, start, end) in self.runs(text) {
+            let run = &text[start..end];
+            match &self.faces[font] {
+                Some(face) => x = shape(face, font, scale, run, start, x, &mut placed),
+                None => {
+                    let mut prev = None;
+                    for (i, c) in run.char_indices() {
+                        let kerning =
+                            prev.map_or(0.0, |prev| self.fonts[font].pair_kerning(scale, prev, c));
+                        let glyph = self.fonts[font].glyph(c).scaled(scale);
+                        let width = kerning + glyph.h_metrics().advance_width;
+                        placed.push(Placed {
+                            font,
+                            id: glyph.id(),
+                            x: x + kerning,
+                            y: 0.0,
+                            width,
+                            cluster: start + i,
+                        });
+                        x += width;
+                        prev = Some(c);
+                    }
+                }
+            }
+        }
+        placed
+    }
+}
+
+/// Shapes `run`, which starts at byte `start` of the text, with `face` and
+/// appends its glyphs to `placed` from `x` on. Returns the x after the run.
+fn shape(
+    face: &Face,
+    font: usize,
+    scale: Scale,
+    run: &str,
+    start: usize,
+    mut x: f32,
+    placed: &mut Vec<Placed>,
+) -> f32 {
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(run);
+    buffer.guess_segment_properties();
+    let glyphs = rustybuzz::shape(face, &[], buffer);
+    // rusttype scales the ascent to descent height of the font to the size.
+    let height = (face.ascender() as f32 - face.descender() as f32).max(1.0);
+    let (fx, fy) = (scale.x / height, scale.y / height);
+    for (info, pos) in glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()) {
+        let width = pos.x_advance as f32 * fx;
+        placed.push(Placed {
+            font,
+            id: GlyphId(info.glyph_id as u16),
+            x: x + pos.x_offset as f32 * fx,
+            y: -pos.y_offset as f32 * fy,
+            width,
+            cluster: start + info.cluster as usize,
+        });
+        x += width;
+    }
+    x
+}
+
+/// Draws text onto NV12 images, rasterizing each glyph only once per
+/// `(glyph, scale)`.
+///
+/// Glyphs are placed on whole pixels, so a glyph looks the same wherever it
+/// is drawn. Luma and chroma are blended with the glyph coverage like
+/// [`overlay_rgba_mut`](crate::overlay::overlay_rgba_mut) blends alpha.
+pub struct TextRenderer<'f> {
+    fonts: FontStack<'f>,
+    cache: HashMap<GlyphKey, GlyphBitmap>,
+}
+
+impl<'f> TextRenderer<'f> {
+    pub fn new(font: Font<'f>) -> Self {
+        Self::with_fonts(FontStack::new(font))
+    }
+
+    /// A renderer shaping text with the first font of `data`. Returns
+    /// `None` if the font cannot be parsed.
+    pub fn from_bytes(data: &'f [u8]) -> Option<Self> {
+        FontStack::from_bytes(data).map(Self::with_fonts)
+    }
+
+    /// A renderer taking each character from the first font of `fonts`
+    /// that has it.
+    pub fn with_fonts(fonts: FontStack<'f>) -> Self {
+        Self {
+            fonts,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// The first font of the stack.
+    pub fn font(&self) -> &Font<'f> {
+        &self.fonts.fonts[0]
+    }
+
+    pub fn fonts(&self) -> &FontStack<'f> {
+        &self.fonts
+    }
+
+    /// Number of glyph bitmaps in the cache.
+    pub fn cached_glyphs(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Rasterizes the glyphs of `text` that are not cached yet. Returns the
+    /// cache key of every glyph with the top-left corner of its bitmap,
+    /// relative to the top-left corner of the text.
+    fn layout(&mut self, scale: Scale, text: &str) -> Vec<(GlyphKey, i64, i64)> {
+        let fonts = &self.fonts;
+        let ascent = fonts.v_metrics(scale).ascent;
+        let mut glyphs = Vec::new();
+        for glyph in fonts.place(scale, text) {
+            let key = (glyph.font, glyph.id, scale.x.to_bits(), scale.y.to_bits());
+            let bitmap = self
+                .cache
+                .entry(key)
+                .or_insert_with(|| rasterize(&fonts.fonts[glyph.font], glyph.id, scale));
+            let left = glyph.x.round() as i64 + bitmap.left as i64;
+            let top = (ascent + glyph.y).round() as i64 + bitmap.top as i64;
+            glyphs.push((key, left, top));
+        }
+        glyphs
+    }
+
+    fn text_width(&self, scale: Scale, text: &str) -> f32 {
+        self.fonts.place(scale, text).iter().map(|g| g.width).sum()
+    }
+
+    /// The clusters of `text`, each as wide as the glyphs shaped from it.
+    fn clusters(&self, scale: Scale, text: &str) -> Vec<Cluster> {
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for (i, c) in text.char_indices() {
+            let end = i + c.len_utf8();
+            match clusters.last_mut() {
+                Some(cluster) if is_combining(c) => {
+                    cluster.end = end;
+                    continue;
+                }
+                _ => {}
+            }
+            let break_before = clusters.last().is_some_and(|p| {
+                !c.is_whitespace()
+                    && (p.base.is_whitespace()
+                        || p.base == '-' && !is_wide(c)
+                        || (is_wide(p.base) || is_wide(c))
+                            && !no_break_before(c)
+                            && !no_break_after(p.base))
+            });
+            clusters.push(Cluster {
+                start: i,
+                end,
+                base: c,
+                width: 0.0,
+                break_before,
+            });
+        }
+        // A ligature counts towards the first cluster it covers.
+        for glyph in self.fonts.place(scale, text) {
+            let i = clusters.partition_point(|c| c.start <= glyph.cluster);
+            clusters[i.saturating_sub(1)].width += glyph.width;
+        }
+        clusters
+    }
+
+    /// Splits `text` into lines at most `max_width` wide, breaking at line
+    /// feeds and where [`Cluster::break_before`] allows, or anywhere
+    /// between clusters for words that do not fit on a line of their own.
+    /// Spaces at the end of lines and at the start of wrapped lines are
+    /// dropped.
+    fn wrap<'t>(&self, scale: Scale, text: &'t str, max_width: f32) -> Vec<&'t str> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let clusters = self.clusters(scale, paragraph);
+            let line = |from: usize, to: usize| match (clusters.get(from), to) {
+                (Some(first), to) if to > from => {
+                    paragraph[first.start..clusters[to - 1].end].trim_end()
+                }
+                _ => "",
+            };
+            let (mut start, mut width, mut last_break) = (0, 0.0, None);
+            let mut i = 0;
+            while i < clusters.len() {
+                let cluster = &clusters[i];
+                if i == start && start > 0 && cluster.base.is_whitespace() {
+                    start += 1;
+                    i += 1;
+                    continue;
+                }
+                if i > start && cluster.break_before {
+                    last_break = Some(i);
+                }
+                if i > start && !cluster.base.is_whitespace() && width + cluster.width > max_width {
+                    let at = last_break.filter(|&b| b > start).unwrap_or(i);
+                    lines.push(line(start, at));
+                    width = clusters[at..i].iter().map(|c| c.width).sum();
+                    start = at;
+                    last_break = None;
+                    continue;
+                }
+                width += cluster.width;
+                i += 1;
+            }
+            lines.push(line(start, clusters.len()));
+        }
+        lines
+    }
+
+    /// Draws `text` into `rect`, wrapped to its width and aligned as
+    /// `style` says. Lines that would extend below `rect` are not drawn.
+    /// Returns the height of the drawn lines.
+    pub fn draw_paragraph_mut<T: DerefMut<Target = [u8]>>(
+        &mut self,
+        img: &mut NV12Image<T>,
+        rect: Region,
+        text: &str,
+        style: &TextStyle,
+    ) -> u32 {
+        let scale = style.scale;
+        let v = self.fonts.v_metrics(scale);
+        let line_height = (v.ascent - v.descent + v.line_gap) * style.line_spacing;
+        let max_width = rect.width as f32;
+        let mut height = 0;
+        for (i, line) in self.wrap(scale, text, max_width).into_iter().enumerate() {
+            let top = i as f32 * line_height;
+            let bottom = (top + line_height).ceil() as u32;
+            if bottom > rect.height {
+                break;
+            }
+            height = bottom;
+            let offset = match style.align {
+                Align::Left => 0.0,
+                Align::Center => (max_width - self.text_width(scale, line)) / 2.0,
+                Align::Right => max_width - self.text_width(scale, line),
+            };
+            let x = rect.x as i32 + offset.round() as i32;
+            let y = rect.y as i32 + top.round() as i32;
+            self.draw_text_mut(img, style.color, x, y, scale, line);
+        }
+        height
+    }
+
+    /// Draws `text` with its top-left corner at `(x, y)`.
+    pub fn draw_text_mut<T: DerefMut<Target = [u8]>>(
+        &mut self,
+        img: &mut NV12Image<T>,
+        color: YUV,
+        x: i32,
+        y: i32,
+        scale: Scale,
+        text: &str,
+    ) {
+        for (key, left, top) in self.layout(scale, text) {
+            let bitmap = &self.cache[&key];
+            let (left, top) = (x as i64 + left, y as i64 + top);
+            blend_region_mut(img, left, top, bitmap.width, bitmap.height, |dx, dy| {
+                let i = (dy * bitmap.width + dx) as usize;
+                (color, bitmap.coverage[i] as u32)
+            });
+        }
+    }
+
+    /// Renders `text` into a coverage mask whose top-left corner is the
+    /// top-left corner of the text, for drawing it later with
+    /// [`Annotation::Mask`](crate::annotate::Annotation::Mask).
+    pub fn render_mask(&mut self, scale: Scale, text: &str) -> CoverageMask {
+        let glyphs = self.layout(scale, text);
+        let (mut width, mut height) = (0, 0);
+        for (key, left, top) in &glyphs {
+            let bitmap = &self.cache[key];
+            width = width.max(left + bitmap.width as i64);
+            height = height.max(top + bitmap.height as i64);
+        }
+        let mut mask = CoverageMask::new(width.max(0) as u32, height.max(0) as u32);
+        for (key, left, top) in glyphs {
+            let bitmap = &self.cache[&key];
+            for gy in 0..bitmap.height as i64 {
+                for gx in 0..bitmap.width as i64 {
+                    let (mx, my) = (left + gx, top + gy);
+                    if mx < 0 || my < 0 {
+                        continue;
+                    }
+                    let i = (my * mask.width as i64 + mx) as usize;
+                    let c = bitmap.coverage[(gy * bitmap.width as i64 + gx) as usize];
+                    mask.coverage[i] = mask.coverage[i].max(c);
+                }
+            }
+        }
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLACK, WHITE};
+
+    #[test]
+    fn glyphs_are_cached() {
+        let data: &[u8] = include_bytes!("../data/fonts/wqy-microhei/WenQuanYiMicroHei.ttf");
+        let mut text = TextRenderer::new(Font::try_from_bytes(data).unwrap());
+        let mut img = NV12Image::from(vec![0; 64 * 32 * 3 / 2], 64, 32);
+        for _ in 0..2 {
+            text.draw_text_mut(&mut img, WHITE, 0, 0, Scale::uniform(24.0), "aa");
+        }
+        assert_eq!(text.cached_glyphs(), 1);
+        let mask = text.render_mask(Scale::uniform(24.0), "aa");
+        assert!(mask.coverage.iter().any(|&c| c > 0));
+        assert!(img.as_bytes()[..64 * 32].iter().any(|&y| y != BLACK.0[0]));
+    }
+
+    #[test]
+    fn paragraphs_wrap_and_align() {
+        let data: &[u8] = include_bytes!("../data/fonts/wqy-microhei/WenQuanYiMicroHei.ttf");
+        let text = TextRenderer::new(Font::try_from_bytes(data).unwrap());
+        let scale = Scale::uniform(24.0);
+        let em = text.text_width(scale, "测");
+        let wrap = |s, chars: f32| text.wrap(scale, s, em * chars);
+        // The full stop stays with the character before it.
+        assert_eq!(wrap("测试。测试", 2.0), ["测", "试。", "测试"]);
+        assert_eq!(wrap("e\u{301}e\u{301}", 0.0), ["e\u{301}", "e\u{301}"]);
+        let word = text.text_width(scale, "ab cd");
+        assert_eq!(text.wrap(scale, "ab cd ef\n", word), ["ab cd", "ef", ""]);
+
+        let mut text = text;
+        let mut img = crate::Nv12Buffer::filled(64, 64, BLACK);
+        let style = TextStyle::new(WHITE, scale).with_align(Align::Right);
+        let height = text.draw_paragraph_mut(&mut img, Region::new(0, 0, 64, 64), "a\nb", &style);
+        assert!(height > 0 && height <= 64);
+        assert!(img.y_row(12)[..32].iter().all(|&y| y == BLACK.0[0]));
+        assert!(img.y_row(12)[32..].iter().any(|&y| y != BLACK.0[0]));
+    }
+
+    #[test]
+    fn shaped_fonts_position_marks() {
+        let data: &[u8] = include_bytes!("../data/fonts/Roboto-Regular.ttf");
+        let fonts = FontStack::from_bytes(data).unwrap();
+        assert!(fonts.is_shaped(0));
+        let text = TextRenderer::with_fonts(fonts.clone());
+        let scale = Scale::uniform(24.0);
+        let (base, marked) = (
+            text.text_width(scale, "e"),
+            text.text_width(scale, "e\u{301}"),
+        );
+        assert!((base - marked).abs() < 0.5, "{} {}", base, marked);
+        assert_eq!(
+            text.wrap(scale, "e\u{301}e\u{301}", 0.0),
+            ["e\u{301}", "e\u{301}"]
+        );
+
+        let mut img = crate::Nv12Buffer::filled(64, 32, BLACK);
+        let style = ParagraphStyle::new(&fonts, TextStyle::new(WHITE, scale));
+        let height = draw_paragraph_mut(&mut img, Region::new(0, 0, 64, 32), "e\u{301}", &style);
+        assert!(height > 0 && height <= 32);
+        assert!(img.as_bytes()[..64 * 32].iter().any(|&y| y != BLACK.0[0]));
+    }
+
+    #[test]
+    fn fallback_fonts_fill_gaps() {
+        let latin: &[u8] = include_bytes!("../data/fonts/Roboto-Regular.ttf");
+        let cjk: &[u8] = include_bytes!("../data/fonts/wqy-microhei/WenQuanYiMicroHei.ttf");
+        let latin = Font::try_from_bytes(latin).unwrap();
+        assert!(!FontStack::new(latin.clone()).covers('测'));
+        let fonts = FontStack::new(latin).with_fallback(Font::try_from_bytes(cjk).unwrap());
+        assert_eq!((fonts.font_for('a'), fonts.font_for('测')), (0, 1));
+        assert!(fonts.covers('试'));
+
+        let mut text = TextRenderer::with_fonts(fonts);
+        let mask = text.render_mask(Scale::uniform(24.0), "a测试");
+        assert_eq!(text.cached_glyphs(), 3);
+        assert!(mask.coverage.iter().any(|&c| c > 0));
+    }
+}
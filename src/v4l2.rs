@@ -0,0 +1,180 @@
+//! Wrapping of dequeued V4L2 capture buffers.
+//!
+//! [`V4l2PixFormat`] mirrors the kernel's `struct v4l2_pix_format`, so it can
+//! be filled from any V4L2 binding after `VIDIOC_G_FMT`.
+
+use alloc::string::String;
+use core::fmt;
+
+use crate::{NV12Image, YUYVImage};
+
+/// Builds a V4L2 fourcc code, like the kernel's `v4l2_fourcc` macro.
+pub const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+pub const V4L2_PIX_FMT_NV12: u32 = fourcc(b"NV12");
+pub const V4L2_PIX_FMT_YUYV: u32 = fourcc(b"YUYV");
+
+/// The single-planar pixel format of a capture queue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct V4l2PixFormat {
+    pub width: u32,
+    pub height: u32,
+    pub pixelformat: u32,
+    pub bytesperline: u32,
+    pub sizeimage: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum V4l2Error {
+    /// The fourcc is not one of the formats this crate can wrap.
+    UnsupportedFormat(u32),
+    /// `bytesperline` is shorter than one row of pixels.
+    StrideTooSmall { bytesperline: u32, min: u32 },
+    /// The buffer cannot hold all planes of the frame.
+    BufferTooSmall { len: usize, needed: usize },
+}
+
+impl fmt::Display for V4l2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            V4l2Error::UnsupportedFormat(code) => {
+                let name = code.to_le_bytes();
+                write!(
+                    f,
+                    "unsupported V4L2 format {:?}",
+                    String::from_utf8_lossy(&name)
+                )
+            }
+            V4l2Error::StrideTooSmall { bytesperline, min } => {
+                write!(f, "bytesperline {} is less than {}", bytesperline, min)
+            }
+            V4l2Error::BufferTooSmall { len, needed } => {
+                write!(f, "buffer of {} bytes is smaller than {}", len, needed)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for V4l2Error {}
+
+/// A capture buffer viewed as the image type matching its pixel format.
+pub enum V4l2Image<'a> {
+    NV12(NV12Image<&'a mut [u8]>),
+    YUYV(YUYVImage<&'a mut [u8]>),
+}
+
+/// Wraps `buf`, the bytes of a dequeued buffer, so it can be drawn on in place
+/// before the buffer is queued again or passed to an encoder.
+///
+/// `buf` should be the `bytesused` part of the mapping. For NV12 the UV plane
+/// is expected right after the Y plane, with the same `bytesperline`, which
+/// must hold a whole U, V pair for the last column of an odd width.
+pub fn wrap_buffer<'a>(
+    buf: &'a mut [u8],
+    format: &V4l2PixFormat,
+) -> Result<V4l2Image<'a>, V4l2Error> {
+    let (width, height, stride) = (format.width, format.height, format.bytesperline);
+    let (min_stride, needed) = match format.pixelformat {
+        V4L2_PIX_FMT_NV12 => (
+            width.div_ceil(2) * 2,
+            stride as usize * (height as usize + height.div_ceil(2) as usize),
+        ),
+        V4L2_PIX_FMT_YUYV => (width.div_ceil(2) * 4, stride as usize * height as usize),
+        code => return Err(V4l2Error::UnsupportedFormat(code)),
+    };
+    if stride < min_stride {
+        return Err(V4l2Error::StrideTooSmall {
+            bytesperline: stride,
+            min: min_stride,
+        });
+    }
+    if buf.len() < needed {
+        return Err(V4l2Error::BufferTooSmall {
+            len: buf.len(),
+            needed,
+        });
+    }
+    let buf = &mut buf[..needed];
+    Ok(match format.pixelformat {
+        V4L2_PIX_FMT_NV12 => {
+            V4l2Image::NV12(NV12Image::from_strided(buf, width, height, stride, stride))
+        }
+        _ => V4l2Image::YUYV(YUYVImage::from_strided(buf, width, height, stride)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(pixelformat: u32, bytesperline: u32) -> V4l2PixFormat {
+        V4l2PixFormat {
+            width: 4,
+            height: 2,
+            pixelformat,
+            bytesperline,
+            sizeimage: 0,
+        }
+    }
+
+    #[test]
+    fn wraps_supported_formats() {
+        let mut buf = vec![0u8; 64];
+        assert!(matches!(
+            wrap_buffer(&mut buf, &format(V4L2_PIX_FMT_NV12, 8)),
+            Ok(V4l2Image::NV12(_))
+        ));
+        assert!(matches!(
+            wrap_buffer(&mut buf, &format(V4L2_PIX_FMT_YUYV, 8)),
+            Ok(V4l2Image::YUYV(_))
+        ));
+    }
+
+    #[test]
+    fn validates_geometry() {
+        let mut buf = vec![0u8; 16];
+        assert_eq!(
+            wrap_buffer(&mut buf, &format(V4L2_PIX_FMT_YUYV, 6)).err(),
+            Some(V4l2Error::StrideTooSmall {
+                bytesperline: 6,
+                min: 8
+            })
+        );
+        assert_eq!(
+            wrap_buffer(&mut buf, &format(V4L2_PIX_FMT_NV12, 8)).err(),
+            Some(V4l2Error::BufferTooSmall {
+                len: 16,
+                needed: 24
+            })
+        );
+        assert_eq!(
+            wrap_buffer(&mut buf, &format(fourcc(b"MJPG"), 8)).err(),
+            Some(V4l2Error::UnsupportedFormat(fourcc(b"MJPG")))
+        );
+    }
+
+    #[test]
+    fn odd_widths_keep_the_last_chroma_pair() {
+        let mut odd = V4l2PixFormat {
+            width: 3,
+            ..format(V4L2_PIX_FMT_NV12, 3)
+        };
+        let mut buf = vec![0u8; 12];
+        assert_eq!(
+            wrap_buffer(&mut buf, &odd).err(),
+            Some(V4l2Error::StrideTooSmall {
+                bytesperline: 3,
+                min: 4
+            })
+        );
+        odd.bytesperline = 4;
+        match wrap_buffer(&mut buf, &odd) {
+            Ok(V4l2Image::NV12(mut img)) => img.set_pixel(2, 0, crate::RED),
+            _ => panic!("expected an NV12 image"),
+        }
+        assert_eq!(buf[8 + 2..], [crate::RED.0[1], crate::RED.0[2]]);
+    }
+}
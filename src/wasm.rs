@@ -0,0 +1,40 @@
+//! Conversions between NV12 images and JS typed arrays, for annotating
+//! frames in the browser.
+//!
+//! A `Uint8Array` lives in JS memory, so these copy. Frames already copied
+//! into wasm memory, e.g. with WebCodecs' `VideoFrame.copyTo()` on a view of
+//! it, can be borrowed with [`NV12Image::from_bytes_mut`] instead.
+
+use std::ops::Deref;
+
+use js_sys::Uint8Array;
+
+use crate::{NV12Image, PixelFormat};
+
+impl NV12Image<Vec<u8>> {
+    /// Copies a tightly packed `width`x`height` frame out of `array`, or
+    /// returns `None` if the array is too short.
+    pub fn from_uint8_array(array: &Uint8Array, width: u32, height: u32) -> Option<Self> {
+        let len = PixelFormat::NV12.frame_size(width, height);
+        if (array.length() as usize) < len {
+            return None;
+        }
+        let data = array.subarray(0, len as u32).to_vec();
+        Some(Self::from(data, width, height))
+    }
+}
+
+impl<T: Deref<Target = [u8]>> NV12Image<T> {
+    /// Copies the image into a new, tightly packed `Uint8Array`.
+    pub fn to_uint8_array(&self) -> Uint8Array {
+        let uv_rows = self.height.div_ceil(2);
+        let mut data = Vec::with_capacity((self.height + uv_rows) as usize * self.width as usize);
+        for y in 0..self.height {
+            data.extend_from_slice(self.y_row(y));
+        }
+        for y in 0..uv_rows {
+            data.extend_from_slice(self.uv_row(y));
+        }
+        Uint8Array::from(&data[..])
+    }
+}
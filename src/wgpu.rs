@@ -0,0 +1,141 @@
+//! Uploading NV12 frames to `wgpu` textures.
+//!
+//! The Y plane becomes an `R8Unorm` texture and the UV plane an `Rg8Unorm`
+//! texture of half the size, rounded up, so shaders can sample both planes with
+//! hardware filtering. [`NV12_TO_RGB_WGSL`] converts them back to RGB.
+
+use std::num::NonZeroU32;
+use std::ops::Deref;
+
+use wgpu::{
+    Device, Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+
+use crate::NV12Image;
+
+/// WGSL helper converting the two plane textures to RGB, with the same
+/// full range BT.601 matrix as [`Pixel::to_rgb`](image::Pixel::to_rgb).
+/// Bind the Y texture at binding 0, the UV texture at binding 1 and a
+/// filtering sampler at binding 2 of group 0, then call `nv12_to_rgb` with
+/// normalized texture coordinates.
+pub const NV12_TO_RGB_WGSL: &str = r#"
+@group(0) @binding(0) var nv12_y: texture_2d<f32>;
+@group(0) @binding(1) var nv12_uv: texture_2d<f32>;
+@group(0) @binding(2) var nv12_sampler: sampler;
+
+fn nv12_to_rgb(coord: vec2<f32>) -> vec3<f32> {
+    let y = textureSample(nv12_y, nv12_sampler, coord).r;
+    let c = textureSample(nv12_uv, nv12_sampler, coord).rg - vec2<f32>(0.5, 0.5);
+    let rgb = vec3<f32>(
+        y + 1.40 * c.y,
+        y - 0.34 * c.x - 0.71 * c.y,
+        y + 1.77 * c.x,
+    );
+    return clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+"#;
+
+/// Rounds `bytes_per_row` up to the alignment `wgpu` requires for copies
+/// between buffers and textures.
+pub fn padded_bytes_per_row(bytes_per_row: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    bytes_per_row.div_ceil(align) * align
+}
+
+/// Size of the UV texture of `width`x`height` frames: one texel per 2x2
+/// block, including the partial blocks of odd dimensions.
+fn uv_extent(width: u32, height: u32) -> (u32, u32) {
+    (width.div_ceil(2), height.div_ceil(2))
+}
+
+/// The two plane textures of one NV12 frame.
+pub struct Nv12Textures {
+    pub y: Texture,
+    pub uv: Texture,
+    width: u32,
+    height: u32,
+}
+
+impl Nv12Textures {
+    /// Creates textures for `width`x`height` frames, usable as shader
+    /// bindings and as copy destinations.
+    pub fn new(device: &Device, width: u32, height: u32) -> Self {
+        let plane = |label, format, width, height| {
+            device.create_texture(&TextureDescriptor {
+                label: Some(label),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            })
+        };
+        let (uv_width, uv_height) = uv_extent(width, height);
+        Self {
+            y: plane("nv12 y", TextureFormat::R8Unorm, width, height),
+            uv: plane("nv12 uv", TextureFormat::Rg8Unorm, uv_width, uv_height),
+            width,
+            height,
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Uploads both planes of `img`, which must have the size the textures
+    /// were created with. Row padding of the image is skipped by the copy.
+    pub fn upload<T: Deref<Target = [u8]>>(&self, queue: &Queue, img: &NV12Image<T>) {
+        assert_eq!(
+            (img.width, img.height),
+            (self.width, self.height),
+            "frame size does not match the textures"
+        );
+        let write = |texture, data: &[u8], stride, width, height| {
+            queue.write_texture(
+                ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                data,
+                ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(stride),
+                    rows_per_image: NonZeroU32::new(height),
+                },
+                Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            )
+        };
+        let (uv_width, uv_height) = uv_extent(self.width, self.height);
+        write(&self.y, &img.data, img.y_stride, self.width, self.height);
+        write(&self.uv, img.uv_plane(), img.uv_stride, uv_width, uv_height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_geometry() {
+        assert_eq!(padded_bytes_per_row(0), 0);
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(256), 256);
+        assert_eq!(padded_bytes_per_row(1920 * 4), 7680);
+        assert_eq!(padded_bytes_per_row(1366), 1536);
+        assert_eq!(uv_extent(1920, 1080), (960, 540));
+        assert_eq!(uv_extent(3, 5), (2, 3));
+    }
+}
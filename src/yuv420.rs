@@ -0,0 +1,293 @@
+use core::ops::{Deref, DerefMut};
+
+#[cfg(feature = "std")]
+use image::{GenericImage, GenericImageView};
+
+use crate::{PixelFormat, YuvError, YUV};
+
+/// One plane of a [`YUV420Image`]. Sample `(x, y)` of the plane is at byte
+/// `y * row_stride + x * pixel_stride` of `data`.
+pub struct Plane<T> {
+    pub data: T,
+    pub row_stride: u32,
+    pub pixel_stride: u32,
+}
+
+impl<T: Deref<Target = [u8]>> Plane<T> {
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.row_stride + x * self.pixel_stride) as usize
+    }
+
+    /// Bytes of `data` a `width`x`height` plane reaches.
+    fn needed(&self, width: u32, height: u32) -> usize {
+        match (width, height) {
+            (0, _) | (_, 0) => 0,
+            _ => self.index(width - 1, height - 1) + 1,
+        }
+    }
+
+    fn check_len(&self, name: &str, width: u32, height: u32) {
+        let needed = self.needed(width, height);
+        if self.data.len() < needed {
+            panic!(
+                "{} plane of {} bytes is too small for {:?}, needs {}",
+                name,
+                self.data.len(),
+                (width, height),
+                needed
+            )
+        }
+    }
+
+    fn try_check_len(&self, width: u32, height: u32) -> Result<(), YuvError> {
+        let needed = self.needed(width, height);
+        if self.data.len() < needed {
+            return Err(YuvError::BufferTooSmall {
+                len: self.data.len(),
+                needed,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A 4:2:0 image with three independently laid out planes, as exposed by
+/// Android's `YUV_420_888` `Image` planes.
+///
+/// The chroma planes may have a pixel stride of 1 (separate U and V planes)
+/// or 2 (U and V interleaved in one buffer, in which case the two planes
+/// are overlapping views of it). [`layout`](Self::layout) reports which of
+/// the common layouts the planes form. Pixels are addressed in 2x2 blocks
+/// like [`NV12Image`](crate::NV12Image).
+pub struct YUV420Image<T: Deref<Target = [u8]>> {
+    y: Plane<T>,
+    u: Plane<T>,
+    v: Plane<T>,
+    width: u32,
+    height: u32,
+}
+
+impl<T: Deref<Target = [u8]>> YUV420Image<T> {
+    fn check_bounds(&self, x: u32, y: u32) {
+        if x >= self.width || y >= self.height {
+            panic!(
+                "Image index {:?} out of bounds {:?}",
+                (x, y),
+                (self.width, self.height)
+            )
+        }
+    }
+
+    /// Wraps the three planes of a `width`x`height` image.
+    ///
+    /// Panics if a plane is too short for its strides.
+    pub fn from_planes(width: u32, height: u32, y: Plane<T>, u: Plane<T>, v: Plane<T>) -> Self {
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        y.check_len("Y", width, height);
+        u.check_len("U", chroma_width, chroma_height);
+        v.check_len("V", chroma_width, chroma_height);
+        Self {
+            y,
+            u,
+            v,
+            width,
+            height,
+        }
+    }
+
+    /// Like [`from_planes`](Self::from_planes), but returns an error instead
+    /// of panicking if a plane is too short.
+    pub fn try_from_planes(
+        width: u32,
+        height: u32,
+        y: Plane<T>,
+        u: Plane<T>,
+        v: Plane<T>,
+    ) -> Result<Self, YuvError> {
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        y.try_check_len(width, height)?;
+        u.try_check_len(chroma_width, chroma_height)?;
+        v.try_check_len(chroma_width, chroma_height)?;
+        Ok(Self {
+            y,
+            u,
+            v,
+            width,
+            height,
+        })
+    }
+
+    /// Detects whether the planes are laid out as I420, NV12 or NV21.
+    /// Returns `None` for any other combination of strides.
+    pub fn layout(&self) -> Option<PixelFormat> {
+        let (u, v) = (&self.u, &self.v);
+        if self.y.pixel_stride != 1 {
+            return None;
+        }
+        if u.pixel_stride == 1 && v.pixel_stride == 1 {
+            return Some(PixelFormat::I420);
+        }
+        if u.pixel_stride != 2 || v.pixel_stride != 2 || u.row_stride != v.row_stride {
+            return None;
+        }
+        let (u_start, v_start) = (u.data.as_ptr() as usize, v.data.as_ptr() as usize);
+        if v_start == u_start + 1 {
+            Some(PixelFormat::NV12)
+        } else if u_start == v_start + 1 {
+            Some(PixelFormat::NV21)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_planes(self) -> (Plane<T>, Plane<T>, Plane<T>) {
+        (self.y, self.u, self.v)
+    }
+
+    /// The pixel of the 2x2 block containing `(x, y)`, read from its top-left
+    /// pixel.
+    pub fn pixel(&self, x: u32, y: u32) -> YUV {
+        self.check_bounds(x, y);
+        let (x, y) = (x / 2, y / 2);
+        YUV([
+            self.y.data[self.y.index(x * 2, y * 2)],
+            self.u.data[self.u.index(x, y)],
+            self.v.data[self.v.index(x, y)],
+        ])
+    }
+}
+
+impl<T: DerefMut<Target = [u8]>> YUV420Image<T> {
+    /// Sets the whole 2x2 block containing `(x, y)` to `pixel`.
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: YUV) {
+        self.check_bounds(x, y);
+        let (x, y) = (x / 2, y / 2);
+        for py in y * 2..(y * 2 + 2).min(self.height) {
+            for px in x * 2..(x * 2 + 2).min(self.width) {
+                let i = self.y.index(px, py);
+                self.y.data[i] = pixel.0[0];
+            }
+        }
+        let i = self.u.index(x, y);
+        self.u.data[i] = pixel.0[1];
+        let i = self.v.index(x, y);
+        self.v.data[i] = pixel.0[2];
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Deref<Target = [u8]>> GenericImageView for YUV420Image<T> {
+    type Pixel = YUV;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        (0, 0, self.width, self.height)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        self.pixel(x, y)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: DerefMut<Target = [u8]>> GenericImage for YUV420Image<T> {
+    /// Always panics: the samples of a pixel are spread over three planes,
+    /// so there is no `YUV` to borrow. None of the `imageops` functions need
+    /// it.
+    fn get_pixel_mut(&mut self, _: u32, _: u32) -> &mut Self::Pixel {
+        panic!("YUV420Image has no addressable pixels, use put_pixel instead")
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.set_pixel(x, y, pixel)
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.put_pixel(x, y, pixel)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn plane<T>(data: T, row_stride: u32, pixel_stride: u32) -> Plane<T> {
+        Plane {
+            data,
+            row_stride,
+            pixel_stride,
+        }
+    }
+
+    #[test]
+    fn detects_layouts() {
+        let y = [0u8; 16];
+        let uv = [0u8; 8];
+        let nv12 = YUV420Image::from_planes(
+            4,
+            4,
+            plane(&y[..], 4, 1),
+            plane(&uv[..7], 4, 2),
+            plane(&uv[1..], 4, 2),
+        );
+        assert_eq!(nv12.layout(), Some(PixelFormat::NV12));
+        let nv21 = YUV420Image::from_planes(
+            4,
+            4,
+            plane(&y[..], 4, 1),
+            plane(&uv[1..], 4, 2),
+            plane(&uv[..7], 4, 2),
+        );
+        assert_eq!(nv21.layout(), Some(PixelFormat::NV21));
+        let i420 = YUV420Image::from_planes(
+            4,
+            4,
+            plane(&y[..], 4, 1),
+            plane(&uv[..4], 2, 1),
+            plane(&uv[4..], 2, 1),
+        );
+        assert_eq!(i420.layout(), Some(PixelFormat::I420));
+    }
+
+    #[test]
+    fn try_from_planes_checks_lengths() {
+        let y = [0u8; 16];
+        let uv = [0u8; 8];
+        assert!(matches!(
+            YUV420Image::try_from_planes(
+                4,
+                4,
+                plane(&y[..], 4, 1),
+                plane(&uv[..4], 2, 1),
+                plane(&uv[5..], 2, 1),
+            ),
+            Err(YuvError::BufferTooSmall { len: 3, needed: 4 })
+        ));
+        assert!(YUV420Image::try_from_planes(
+            4,
+            4,
+            plane(&y[..], 4, 1),
+            plane(&uv[..4], 2, 1),
+            plane(&uv[4..], 2, 1),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn put_pixel_on_separate_planes() {
+        let mut img = YUV420Image::from_planes(
+            2,
+            2,
+            plane(vec![0u8; 2 * 2], 2, 1),
+            plane(vec![0u8; 1], 1, 1),
+            plane(vec![0u8; 1], 1, 1),
+        );
+        img.put_pixel(1, 1, YUV([1, 2, 3]));
+        assert_eq!(img.get_pixel(0, 0).0, [1, 2, 3]);
+        let (y, u, v) = img.into_planes();
+        assert_eq!((y.data, u.data, v.data), (vec![1; 4], vec![2], vec![3]));
+    }
+}
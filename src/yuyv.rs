@@ -0,0 +1,172 @@
+use core::ops::{Deref, DerefMut};
+
+#[cfg(feature = "std")]
+use image::{GenericImage, GenericImageView};
+
+use crate::{YuvError, YUV};
+
+/// A packed 4:2:2 image: every two horizontally adjacent pixels are stored
+/// as `Y0 U Y1 V`.
+///
+/// Like [`NV12Image`](crate::NV12Image), pixels are addressed in blocks that
+/// share one chroma sample: reads use the even pixel of the pair and writes
+/// set both.
+pub struct YUYVImage<T: Deref<Target = [u8]>> {
+    data: T,
+    width: u32,
+    height: u32,
+    stride: u32,
+}
+
+impl<T: Deref<Target = [u8]>> YUYVImage<T> {
+    fn check_bounds(&self, x: u32, y: u32) {
+        if x >= self.width || y >= self.height {
+            panic!(
+                "Image index {:?} out of bounds {:?}",
+                (x, y),
+                (self.width, self.height)
+            )
+        }
+    }
+
+    fn pair_index(&self, x: u32, y: u32) -> usize {
+        (y * self.stride + (x - x % 2) * 2) as usize
+    }
+
+    /// Wraps a frame without row padding. An odd width still stores the
+    /// whole last pair.
+    pub fn from(data: T, width: u32, height: u32) -> Self {
+        Self::from_strided(data, width, height, width.div_ceil(2) * 4)
+    }
+
+    /// Wraps a frame whose rows are `stride` bytes long.
+    pub fn from_strided(data: T, width: u32, height: u32, stride: u32) -> Self {
+        Self {
+            data,
+            width,
+            height,
+            stride,
+        }
+    }
+
+    /// Like [`from`](Self::from), but checks the buffer length.
+    pub fn try_from(data: T, width: u32, height: u32) -> Result<Self, YuvError> {
+        Self::try_from_strided(data, width, height, width.div_ceil(2) * 4)
+    }
+
+    /// Like [`from_strided`](Self::from_strided), but checks that `stride`
+    /// holds a row of pairs and `data` all rows.
+    pub fn try_from_strided(
+        data: T,
+        width: u32,
+        height: u32,
+        stride: u32,
+    ) -> Result<Self, YuvError> {
+        let min = width.div_ceil(2) * 4;
+        if stride < min {
+            return Err(YuvError::StrideTooSmall { stride, min });
+        }
+        let needed = stride as usize * height as usize;
+        if data.len() < needed {
+            return Err(YuvError::BufferTooSmall {
+                len: data.len(),
+                needed,
+            });
+        }
+        Ok(Self::from_strided(data, width, height, stride))
+    }
+
+    pub fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    pub fn take_data(self) -> T {
+        self.data
+    }
+
+    pub fn ref_data(&self) -> &T {
+        &self.data
+    }
+
+    /// The pixel at `(x, y)`, with the chroma of its pair.
+    pub fn pixel(&self, x: u32, y: u32) -> YUV {
+        self.check_bounds(x, y);
+        let i = self.pair_index(x, y);
+        YUV([self.data[i], self.data[i + 1], self.data[i + 3]])
+    }
+}
+
+impl<T: DerefMut<Target = [u8]>> YUYVImage<T> {
+    /// Sets both pixels of the pair containing `(x, y)` to `pixel`.
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: YUV) {
+        self.check_bounds(x, y);
+        let i = self.pair_index(x, y);
+        self.data[i..i + 4].copy_from_slice(&[pixel.0[0], pixel.0[1], pixel.0[0], pixel.0[2]]);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Deref<Target = [u8]>> GenericImageView for YUYVImage<T> {
+    type Pixel = YUV;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        (0, 0, self.width, self.height)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Self::Pixel {
+        self.pixel(x, y)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: DerefMut<Target = [u8]>> GenericImage for YUYVImage<T> {
+    /// Always panics: two pixels share their chroma samples in a YUYV
+    /// macropixel, so there is no `YUV` to borrow. None of the `imageops`
+    /// functions need it.
+    fn get_pixel_mut(&mut self, _: u32, _: u32) -> &mut Self::Pixel {
+        panic!("YUYVImage has no addressable pixels, use put_pixel instead")
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.set_pixel(x, y, pixel)
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, pixel: Self::Pixel) {
+        self.put_pixel(x, y, pixel)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn put_pixel_writes_pair() {
+        let mut img = YUYVImage::from(vec![0u8; 4 * 2 * 2], 4, 2);
+        img.put_pixel(3, 1, YUV([1, 2, 3]));
+        assert_eq!(img.get_pixel(2, 1).0, [1, 2, 3]);
+        assert_eq!(img.take_data()[8..], [0, 0, 0, 0, 1, 2, 1, 3]);
+    }
+
+    #[test]
+    fn try_constructors_validate() {
+        assert!(matches!(
+            YUYVImage::try_from_strided(vec![0u8; 16], 3, 2, 6),
+            Err(YuvError::StrideTooSmall { stride: 6, min: 8 })
+        ));
+        assert!(matches!(
+            YUYVImage::try_from(vec![0u8; 12], 3, 2),
+            Err(YuvError::BufferTooSmall {
+                len: 12,
+                needed: 16
+            })
+        ));
+        let mut img = YUYVImage::try_from(vec![0u8; 16], 3, 2).unwrap();
+        img.set_pixel(2, 1, YUV([1, 2, 3]));
+        assert_eq!(img.take_data()[12..], [1, 2, 1, 3]);
+    }
+}